@@ -0,0 +1,222 @@
+// Flocking for the `--gpu` mode. Each frame boids are binned into a uniform grid of
+// vision-range cells, steered against the boids in the 3x3 cells around them, then
+// splatted into the storage texture the main world shows as a sprite.
+
+struct Params {
+    count: u32,
+    cols: u32,
+    rows: u32,
+    seed: u32,
+    bounds: vec2<f32>,
+    cell: f32,
+    dt: f32,
+    vis_range: f32,
+    prot_range: f32,
+    fov: f32,
+    center_factor: f32,
+    matching_factor: f32,
+    avoid_factor: f32,
+    turn_factor: f32,
+    boundary_size: f32,
+    min_speed: f32,
+    max_speed: f32,
+}
+
+struct Boid {
+    position: vec2<f32>,
+    velocity: vec2<f32>,
+}
+
+@group(0) @binding(0) var<uniform> params: Params;
+@group(0) @binding(1) var<storage, read> boids_in: array<Boid>;
+@group(0) @binding(2) var<storage, read_write> boids_out: array<Boid>;
+// Boids per cell while counting, then each cell's next free slot while scattering
+@group(0) @binding(3) var<storage, read_write> cell_counts: array<atomic<u32>>;
+// Where each cell's boids start in `sorted`, with the total at the end
+@group(0) @binding(4) var<storage, read_write> cell_starts: array<u32>;
+@group(0) @binding(5) var<storage, read_write> sorted: array<u32>;
+@group(0) @binding(6) var image: texture_storage_2d<rgba8unorm, write>;
+
+const WORKGROUP: u32 = 64u;
+
+fn hash(value: u32) -> u32 {
+    var x = value;
+    x ^= x >> 16u;
+    x *= 0x7feb352du;
+    x ^= x >> 15u;
+    x *= 0x846ca68bu;
+    x ^= x >> 16u;
+    return x;
+}
+
+fn random(seed: u32) -> f32 {
+    return f32(hash(seed)) / 4294967295.0;
+}
+
+fn cell_coords(position: vec2<f32>) -> vec2<i32> {
+    let cell = vec2<i32>(floor((position + params.bounds / 2.0) / params.cell));
+    return clamp(cell, vec2<i32>(0), vec2<i32>(i32(params.cols) - 1, i32(params.rows) - 1));
+}
+
+fn cell_index(cell: vec2<i32>) -> u32 {
+    return u32(cell.y) * params.cols + u32(cell.x);
+}
+
+// Scatter boids over the world with random headings at minimum speed
+@compute @workgroup_size(64)
+fn init(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if i >= params.count {
+        return;
+    }
+
+    let seed = params.seed ^ (i * 3u);
+    let position = (vec2<f32>(random(seed), random(seed + 1u)) - 0.5) * params.bounds;
+    let angle = random(seed + 2u) * 6.2831853;
+    let boid = Boid(position, vec2<f32>(cos(angle), sin(angle)) * params.min_speed);
+
+    boids_out[i] = boid;
+}
+
+@compute @workgroup_size(64)
+fn clear_cells(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x < params.cols * params.rows {
+        atomicStore(&cell_counts[id.x], 0u);
+    }
+}
+
+@compute @workgroup_size(64)
+fn count_cells(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x < params.count {
+        atomicAdd(&cell_counts[cell_index(cell_coords(boids_in[id.x].position))], 1u);
+    }
+}
+
+// A handful of cells at most, so a single invocation scans them all
+@compute @workgroup_size(1)
+fn prefix_sum() {
+    var start = 0u;
+    for (var i = 0u; i < params.cols * params.rows; i++) {
+        cell_starts[i] = start;
+        start += atomicLoad(&cell_counts[i]);
+        atomicStore(&cell_counts[i], cell_starts[i]);
+    }
+    cell_starts[params.cols * params.rows] = start;
+}
+
+@compute @workgroup_size(64)
+fn scatter(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x < params.count {
+        let slot = atomicAdd(&cell_counts[cell_index(cell_coords(boids_in[id.x].position))], 1u);
+        sorted[slot] = id.x;
+    }
+}
+
+// Cohesion, alignment and separation against perceived neighbors, as flocking_rules does
+@compute @workgroup_size(64)
+fn simulate(@builtin(global_invocation_id) id: vec3<u32>) {
+    let i = id.x;
+    if i >= params.count {
+        return;
+    }
+
+    let boid = boids_in[i];
+    let heading = normalize(boid.velocity);
+    let center = cell_coords(boid.position);
+
+    var vec_away = vec2<f32>(0.0);
+    var avg_position = vec2<f32>(0.0);
+    var avg_velocity = vec2<f32>(0.0);
+    var neighbors = 0u;
+    var close = 0u;
+
+    for (var dy = -1; dy <= 1; dy++) {
+        for (var dx = -1; dx <= 1; dx++) {
+            let cell = center + vec2<i32>(dx, dy);
+            if any(cell < vec2<i32>(0)) || cell.x >= i32(params.cols) || cell.y >= i32(params.rows) {
+                continue;
+            }
+
+            let c = cell_index(cell);
+            for (var s = cell_starts[c]; s < cell_starts[c + 1u]; s++) {
+                let j = sorted[s];
+                if j == i {
+                    continue;
+                }
+
+                let other = boids_in[j];
+                let offset = other.position - boid.position;
+                let dist_sq = dot(offset, offset);
+
+                // Out of range or behind
+                if dist_sq > params.vis_range * params.vis_range {
+                    continue;
+                }
+                if dist_sq > 0.0 && acos(clamp(dot(heading, normalize(offset)), -1.0, 1.0)) > params.fov {
+                    continue;
+                }
+
+                if dist_sq < params.prot_range * params.prot_range {
+                    vec_away -= offset;
+                    close += 1u;
+                } else {
+                    avg_position += offset;
+                    avg_velocity += other.velocity;
+                    neighbors += 1u;
+                }
+            }
+        }
+    }
+
+    var dv = vec2<f32>(0.0);
+    if neighbors > 0u {
+        dv += avg_position / f32(neighbors) * params.center_factor;
+        dv += avg_velocity / f32(neighbors) * params.matching_factor;
+    }
+    if close > 0u {
+        dv += vec_away / f32(close) * params.avoid_factor;
+    }
+
+    var velocity = boid.velocity + dv * params.dt;
+
+    // Steer back into the visible region
+    let edge = (params.bounds - params.boundary_size) / 2.0;
+    let turn = params.turn_factor * params.dt;
+    velocity += select(vec2<f32>(0.0), vec2<f32>(turn), boid.position < -edge);
+    velocity -= select(vec2<f32>(0.0), vec2<f32>(turn), boid.position > edge);
+
+    let speed = length(velocity);
+    if speed > 0.0 {
+        velocity *= clamp(speed, params.min_speed, params.max_speed) / speed;
+    }
+
+    boids_out[i] = Boid(boid.position + velocity * params.dt, velocity);
+}
+
+@compute @workgroup_size(8, 8)
+fn clear_image(@builtin(global_invocation_id) id: vec3<u32>) {
+    textureStore(image, vec2<i32>(id.xy), vec4<f32>(0.0, 0.0, 0.0, 1.0));
+}
+
+// Slow boids blue, fast ones orange
+@compute @workgroup_size(64)
+fn draw(@builtin(global_invocation_id) id: vec3<u32>) {
+    if id.x >= params.count {
+        return;
+    }
+
+    let boid = boids_out[id.x];
+    let size = vec2<i32>(textureDimensions(image));
+    let pixel = vec2<i32>(floor(vec2<f32>(boid.position.x, -boid.position.y) + params.bounds / 2.0));
+    if any(pixel < vec2<i32>(0)) || any(pixel >= size) {
+        return;
+    }
+
+    let fast = clamp((length(boid.velocity) - params.min_speed) / max(params.max_speed - params.min_speed, 1.0), 0.0, 1.0);
+    let color = mix(vec4<f32>(0.3, 0.6, 1.0, 1.0), vec4<f32>(1.0, 0.6, 0.2, 1.0), fast);
+
+    textureStore(image, pixel, color);
+    textureStore(image, min(pixel + vec2<i32>(1, 0), size - 1), color);
+    textureStore(image, min(pixel + vec2<i32>(0, 1), size - 1), color);
+    textureStore(image, min(pixel + vec2<i32>(1, 1), size - 1), color);
+}
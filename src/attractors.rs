@@ -0,0 +1,127 @@
+use bevy::{ecs::system::SystemParam, math::Vec3Swizzles, prelude::*, window::PrimaryWindow};
+
+use crate::{input::{Action, Actions}, CursorPosition};
+
+// Placed by middle click, and at each networked client's cursor
+pub const PLACED_STRENGTH: f32 = 300.;
+pub const PLACED_RADIUS: f32 = 200.;
+// Middle clicking this close to a placed one removes it
+const PICK_RADIUS: f32 = 15.;
+// A middle press that moves further than this many pixels before release is a camera pan instead
+const CLICK_SLOP: f32 = 4.;
+
+// Pulls boids within `radius` towards it, `strength` in units/s² at the center fading to zero at the edge
+#[derive(Component, Clone, Copy, Debug, Reflect)]
+#[reflect(Component)]
+pub struct Attractor {
+    pub strength: f32,
+    pub radius: f32,
+}
+
+// Pushes boids within `radius` away, with the same falloff as an Attractor
+#[derive(Component, Clone, Copy, Debug, Reflect)]
+#[reflect(Component)]
+pub struct Repeller {
+    pub strength: f32,
+    pub radius: f32,
+}
+
+// Linear falloff towards `center`, zero outside `radius`
+fn pull(center: Vec2, position: Vec2, strength: f32, radius: f32) -> Vec2 {
+    let to_center = center - position;
+    let dist = to_center.length();
+
+    if dist >= radius {
+        return Vec2::ZERO;
+    }
+
+    to_center.normalize_or_zero() * strength * (1.0 - dist / radius)
+}
+
+// Read access to attractors and repellers for the flocking pass
+#[derive(SystemParam)]
+pub struct Attractors<'w, 's> {
+    attractors: Query<'w, 's, (&'static Attractor, &'static Transform)>,
+    repellers: Query<'w, 's, (&'static Repeller, &'static Transform)>,
+}
+
+impl Attractors<'_, '_> {
+    // Summed influence of every attractor and repeller on a boid at `position`
+    pub fn steering(&self, position: Vec2) -> Vec2 {
+        let attract = self.attractors.iter().map(|(attractor, transform)| {
+            pull(transform.translation.xy(), position, attractor.strength, attractor.radius)
+        });
+        let repel = self.repellers.iter().map(|(repeller, transform)| {
+            -pull(transform.translation.xy(), position, repeller.strength, repeller.radius)
+        });
+
+        attract.chain(repel).sum()
+    }
+}
+
+// Middle click places an attractor, shift + middle click a repeller, middle clicking one removes it
+pub struct AttractorPlugin;
+
+impl Plugin for AttractorPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Attractor>()
+            .register_type::<Repeller>()
+            .add_systems(Update, (place_attractors, draw_attractors));
+    }
+}
+
+type Placed = Or<(With<Attractor>, With<Repeller>)>;
+
+fn place_attractors(
+    mut commands: Commands,
+    actions: Actions,
+    cursor: Res<CursorPosition>,
+    placed: Query<(Entity, &Transform), Placed>,
+    window: Query<&Window, With<PrimaryWindow>>,
+    mut pressed_at: Local<Option<Vec2>>,
+) {
+    let on_window = window.get_single().ok().and_then(Window::cursor_position);
+    if actions.just_pressed(Action::Place) {
+        *pressed_at = on_window;
+    }
+    if !actions.just_released(Action::Place) {
+        return;
+    }
+
+    let clicked = pressed_at.take().zip(on_window).is_some_and(|(from, to)| from.distance(to) <= CLICK_SLOP);
+    let Some(position) = cursor.0.filter(|_| clicked) else {
+        return;
+    };
+
+    if let Some((entity, _)) = placed.iter()
+        .find(|(_, transform)| transform.translation.xy().distance(position) < PICK_RADIUS)
+    {
+        commands.entity(entity).despawn();
+        return;
+    }
+
+    let transform = TransformBundle::from_transform(Transform::from_translation(position.extend(0.0)));
+
+    if actions.pressed(Action::Alternate) {
+        commands.spawn((Repeller { strength: PLACED_STRENGTH, radius: PLACED_RADIUS }, transform));
+    } else {
+        commands.spawn((Attractor { strength: PLACED_STRENGTH, radius: PLACED_RADIUS }, transform));
+    }
+}
+
+fn draw_attractors(
+    attractors: Query<(&Attractor, &Transform)>,
+    repellers: Query<(&Repeller, &Transform)>,
+    mut gizmos: Gizmos,
+) {
+    for (attractor, transform) in attractors.iter() {
+        let center = transform.translation.xy();
+        gizmos.circle_2d(center, PICK_RADIUS, Color::LIME_GREEN);
+        gizmos.circle_2d(center, attractor.radius, Color::LIME_GREEN.with_a(0.2));
+    }
+    for (repeller, transform) in repellers.iter() {
+        let center = transform.translation.xy();
+        gizmos.circle_2d(center, PICK_RADIUS, Color::PURPLE);
+        gizmos.circle_2d(center, repeller.radius, Color::PURPLE.with_a(0.2));
+    }
+}
@@ -0,0 +1,329 @@
+use std::{
+    f32::consts::{PI, TAU},
+    sync::{
+        atomic::{AtomicU32, Ordering},
+        Arc,
+    },
+    time::Duration,
+};
+use rand::prelude::*;
+use bevy::{
+    audio::{AddAudioSource, AudioSinkPlayback, Source, SpatialScale, Volume},
+    math::Vec3Swizzles,
+    prelude::*,
+    utils::HashMap,
+};
+use bevy_spatial::SpatialAccess;
+
+use crate::{
+    camera::OverviewCamera,
+    index::BoidIndex,
+    input::{Action, Actions},
+    settings::BoidSettings,
+    simulation::SimulationState,
+    MainCamera,
+    SpatialEntity,
+    Velocity,
+};
+
+const SAMPLE_RATE: u32 = 44_100;
+// Seconds between reading the flock's metrics
+const SAMPLE_INTERVAL: f32 = 0.1;
+// Neighbors in vision range at which a boid counts as part of a cluster, and at which the flock sounds loudest
+const CLUSTER_NEIGHBORS: usize = 4;
+const CROWDED_NEIGHBORS: f32 = 12.;
+// Turning at this rate, in radians per second, brightens the rustle fully
+const FAST_TURN: f32 = PI;
+// At most this many chirps start per sample, so a whole flock merging doesn't shriek
+const MAX_CHIRPS: usize = 3;
+const CHIRP_SECONDS: f32 = 0.12;
+// World units per unit of distance for spatial audio, and the gap between the listener's ears
+const AUDIO_SCALE: f32 = 1. / 100.;
+const EAR_GAP: f32 = 400.;
+
+// Parameters the audio thread reads from the flock, each in [0, 1], stored as f32 bits
+#[derive(Default)]
+struct FlockSoundParams {
+    density: AtomicU32,
+    speed: AtomicU32,
+    turning: AtomicU32,
+}
+
+impl FlockSoundParams {
+    fn set(value: &AtomicU32, to: f32) {
+        value.store(to.clamp(0.0, 1.0).to_bits(), Ordering::Relaxed);
+    }
+
+    fn get(value: &AtomicU32) -> f32 {
+        f32::from_bits(value.load(Ordering::Relaxed))
+    }
+}
+
+// Endless wing rustle: noise pulsing at a flap rate set by speed, louder when dense, brighter when turning
+#[derive(Asset, TypePath)]
+struct FlockSound(Arc<FlockSoundParams>);
+
+struct FlockSoundDecoder {
+    params: Arc<FlockSoundParams>,
+    // Smoothed towards the params so changes don't click
+    density: f32,
+    speed: f32,
+    turning: f32,
+    phase: f32,
+    noise: u32,
+    filtered: f32,
+}
+
+impl Iterator for FlockSoundDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let params = &self.params;
+        let smoothing = 8.0 / SAMPLE_RATE as f32;
+        self.density += (FlockSoundParams::get(&params.density) - self.density) * smoothing;
+        self.speed += (FlockSoundParams::get(&params.speed) - self.speed) * smoothing;
+        self.turning += (FlockSoundParams::get(&params.turning) - self.turning) * smoothing;
+
+        // Xorshift white noise through a one-pole lowpass
+        self.noise ^= self.noise << 13;
+        self.noise ^= self.noise >> 17;
+        self.noise ^= self.noise << 5;
+        let white = self.noise as f32 / u32::MAX as f32 * 2.0 - 1.0;
+        self.filtered += (white - self.filtered) * (0.02 + 0.3 * self.turning);
+
+        let flap_rate = 3.0 + 9.0 * self.speed;
+        self.phase = (self.phase + flap_rate / SAMPLE_RATE as f32).fract();
+        let flap = (0.5 - 0.5 * (TAU * self.phase).cos()).powi(2);
+
+        Some(self.filtered * (0.3 + 0.7 * flap) * self.density * 0.5)
+    }
+}
+
+impl Source for FlockSoundDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        None
+    }
+}
+
+impl Decodable for FlockSound {
+    type DecoderItem = f32;
+    type Decoder = FlockSoundDecoder;
+
+    fn decoder(&self) -> FlockSoundDecoder {
+        FlockSoundDecoder {
+            params: self.0.clone(),
+            density: 0.0,
+            speed: 0.0,
+            turning: 0.0,
+            phase: 0.0,
+            noise: 0x9e37_79b9,
+            filtered: 0.0,
+        }
+    }
+}
+
+// A short sine sweep between two pitches, in Hz
+#[derive(Asset, TypePath)]
+struct Chirp {
+    from: f32,
+    to: f32,
+}
+
+struct ChirpDecoder {
+    from: f32,
+    to: f32,
+    sample: u32,
+    phase: f32,
+}
+
+impl Iterator for ChirpDecoder {
+    type Item = f32;
+
+    fn next(&mut self) -> Option<f32> {
+        let length = (CHIRP_SECONDS * SAMPLE_RATE as f32) as u32;
+        if self.sample >= length {
+            return None;
+        }
+
+        let t = self.sample as f32 / length as f32;
+        self.sample += 1;
+        self.phase = (self.phase + (self.from + (self.to - self.from) * t) / SAMPLE_RATE as f32).fract();
+
+        Some((TAU * self.phase).sin() * (PI * t).sin() * 0.3)
+    }
+}
+
+impl Source for ChirpDecoder {
+    fn current_frame_len(&self) -> Option<usize> {
+        None
+    }
+
+    fn channels(&self) -> u16 {
+        1
+    }
+
+    fn sample_rate(&self) -> u32 {
+        SAMPLE_RATE
+    }
+
+    fn total_duration(&self) -> Option<Duration> {
+        Some(Duration::from_secs_f32(CHIRP_SECONDS))
+    }
+}
+
+impl Decodable for Chirp {
+    type DecoderItem = f32;
+    type Decoder = ChirpDecoder;
+
+    fn decoder(&self) -> ChirpDecoder {
+        ChirpDecoder { from: self.from, to: self.to, sample: 0, phase: 0.0 }
+    }
+}
+
+#[derive(Resource)]
+struct FlockAudio {
+    params: Arc<FlockSoundParams>,
+    muted: bool,
+    // Rising as a boid joins a cluster, falling as it leaves
+    join: Handle<Chirp>,
+    leave: Handle<Chirp>,
+}
+
+#[derive(Component)]
+struct Ambient;
+
+// Last sample's heading per boid, and whether it was in a cluster
+#[derive(Default)]
+struct Tracked(HashMap<Entity, (Vec2, bool)>);
+
+// Wing sounds following the flock's density, speed and turning, with chirps where boids
+// join or leave a cluster; `V` mutes them
+pub struct FlockAudioPlugin;
+
+impl Plugin for FlockAudioPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_audio_source::<FlockSound>()
+            .add_audio_source::<Chirp>()
+            .add_systems(Startup, setup_audio)
+            .add_systems(Update, (attach_listener, toggle_audio, listen_to_flock));
+    }
+}
+
+fn setup_audio(mut commands: Commands, mut sounds: ResMut<Assets<FlockSound>>, mut chirps: ResMut<Assets<Chirp>>) {
+    let params = Arc::new(FlockSoundParams::default());
+
+    commands.spawn((
+        AudioSourceBundle {
+            source: sounds.add(FlockSound(params.clone())),
+            settings: PlaybackSettings::LOOP,
+        },
+        Ambient,
+    ));
+
+    commands.insert_resource(FlockAudio {
+        params,
+        muted: false,
+        join: chirps.add(Chirp { from: 2000., to: 3200. }),
+        leave: chirps.add(Chirp { from: 3000., to: 1800. }),
+    });
+}
+
+type Ears = (With<MainCamera>, Without<OverviewCamera>, Without<SpatialListener>);
+
+// Heard from the followed view, not the overview
+fn attach_listener(mut commands: Commands, cameras: Query<Entity, Ears>) {
+    for camera in cameras.iter() {
+        commands.entity(camera).insert(SpatialListener::new(EAR_GAP));
+    }
+}
+
+fn toggle_audio(actions: Actions, mut audio: ResMut<FlockAudio>) {
+    if actions.just_pressed(Action::Audio) {
+        audio.muted = !audio.muted;
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn listen_to_flock(
+    mut commands: Commands,
+    audio: Res<FlockAudio>,
+    ambient: Query<&AudioSink, With<Ambient>>,
+    boids: Query<(Entity, &Transform, &Velocity), With<SpatialEntity>>,
+    index: Res<BoidIndex>,
+    simulation: Res<SimulationState>,
+    time: Res<Time>,
+    settings: Res<BoidSettings>,
+    mut elapsed: Local<f32>,
+    mut tracked: Local<Tracked>,
+) {
+    // Silent while paused, but keeps tracking so unpausing doesn't chirp for every change in between
+    let silent = audio.muted || simulation.paused;
+    for sink in ambient.iter() {
+        if silent { sink.pause() } else { sink.play() }
+    }
+
+    *elapsed += time.delta_seconds();
+    if *elapsed < SAMPLE_INTERVAL {
+        return;
+    }
+    let dt = std::mem::take(&mut *elapsed);
+
+    let mut rng = rand::rng();
+    let mut neighbors = 0;
+    let mut speed = 0.0;
+    let mut turning = 0.0;
+    let mut chirps = 0;
+    let mut now = HashMap::with_capacity(tracked.0.len());
+
+    for (boid, transform, velocity) in boids.iter() {
+        let position = transform.translation.xy();
+        let heading = velocity.0.normalize_or_zero();
+        let around = index.within_distance(position, settings.vis_range).len().saturating_sub(1);
+        let clustered = around >= CLUSTER_NEIGHBORS;
+
+        neighbors += around;
+        speed += velocity.0.length();
+
+        if let Some(&(before, was_clustered)) = tracked.0.get(&boid) {
+            if before != Vec2::ZERO && heading != Vec2::ZERO {
+                turning += before.angle_between(heading).abs();
+            }
+
+            if clustered != was_clustered && !silent && chirps < MAX_CHIRPS {
+                chirps += 1;
+                commands.spawn((
+                    AudioSourceBundle {
+                        source: if clustered { audio.join.clone() } else { audio.leave.clone() },
+                        settings: PlaybackSettings::DESPAWN
+                            .with_spatial(true)
+                            .with_spatial_scale(SpatialScale::new_2d(AUDIO_SCALE))
+                            .with_speed(rng.random_range(0.85..1.2))
+                            .with_volume(Volume::new(0.5)),
+                    },
+                    TransformBundle::from_transform(Transform::from_translation(position.extend(0.0))),
+                ));
+            }
+        }
+
+        now.insert(boid, (heading, clustered));
+    }
+
+    let n = now.len().max(1) as f32;
+    FlockSoundParams::set(&audio.params.density, neighbors as f32 / n / CROWDED_NEIGHBORS);
+    FlockSoundParams::set(&audio.params.speed, speed / n / settings.max_speed);
+    FlockSoundParams::set(&audio.params.turning, turning / n / dt / FAST_TURN);
+
+    tracked.0 = now;
+}
@@ -0,0 +1,65 @@
+use bevy::{math::Vec3Swizzles, prelude::*};
+
+use crate::{
+    energy::Energy,
+    food::Foraging,
+    predators::Predators,
+    settings::{BoidSettings, StateWeights},
+    SpatialEntity,
+};
+
+// Tired boids rest until their energy is back up to here
+const RESTED: f32 = 0.8;
+
+// What a boid is busy with, picking the weights on its steering terms
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Reflect)]
+#[reflect(Component)]
+pub enum BoidState {
+    #[default]
+    Flocking,
+    // A predator is within vision range
+    Fleeing,
+    // Hungry or tired, with food in sight
+    Feeding,
+    // Tired with nothing to eat nearby
+    Resting,
+}
+
+impl BoidState {
+    pub fn weights(self, settings: &BoidSettings) -> StateWeights {
+        match self {
+            BoidState::Flocking => settings.behavior.flocking,
+            BoidState::Fleeing => settings.behavior.fleeing,
+            BoidState::Feeding => settings.behavior.feeding,
+            BoidState::Resting => settings.behavior.resting,
+        }
+    }
+}
+
+type StateQuery = (Entity, &'static Transform, Option<&'static Energy>, &'static mut BoidState);
+
+// Danger first, then food, then rest
+pub fn update_states(
+    mut boids: Query<StateQuery, With<SpatialEntity>>,
+    predators: Predators,
+    foraging: Foraging,
+    settings: Res<BoidSettings>,
+) {
+    for (boid, transform, energy, mut state) in boids.iter_mut() {
+        let position = transform.translation.xy();
+        let tired = energy.is_some_and(Energy::tired);
+        let rested = energy.is_none_or(|energy| energy.0 >= RESTED);
+
+        let next = if predators.near(&settings, position) {
+            BoidState::Fleeing
+        } else if foraging.wants_food(boid) && foraging.in_sight(&settings, position) {
+            BoidState::Feeding
+        } else if tired || (*state == BoidState::Resting && !rested) {
+            BoidState::Resting
+        } else {
+            BoidState::Flocking
+        };
+
+        state.set_if_neq(next);
+    }
+}
@@ -0,0 +1,186 @@
+use std::time::{Duration, Instant};
+use bevy::{prelude::*, time::TimeUpdateStrategy};
+
+use crate::{
+    cli::Cli,
+    rng::SimRng,
+    settings::BoidSettings,
+    simulation::SimulationPlugin,
+    BoidSet,
+    BoidsPlugin,
+};
+
+const DEFAULT_COUNTS: [usize; 3] = [256, 1024, 4096];
+
+// A stress test starts from this many boids unless given --boids, adding an eighth of the
+// flock, or at least a batch, each round
+const STRESS_START: usize = 256;
+const STRESS_BATCH: usize = 128;
+// Ticks given to each new batch to settle into the index and neighbor caches, then timed
+const STRESS_SETTLE: usize = 10;
+const STRESS_WINDOW: usize = 60;
+// Past this the target is out of reach
+const STRESS_MAX: usize = 1 << 20;
+
+// What runs between consecutive stage markers
+const STAGES: [&str; 4] = [
+    "index (rebuild_index)",
+    "steer (flocking_system)",
+    "velocity (velocity_system)",
+    "movement (movement_system)",
+];
+
+#[derive(Resource, Default)]
+struct StageTimings {
+    last: Option<Instant>,
+    totals: [Duration; STAGES.len()],
+    ticks: usize,
+}
+
+// `--headless [--ticks <n>] [--boids <a,b,..>] [--index kdtree|flat|grid]
+// [--query nearest|radius|hybrid] [--integrator euler|semi-implicit|rk4] [--refresh <ticks>]
+// [--emit <rate> [--emit-cap <boids>]] [--stress [<ms>]]` times each boid count for `n` ticks,
+// or with `--stress` grows the flock until ticks take longer than `ms`
+pub fn run(cli: &Cli) {
+    let counts = if cli.boids.is_empty() { DEFAULT_COUNTS.to_vec() } else { cli.boids.clone() };
+
+    let defaults = BoidSettings::default();
+    let settings = BoidSettings {
+        spatial_index: cli.index.map_or(defaults.spatial_index, Into::into),
+        neighbor_query: cli.query.map_or(defaults.neighbor_query, Into::into),
+        integrator: cli.integrator.map_or(defaults.integrator, Into::into),
+        neighbor_refresh_ticks: cli.refresh.unwrap_or(defaults.neighbor_refresh_ticks),
+        ..defaults
+    };
+
+    if let Some(target) = cli.stress {
+        stress(cli, &settings, target);
+        return;
+    }
+
+    for count in counts {
+        bench(count, cli, &settings);
+    }
+}
+
+// The simulation alone, ticking exactly once per update after the first, which runs Startup
+pub fn headless_app(settings: BoidSettings, seed: Option<u64>, build: impl FnOnce(&mut App)) -> App {
+    let mut app = App::new();
+
+    app.add_plugins(MinimalPlugins)
+        .insert_resource(settings)
+        .insert_resource(SimRng::from_env(seed))
+        .add_plugins((SimulationPlugin, BoidsPlugin));
+    build(&mut app);
+
+    // Advance exactly one fixed timestep per update, however long the tick took
+    let timestep = app.world.resource::<Time<Fixed>>().timestep();
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(timestep));
+
+    app.finish();
+    app.cleanup();
+
+    // The first update has no elapsed time so no tick yet
+    app.update();
+    app
+}
+
+// A headless app charging each tick's time to its stages in `StageTimings`
+fn timed_app(count: usize, cli: &Cli, settings: &BoidSettings) -> App {
+    let mut app = headless_app(BoidSettings { count, ..settings.clone() }, cli.seed, |app| {
+        // For any nest to stream boids in from
+        app.insert_resource(cli.clone())
+            .init_resource::<StageTimings>()
+            .add_systems(FixedUpdate, (
+                mark_stage::<0>.before(BoidSet::Index),
+                mark_stage::<1>.after(BoidSet::Index).before(BoidSet::Steer),
+                mark_stage::<2>.after(BoidSet::Steer).before(BoidSet::Velocity),
+                mark_stage::<3>.after(BoidSet::Velocity).before(BoidSet::Movement),
+                mark_stage::<4>.after(BoidSet::Movement),
+            ));
+    });
+    *app.world.resource_mut::<StageTimings>() = StageTimings::default();
+    app
+}
+
+fn print_stages(timings: &StageTimings) {
+    for (stage, total) in STAGES.iter().zip(timings.totals) {
+        println!("  {stage:<28} {:>8.3} ms/tick", total.as_secs_f64() * 1000.0 / timings.ticks as f64);
+    }
+}
+
+fn bench(count: usize, cli: &Cli, settings: &BoidSettings) {
+    let ticks = cli.ticks;
+    let mut app = timed_app(count, cli, settings);
+
+    let start = Instant::now();
+    while app.world.resource::<StageTimings>().ticks < ticks {
+        app.update();
+    }
+    let elapsed = start.elapsed();
+
+    let seed = app.world.resource::<SimRng>().seed;
+    let timings = app.world.resource::<StageTimings>();
+
+    println!("{count} boids, {:?} index, {:?} query, {:?} integrator, neighbors every {} ticks, {ticks} ticks, seed {seed}: {:.1} ticks/s",
+             settings.spatial_index, settings.neighbor_query, settings.integrator, settings.neighbor_refresh_ticks, ticks as f64 / elapsed.as_secs_f64());
+
+    print_stages(timings);
+    if let Some(rate) = cli.emit {
+        println!("  emitting {rate} boids/s, ending with {}", app.world.resource::<BoidSettings>().count);
+    }
+}
+
+// Reports the largest flock whose ticks kept within `target` milliseconds, and where its time went
+fn stress(cli: &Cli, settings: &BoidSettings, target: f64) {
+    let mut app = timed_app(cli.boids.first().copied().unwrap_or(STRESS_START), cli, settings);
+    let mut sustained: Option<(usize, StageTimings)> = None;
+
+    loop {
+        for _ in 0..STRESS_SETTLE {
+            app.update();
+        }
+        *app.world.resource_mut::<StageTimings>() = StageTimings::default();
+
+        let start = Instant::now();
+        while app.world.resource::<StageTimings>().ticks < STRESS_WINDOW {
+            app.update();
+        }
+        let ms = start.elapsed().as_secs_f64() * 1000.0 / STRESS_WINDOW as f64;
+
+        // Emitters and lifespans can change the population under way
+        let count = app.world.resource::<BoidSettings>().count;
+        println!("{count} boids: {ms:.2} ms/tick");
+        if ms > target || count >= STRESS_MAX {
+            break;
+        }
+
+        let timings = std::mem::take(&mut *app.world.resource_mut::<StageTimings>());
+        sustained = Some((count, timings));
+        app.world.resource_mut::<BoidSettings>().count = count + (count / 8).max(STRESS_BATCH);
+    }
+
+    let seed = app.world.resource::<SimRng>().seed;
+    let Some((count, timings)) = sustained else {
+        println!("even the starting flock takes over {target} ms a tick, seed {seed}");
+        return;
+    };
+
+    println!("sustainable population within {target} ms/tick: {count} boids, {:?} index, {:?} query, {:?} integrator, neighbors every {} ticks, seed {seed}",
+             settings.spatial_index, settings.neighbor_query, settings.integrator, settings.neighbor_refresh_ticks);
+    print_stages(&timings);
+}
+
+// Charges the time since the previous marker to the stage that just finished
+fn mark_stage<const STAGE: usize>(mut timings: ResMut<StageTimings>) {
+    let now = Instant::now();
+
+    if let (Some(stage), Some(last)) = (STAGE.checked_sub(1), timings.last) {
+        timings.totals[stage] += now - last;
+    }
+    if STAGE == STAGES.len() {
+        timings.ticks += 1;
+    }
+
+    timings.last = Some(now);
+}
@@ -0,0 +1,233 @@
+use std::sync::Arc;
+use rand::prelude::*;
+use bevy::prelude::*;
+
+use crate::{input::{Action, Actions}, settings::BoidSettings, WorldBounds};
+
+// Shapes `B` cycles through, each sized to the world
+const SHAPES: usize = 4;
+
+// Shortest offset between two points on a torus of size `world`
+pub fn wrap_offset(offset: Vec2, world: Vec2) -> Vec2 {
+    offset - world * (offset / world).round()
+}
+
+// Bring a point that left the play area back in through the opposite edge
+pub fn wrap_position(position: Vec2, world: Vec2) -> Vec2 {
+    let half = world / 2.0;
+    (position + half).rem_euclid(world) - half
+}
+
+// Direction back into a box `world` across, less `boundary_size`, each axis on its own so boids in
+// a corner turn back along both; `Boundary::Rect` in 2D and the 3D volume alike
+pub fn box_turn<const N: usize>(position: [f32; N], world: [f32; N], boundary_size: f32) -> [f32; N] {
+    std::array::from_fn(|axis| {
+        let half = (world[axis] - boundary_size) / 2.0;
+        if position[axis] < -half {
+            1.0
+        } else if position[axis] > half {
+            -1.0
+        } else {
+            0.0
+        }
+    })
+}
+
+// Copies of `position` shifted across any seam closer than `range`,
+// so neighbor queries also find boids just across the edge
+pub fn seam_ghosts(position: Vec2, world: Vec2, range: f32) -> Vec<Vec2> {
+    let half = world / 2.0;
+    let shift = |p: f32, half: f32, size: f32| {
+        if p > half - range {
+            -size
+        } else if p < range - half {
+            size
+        } else {
+            0.0
+        }
+    };
+
+    let dx = shift(position.x, half.x, world.x);
+    let dy = shift(position.y, half.y, world.y);
+
+    let mut ghosts = vec![];
+    if dx != 0.0 {
+        ghosts.push(position + Vec2::new(dx, 0.0));
+    }
+    if dy != 0.0 {
+        ghosts.push(position + Vec2::new(0.0, dy));
+    }
+    if dx != 0.0 && dy != 0.0 {
+        ghosts.push(position + Vec2::new(dx, dy));
+    }
+    ghosts
+}
+
+// Signed distance to an edge, negative inside
+pub type Sdf = Arc<dyn Fn(Vec2) -> f32 + Send + Sync>;
+
+// Region boids steer back into, bounce off or die leaving, per `BoundaryMode`
+#[derive(Resource, Clone, Default)]
+pub enum Boundary {
+    // The play area shrunk by `boundary_size` on each side
+    #[default]
+    Rect,
+    Circle { center: Vec2, radius: f32 },
+    // Vertices in order, convex or not
+    Polygon(Vec<Vec2>),
+    Sdf(Sdf),
+}
+
+impl Boundary {
+    // Direction to push a boid at `position` back inside, zero when already in
+    pub fn turn(&self, position: Vec2, world: Vec2, boundary_size: f32) -> Vec2 {
+        if let Boundary::Rect = self {
+            return Vec2::from_array(box_turn(position.to_array(), world.to_array(), boundary_size));
+        }
+
+        if self.distance(position, world, boundary_size) <= 0.0 {
+            return Vec2::ZERO;
+        }
+        -self.normal(position, world, boundary_size)
+    }
+
+    pub fn distance(&self, position: Vec2, world: Vec2, boundary_size: f32) -> f32 {
+        match self {
+            Boundary::Rect => {
+                // https://iquilezles.org/articles/distfunctions2d/
+                let d = position.abs() - (world - boundary_size) / 2.0;
+                d.max(Vec2::ZERO).length() + d.max_element().min(0.0)
+            }
+            Boundary::Circle { center, radius } => position.distance(*center) - radius,
+            Boundary::Polygon(vertices) => polygon_distance(vertices, position),
+            Boundary::Sdf(sdf) => sdf(position),
+        }
+    }
+
+    // Outward, by central differences so any shape works
+    pub fn normal(&self, position: Vec2, world: Vec2, boundary_size: f32) -> Vec2 {
+        const EPS: f32 = 0.5;
+        let d = |offset: Vec2| self.distance(position + offset, world, boundary_size);
+
+        Vec2::new(d(Vec2::X * EPS) - d(-Vec2::X * EPS), d(Vec2::Y * EPS) - d(-Vec2::Y * EPS)).normalize_or_zero()
+    }
+
+    // Uniform over the play area, falling back to the center for shapes too small to hit
+    pub fn random_point(&self, rng: &mut impl Rng, world: Vec2, boundary_size: f32) -> Vec2 {
+        let half = world / 2.0;
+
+        (0..16)
+            .map(|_| Vec2::new(rng.random_range(-half.x..half.x), rng.random_range(-half.y..half.y)))
+            .find(|point| self.distance(*point, world, boundary_size) <= 0.0)
+            .unwrap_or(Vec2::ZERO)
+    }
+
+    fn shape_index(&self) -> usize {
+        match self {
+            Boundary::Rect => 0,
+            Boundary::Circle { .. } => 1,
+            Boundary::Polygon(_) => 2,
+            Boundary::Sdf(_) => 3,
+        }
+    }
+
+    // Rectangle, circle, hexagon and a two-lobed arena, fitting `world` less `boundary_size`
+    fn shape(index: usize, world: Vec2, boundary_size: f32) -> Self {
+        let radius = (world.min_element() - boundary_size) / 2.0;
+        match index % SHAPES {
+            0 => Boundary::Rect,
+            1 => Boundary::Circle { center: Vec2::ZERO, radius },
+            2 => Boundary::Polygon((0..6)
+                .map(|i| Vec2::from_angle(std::f32::consts::TAU * i as f32 / 6.0) * radius)
+                .collect()),
+            _ => {
+                // Smooth union of two overlapping circles
+                let lobe = Vec2::new(radius * 0.8, 0.0);
+                Boundary::Sdf(Arc::new(move |p| {
+                    const K: f32 = 40.0;
+                    let (a, b) = (p.distance(-lobe) - radius * 0.8, p.distance(lobe) - radius * 0.8);
+                    let h = (0.5 + 0.5 * (b - a) / K).clamp(0.0, 1.0);
+                    b + (a - b) * h - K * h * (1.0 - h)
+                }))
+            }
+        }
+    }
+
+    pub fn draw(&self, gizmos: &mut Gizmos, world: Vec2, boundary_size: f32) {
+        match self {
+            Boundary::Rect => {
+                gizmos.rect_2d(Vec2::ZERO, 0.0, world - Vec2::splat(boundary_size), Color::GRAY);
+            }
+            Boundary::Circle { center, radius } => {
+                gizmos.circle_2d(*center, *radius, Color::GRAY).segments(64);
+            }
+            Boundary::Polygon(vertices) => {
+                gizmos.linestrip_2d(vertices.iter().chain(vertices.first()).copied(), Color::GRAY);
+            }
+            // No closed form, so trace where the distance crosses zero cell by cell
+            Boundary::Sdf(_) => {
+                const CELL: f32 = 8.0;
+                let half = world / 2.0;
+                let (cols, rows) = ((world.x / CELL) as i32, (world.y / CELL) as i32);
+
+                for y in 0..=rows {
+                    for x in 0..=cols {
+                        let p = Vec2::new(x as f32, y as f32) * CELL - half;
+                        let d = self.distance(p, world, boundary_size);
+                        if d.abs() > CELL / 2.0 {
+                            continue;
+                        }
+
+                        let normal = self.normal(p, world, boundary_size);
+                        let on_edge = p - normal * d;
+                        let along = normal.perp() * CELL / 2.0;
+                        gizmos.line_2d(on_edge - along, on_edge + along, Color::GRAY);
+                    }
+                }
+            }
+        }
+    }
+}
+
+// https://iquilezles.org/articles/distfunctions2d/, exact for any simple polygon
+fn polygon_distance(vertices: &[Vec2], p: Vec2) -> f32 {
+    let Some(first) = vertices.first() else {
+        return f32::INFINITY;
+    };
+    let mut d = p.distance_squared(*first);
+    let mut sign = 1.0;
+
+    for i in 0..vertices.len() {
+        let (a, b) = (vertices[i], vertices[(i + vertices.len() - 1) % vertices.len()]);
+        let (e, w) = (b - a, p - a);
+        let nearest = w - e * (w.dot(e) / e.dot(e)).clamp(0.0, 1.0);
+        d = d.min(nearest.length_squared());
+
+        // Crossings of a ray from `p` flip whether it's inside
+        let crossing = [p.y >= a.y, p.y < b.y, e.x * w.y > e.y * w.x];
+        if crossing.iter().all(|c| *c) || crossing.iter().all(|c| !*c) {
+            sign = -sign;
+        }
+    }
+
+    sign * d.sqrt()
+}
+
+// `B` cycles rectangle, circle, hexagon and a two-lobed arena
+pub fn cycle_boundary(
+    actions: Actions,
+    mut boundary: ResMut<Boundary>,
+    bounds: Res<WorldBounds>,
+    settings: Res<BoidSettings>,
+) {
+    if actions.just_pressed(Action::CycleBoundary) {
+        *boundary = Boundary::shape(boundary.shape_index() + 1, **bounds, settings.boundary_size);
+    }
+}
+
+// Keeps the shape fitted to the world as the window is resized or the margin changed
+pub fn resize_boundary(mut boundary: ResMut<Boundary>, bounds: Res<WorldBounds>, settings: Res<BoidSettings>) {
+    if (bounds.is_changed() || settings.is_changed()) && !matches!(*boundary, Boundary::Rect) {
+        *boundary = Boundary::shape(boundary.shape_index(), **bounds, settings.boundary_size);
+    }
+}
@@ -0,0 +1,248 @@
+use bevy::{
+    input::mouse::{MouseScrollUnit, MouseWheel},
+    math::Vec3Swizzles,
+    prelude::*,
+    render::camera::Viewport,
+    transform::TransformSystem,
+    window::PrimaryWindow,
+};
+
+use crate::{
+    input::{Action, Actions},
+    interpolation::interpolate_transforms,
+    selection::Selection,
+    MainCamera,
+    SpatialEntity,
+    WorldBounds,
+};
+
+const MIN_ZOOM: f32 = 0.1;
+const MAX_ZOOM: f32 = 2.0;
+// How quickly the camera closes the gap to its target, per second
+const FOLLOW_RATE: f32 = 6.0;
+
+// The boid the camera is tracking, if any
+#[derive(Resource, Default)]
+pub struct CameraFollow(pub Option<Entity>);
+
+// Where the camera settles while it isn't following a boid, moved by dragging
+#[derive(Resource, Default)]
+pub struct CameraPan(pub Vec2);
+
+// The right half of the window while split, framing the whole world; the left half is the
+// usual camera, still following and zooming on its own
+#[derive(Component)]
+pub struct OverviewCamera;
+
+// The usual camera, not the overview
+type MainView = (With<MainCamera>, Without<OverviewCamera>);
+
+// `Tab` follows the next boid, `C` lets the camera drift back to center, the mouse wheel zooms
+// whichever view it's over, dragging with the middle button pans and `X` splits the window with
+// an overview
+pub struct CameraFollowPlugin;
+
+impl Plugin for CameraFollowPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CameraFollow>()
+            .init_resource::<CameraPan>()
+            .add_systems(Update, (
+                cycle_follow_target,
+                zoom_camera,
+                pan_camera,
+                (toggle_split_screen, split_viewports).chain(),
+            ))
+            .add_systems(PostUpdate, follow_camera
+                .after(interpolate_transforms)
+                .before(TransformSystem::TransformPropagate));
+    }
+}
+
+// The world-viewing camera whose viewport a window position is over, and that position within it
+pub fn camera_under<'a>(
+    cameras: impl IntoIterator<Item = (&'a Camera, &'a GlobalTransform)>,
+    position: Vec2,
+) -> Option<(&'a Camera, &'a GlobalTransform, Vec2)> {
+    cameras.into_iter()
+        .filter(|(camera, _)| camera.is_active)
+        .find_map(|(camera, t_camera)| {
+            let viewport = camera.logical_viewport_rect()?;
+            viewport.contains(position).then_some((camera, t_camera, position - viewport.min))
+        })
+}
+
+fn toggle_split_screen(
+    mut commands: Commands,
+    actions: Actions,
+    overview: Query<Entity, With<OverviewCamera>>,
+    mut follow: ResMut<CameraFollow>,
+    selection: Option<Res<Selection>>,
+) {
+    if !actions.just_pressed(Action::SplitScreen) {
+        return;
+    }
+
+    if let Ok(overview) = overview.get_single() {
+        commands.entity(overview).despawn();
+        return;
+    }
+
+    // The followed half has something to follow, the selected boid when there is one
+    if follow.0.is_none() {
+        follow.0 = selection.and_then(|selection| selection.boid);
+    }
+
+    commands.spawn((
+        Camera2dBundle {
+            // Between the main camera and the minimap
+            camera: Camera { order: 1, ..default() },
+            ..default()
+        },
+        MainCamera,
+        OverviewCamera,
+    ));
+}
+
+// Side by side while there's an overview, otherwise the main camera has the whole window
+fn split_viewports(
+    mut cameras: Query<(&mut Camera, &mut OrthographicProjection, Has<OverviewCamera>), With<MainCamera>>,
+    added: Query<(), Added<OverviewCamera>>,
+    window: Query<&Window, With<PrimaryWindow>>,
+    bounds: Res<WorldBounds>,
+) {
+    let Ok(window) = window.get_single() else {
+        return;
+    };
+
+    let split = cameras.iter().any(|(_, _, overview)| overview);
+    let size = UVec2::new(window.physical_width(), window.physical_height());
+    let half = UVec2::new(size.x / 2, size.y);
+
+    for (mut camera, mut projection, overview) in cameras.iter_mut() {
+        let viewport = match (split, overview) {
+            (false, _) => None,
+            (true, false) => Some((UVec2::ZERO, half)),
+            (true, true) => Some((UVec2::new(half.x, 0), UVec2::new(size.x - half.x, size.y))),
+        };
+
+        let current = camera.viewport.as_ref().map(|v| (v.physical_position, v.physical_size));
+        if current != viewport {
+            camera.viewport = viewport.map(|(physical_position, physical_size)| Viewport {
+                physical_position,
+                physical_size,
+                ..default()
+            });
+        }
+
+        // The overview starts out framing the whole world, zoomed independently after
+        if overview && !added.is_empty() {
+            let logical = Vec2::new((size.x - half.x) as f32, size.y as f32) / window.scale_factor();
+            projection.scale = (**bounds / logical).max_element().clamp(MIN_ZOOM, MAX_ZOOM);
+        }
+    }
+}
+
+fn cycle_follow_target(
+    actions: Actions,
+    boids: Query<Entity, With<SpatialEntity>>,
+    mut follow: ResMut<CameraFollow>,
+    mut pan: ResMut<CameraPan>,
+) {
+    if actions.just_pressed(Action::StopFollowing) {
+        follow.0 = None;
+        pan.0 = Vec2::ZERO;
+    }
+    if !actions.just_pressed(Action::FollowNext) {
+        return;
+    }
+
+    // Cycle in a stable order, independent of query iteration
+    let mut boids = boids.iter().collect::<Vec<_>>();
+    boids.sort_unstable();
+
+    follow.0 = match follow.0 {
+        Some(current) => boids.iter().find(|boid| **boid > current).or(boids.first()).copied(),
+        None => boids.first().copied(),
+    };
+}
+
+fn zoom_camera(
+    mut wheel: EventReader<MouseWheel>,
+    mut cameras: Query<(&Camera, &mut OrthographicProjection), With<MainCamera>>,
+    window: Query<&Window, With<PrimaryWindow>>,
+) {
+    let scroll = wheel.read()
+        .map(|event| match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / 40.0,
+        })
+        .sum::<f32>();
+
+    if scroll == 0.0 {
+        return;
+    }
+
+    // Only the view under the cursor, or every one if it's outside the window
+    let cursor = window.get_single().ok().and_then(Window::cursor_position);
+    for (camera, mut projection) in cameras.iter_mut() {
+        let under = camera.logical_viewport_rect().zip(cursor).map(|(viewport, cursor)| viewport.contains(cursor));
+        if under != Some(false) {
+            projection.scale = (projection.scale * 0.9_f32.powf(scroll)).clamp(MIN_ZOOM, MAX_ZOOM);
+        }
+    }
+}
+
+// The world moves with the cursor, at whatever zoom; grabbing the view lets go of a followed boid
+fn pan_camera(
+    actions: Actions,
+    mut follow: ResMut<CameraFollow>,
+    mut pan: ResMut<CameraPan>,
+    mut camera: Query<(&mut Transform, &OrthographicProjection), MainView>,
+    window: Query<&Window, With<PrimaryWindow>>,
+    mut last: Local<Option<Vec2>>,
+) {
+    let cursor = window.get_single().ok().and_then(Window::cursor_position);
+    let (Ok((mut transform, projection)), true) = (camera.get_single_mut(), actions.pressed(Action::Place)) else {
+        *last = None;
+        return;
+    };
+
+    // Picked up from wherever the camera is, mid-drift or on a boid
+    if last.is_none() || follow.0.take().is_some() {
+        pan.0 = transform.translation.xy();
+    }
+
+    if let (Some(from), Some(to)) = (*last, cursor) {
+        // Window y runs down, world y up
+        pan.0 += (from - to) * Vec2::new(1.0, -1.0) * projection.scale;
+        transform.translation.x = pan.0.x;
+        transform.translation.y = pan.0.y;
+    }
+    *last = cursor;
+}
+
+fn follow_camera(
+    mut follow: ResMut<CameraFollow>,
+    pan: Res<CameraPan>,
+    mut camera: Query<&mut Transform, MainView>,
+    targets: Query<&Transform, (With<SpatialEntity>, Without<MainCamera>)>,
+    time: Res<Time>,
+) {
+    // Caught or removed boids can't be followed
+    let target = match follow.0.map(|boid| targets.get(boid)) {
+        Some(Ok(transform)) => transform.translation.xy(),
+        Some(Err(_)) => {
+            follow.0 = None;
+            pan.0
+        }
+        None => pan.0,
+    };
+
+    let t = 1.0 - (-FOLLOW_RATE * time.delta_seconds()).exp();
+
+    for mut transform in camera.iter_mut() {
+        let position = transform.translation.xy().lerp(target, t);
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+    }
+}
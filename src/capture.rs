@@ -0,0 +1,206 @@
+use std::sync::{Arc, Mutex};
+use bevy::{
+    core::FrameCount,
+    prelude::*,
+    render::view::screenshot::ScreenshotManager,
+    tasks::AsyncComputeTaskPool,
+    window::PrimaryWindow,
+};
+use gif::{Encoder, Frame, Repeat};
+
+use crate::input::{Action, Actions};
+
+// Frames are grabbed at most this often, GIF delays only having centisecond precision
+const CAPTURE_INTERVAL: f32 = 1. / 20.;
+// Longest side of each GIF frame, larger windows are scaled down to keep files shareable
+const MAX_GIF_SIZE: u32 = 640;
+// Quantization effort, 1 (best) to 30 (fastest)
+const GIF_SPEED: i32 = 10;
+// Stops itself after this many frames, a minute at the capture rate
+const MAX_GIF_FRAMES: usize = 1200;
+
+// Frames encoded so far, appended to from the screenshot callbacks
+#[derive(Default)]
+struct GifFrames {
+    encoder: Option<Encoder<Vec<u8>>>,
+    size: (u16, u16),
+    frames: usize,
+    // Set once saved, so late callbacks don't start another
+    done: bool,
+}
+
+#[derive(Resource, Default)]
+struct GifCapture {
+    // While capturing: the frames, and the time since the last was grabbed
+    active: Option<(Arc<Mutex<GifFrames>>, f32)>,
+}
+
+// `F12` saves a PNG screenshot, `F8` starts and then stops capturing the window to an animated
+// GIF; both download through the browser under WASM
+pub struct CapturePlugin;
+
+impl Plugin for CapturePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GifCapture>()
+            .add_systems(Update, (take_screenshot, toggle_gif_capture, capture_gif_frame).chain());
+    }
+}
+
+fn take_screenshot(
+    actions: Actions,
+    window: Query<Entity, With<PrimaryWindow>>,
+    mut screenshots: ResMut<ScreenshotManager>,
+    frame: Res<FrameCount>,
+) {
+    if !actions.just_pressed(Action::Screenshot) {
+        return;
+    }
+
+    if let Ok(window) = window.get_single() {
+        let path = format!("boids_{}.png", frame.0);
+        if screenshots.save_screenshot_to_disk(window, &path).is_err() {
+            warn!("a screenshot is already being taken this frame");
+        }
+    }
+}
+
+fn toggle_gif_capture(actions: Actions, mut capture: ResMut<GifCapture>, frame: Res<FrameCount>) {
+    let full = capture.active.as_ref()
+        .is_some_and(|(frames, _)| frames.lock().unwrap().frames >= MAX_GIF_FRAMES);
+    if !actions.just_pressed(Action::Gif) && !full {
+        return;
+    }
+
+    let Some((frames, _)) = capture.active.take() else {
+        info!("capturing a GIF, press F8 again to stop");
+        // Due straight away
+        capture.active = Some((default(), CAPTURE_INTERVAL));
+        return;
+    };
+
+    // Finished on the task pool, after any frames still being encoded
+    let path = format!("boids_{}.gif", frame.0);
+    AsyncComputeTaskPool::get().spawn(async move {
+        let mut frames = frames.lock().unwrap();
+        let count = frames.frames;
+        frames.done = true;
+
+        match frames.encoder.take().map(Encoder::into_inner) {
+            Some(Ok(bytes)) => match save(&path, bytes) {
+                Ok(()) => info!("saved {count} frames to {path}"),
+                Err(err) => error!("failed to save {path}: {err}"),
+            },
+            Some(Err(err)) => error!("failed to encode {path}: {err}"),
+            None => warn!("no frames were captured"),
+        }
+    }).detach();
+}
+
+fn capture_gif_frame(
+    mut capture: ResMut<GifCapture>,
+    window: Query<Entity, With<PrimaryWindow>>,
+    mut screenshots: ResMut<ScreenshotManager>,
+    time: Res<Time<Real>>,
+) {
+    let (Some((frames, elapsed)), Ok(window)) = (capture.active.as_mut(), window.get_single()) else {
+        return;
+    };
+
+    *elapsed += time.delta_seconds();
+    if *elapsed < CAPTURE_INTERVAL {
+        return;
+    }
+
+    // Each frame shows until the next was grabbed, however long that actually took
+    let delay = (*elapsed * 100.0).round() as u16;
+    let frames = frames.clone();
+
+    // Skipped if F12 already asked for this frame's screenshot
+    if screenshots.take_screenshot(window, move |image| encode_frame(&frames, image, delay)).is_ok() {
+        *elapsed = 0.0;
+    }
+}
+
+fn encode_frame(frames: &Mutex<GifFrames>, image: Image, delay: u16) {
+    let image = match image.try_into_dynamic() {
+        Ok(image) => image.to_rgba8(),
+        Err(err) => {
+            error!("failed to read a GIF frame: {err}");
+            return;
+        }
+    };
+
+    let (width, height) = image.dimensions();
+    let step = width.max(height).div_ceil(MAX_GIF_SIZE).max(1);
+    let size = ((width / step) as u16, (height / step) as u16);
+
+    // Nearest downscale, with alpha dropped: under HDR it holds brightness, not coverage
+    let mut pixels = Vec::with_capacity(size.0 as usize * size.1 as usize * 4);
+    for y in 0..size.1 as u32 {
+        for x in 0..size.0 as u32 {
+            let [r, g, b, _] = image.get_pixel(x * step, y * step).0;
+            pixels.extend([r, g, b, 255]);
+        }
+    }
+
+    let mut frames = frames.lock().unwrap();
+    if frames.done {
+        return;
+    }
+    if frames.encoder.is_none() {
+        let encoder = Encoder::new(vec![], size.0, size.1, &[])
+            .and_then(|mut encoder| encoder.set_repeat(Repeat::Infinite).map(|_| encoder));
+        match encoder {
+            Ok(encoder) => {
+                frames.encoder = Some(encoder);
+                frames.size = size;
+            }
+            Err(err) => {
+                error!("failed to start encoding a GIF: {err}");
+                return;
+            }
+        }
+    }
+
+    // Frames from after a resize can't join the others
+    if frames.size != size {
+        return;
+    }
+
+    let mut frame = Frame::from_rgba_speed(size.0, size.1, &mut pixels, GIF_SPEED);
+    frame.delay = delay;
+
+    let written = frames.encoder.as_mut().map(|encoder| encoder.write_frame(&frame));
+    match written {
+        Some(Ok(())) => frames.frames += 1,
+        Some(Err(err)) => error!("failed to encode a GIF frame: {err}"),
+        None => {}
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn save(path: &str, bytes: Vec<u8>) -> Result<(), String> {
+    std::fs::write(path, bytes).map_err(|err| err.to_string())
+}
+
+// The browser has no file system to write to, so hand the file to it as a download
+#[cfg(target_arch = "wasm32")]
+fn save(path: &str, bytes: Vec<u8>) -> Result<(), String> {
+    use wasm_bindgen::JsCast;
+
+    let download = || {
+        let parts = js_sys::Array::of1(&js_sys::Uint8Array::from(bytes.as_slice()));
+        let blob = web_sys::Blob::new_with_u8_array_sequence(&parts)?;
+        let url = web_sys::Url::create_object_url_with_blob(&blob)?;
+
+        let document = web_sys::window().and_then(|window| window.document()).ok_or("no document")?;
+        let link = document.create_element("a")?;
+        link.set_attribute("href", &url)?;
+        link.set_attribute("download", path)?;
+        link.dyn_into::<web_sys::HtmlElement>()?.click();
+
+        web_sys::Url::revoke_object_url(&url)
+    };
+
+    download().map_err(|err: wasm_bindgen::JsValue| format!("{err:?}"))
+}
@@ -0,0 +1,166 @@
+use std::{net::SocketAddr, path::PathBuf};
+use bevy::prelude::*;
+use clap::{Parser, ValueEnum};
+
+use crate::{
+    presets::Preset,
+    settings::{BoidSettings, Integrator, NeighborQuery, SpatialIndex},
+    WINDOW_BOUNDS,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum IndexArg {
+    Kdtree,
+    Flat,
+    Grid,
+}
+
+impl From<IndexArg> for SpatialIndex {
+    fn from(index: IndexArg) -> Self {
+        match index {
+            IndexArg::Kdtree => SpatialIndex::KdTree,
+            IndexArg::Flat => SpatialIndex::Flat,
+            IndexArg::Grid => SpatialIndex::Grid,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum QueryArg {
+    Nearest,
+    Radius,
+    Hybrid,
+}
+
+impl From<QueryArg> for NeighborQuery {
+    fn from(query: QueryArg) -> Self {
+        match query {
+            QueryArg::Nearest => NeighborQuery::Nearest,
+            QueryArg::Radius => NeighborQuery::Radius,
+            QueryArg::Hybrid => NeighborQuery::Hybrid,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum IntegratorArg {
+    Euler,
+    SemiImplicit,
+    Rk4,
+}
+
+impl From<IntegratorArg> for Integrator {
+    fn from(integrator: IntegratorArg) -> Self {
+        match integrator {
+            IntegratorArg::Euler => Integrator::Euler,
+            IntegratorArg::SemiImplicit => Integrator::SemiImplicit,
+            IntegratorArg::Rk4 => Integrator::Rk4,
+        }
+    }
+}
+
+/// Boids flocking with bevy_spatial
+#[derive(Parser, Resource, Clone, Debug)]
+#[command(version)]
+pub struct Cli {
+    /// Boids to spawn; with --headless, a comma separated list of counts to benchmark in turn
+    #[arg(long, value_delimiter = ',')]
+    pub boids: Vec<usize>,
+
+    /// Seed for the simulation's randomness, otherwise BOIDS_SEED or a random one
+    #[arg(long)]
+    pub seed: Option<u64>,
+
+    /// Window width in logical pixels
+    #[arg(long, default_value_t = WINDOW_BOUNDS.x)]
+    pub width: f32,
+
+    /// Window height in logical pixels
+    #[arg(long, default_value_t = WINDOW_BOUNDS.y)]
+    pub height: f32,
+
+    /// Benchmark the simulation without a window, printing per-stage timings
+    #[arg(long)]
+    pub headless: bool,
+
+    /// Ticks to run each headless benchmark for
+    #[arg(long, default_value_t = 1000, requires = "headless")]
+    pub ticks: usize,
+
+    /// Spatial index to benchmark with
+    #[arg(long, value_enum, requires = "headless")]
+    pub index: Option<IndexArg>,
+
+    /// How boids query the index for neighbors while benchmarking
+    #[arg(long, value_enum, requires = "headless")]
+    pub query: Option<QueryArg>,
+
+    /// How boids' flight is stepped through each tick while benchmarking
+    #[arg(long, value_enum, requires = "headless")]
+    pub integrator: Option<IntegratorArg>,
+
+    /// Ticks boids reuse their neighbor lists for while benchmarking
+    #[arg(long, requires = "headless")]
+    pub refresh: Option<u32>,
+
+    /// Keep adding boids until a tick takes longer than this many milliseconds, then report the
+    /// largest flock that kept up
+    #[arg(long, value_name = "MS", num_args = 0..=1, default_missing_value = "16", requires = "headless")]
+    pub stress: Option<f64>,
+
+    /// Stream boids in from a nest on the left edge at this many per second
+    #[arg(long, value_name = "RATE")]
+    pub emit: Option<f32>,
+
+    /// Despawn the oldest boids once there are more than this many, while emitting
+    #[arg(long, value_name = "BOIDS", requires = "emit")]
+    pub emit_cap: Option<usize>,
+
+    /// Fly the flock in a boxed 3D volume
+    #[arg(long = "3d", conflicts_with = "gpu")]
+    pub three_d: bool,
+
+    /// Flock entirely in compute shaders
+    #[arg(long)]
+    pub gpu: bool,
+
+    /// Share the flock with --connect'ed clients over UDP on this port
+    #[arg(long, value_name = "PORT", num_args = 0..=1, default_missing_value = "7878")]
+    pub serve: Option<u16>,
+
+    /// Show a flock served elsewhere, steering it with the cursor
+    #[arg(long, value_name = "HOST:PORT", conflicts_with = "serve")]
+    pub connect: Option<SocketAddr>,
+
+    /// Start in one of the built-in scenarios, also picked with the number keys
+    #[arg(long, value_enum)]
+    pub preset: Option<Preset>,
+
+    /// Play back a recording saved with R
+    #[arg(long, value_name = "FILE")]
+    pub replay: Option<PathBuf>,
+
+    /// Log flock metrics every tick to this CSV
+    #[arg(long, value_name = "FILE")]
+    pub telemetry: Option<PathBuf>,
+
+    /// Also log every boid's state every this many ticks, next to the --telemetry CSV
+    #[arg(long, value_name = "TICKS", requires = "telemetry", value_parser = clap::value_parser!(u32).range(1..))]
+    pub telemetry_boids: Option<u32>,
+}
+
+impl Cli {
+    pub fn boid_count(&self) -> Option<usize> {
+        self.boids.first().copied()
+    }
+
+    // What the command line sets, kept over the defaults and anything loaded from assets/boids.ron
+    pub fn apply(&self, settings: &mut BoidSettings) {
+        if let Some(preset) = self.preset {
+            preset.apply(settings);
+        }
+        if let Some(count) = self.boid_count() {
+            settings.count = count;
+        }
+    }
+}
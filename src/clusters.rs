@@ -0,0 +1,98 @@
+use bevy::{math::Vec3Swizzles, prelude::*, utils::HashMap};
+use bevy_spatial::SpatialAccess;
+
+use crate::{
+    index::BoidIndex,
+    settings::{BoidSettings, ColorMode},
+    stats::FlockStats,
+    SpatialEntity,
+};
+
+// Seconds between searches, sub-flocks split and merge slowly enough that every tick is wasted work
+const CLUSTER_INTERVAL: f32 = 0.5;
+
+// The sub-flock each boid was last found in, labelled by its lowest entity index so a sub-flock
+// keeps its label while it holds together; boids with no one in sight are strays and absent
+#[derive(Resource, Default)]
+pub struct Clusters {
+    pub of: HashMap<Entity, u32>,
+}
+
+// Groups boids into sub-flocks, the connected components of boids within `vis_range` of each
+// other, for the stats HUD and `ColorMode::Cluster`
+pub struct ClusterPlugin;
+
+impl Plugin for ClusterPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Clusters>()
+            .add_systems(Update, find_clusters.run_if(wanted));
+    }
+}
+
+fn wanted(stats: Res<FlockStats>, settings: Res<BoidSettings>) -> bool {
+    stats.enabled || settings.color_mode == ColorMode::Cluster
+}
+
+// Root of `i`'s set, halving the path on the way
+fn find(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
+
+fn find_clusters(
+    boids: Query<(Entity, &Transform), With<SpatialEntity>>,
+    index: Res<BoidIndex>,
+    settings: Res<BoidSettings>,
+    mut clusters: ResMut<Clusters>,
+    mut stats: ResMut<FlockStats>,
+    time: Res<Time>,
+    mut since: Local<Option<f32>>,
+) {
+    // Due straight away when first wanted
+    let elapsed = since.get_or_insert(CLUSTER_INTERVAL);
+    *elapsed += time.delta_seconds();
+    if *elapsed < CLUSTER_INTERVAL {
+        return;
+    }
+    *elapsed = 0.0;
+
+    let entities: Vec<_> = boids.iter().collect();
+    let slots: HashMap<_, _> = entities.iter().enumerate().map(|(slot, (boid, _))| (*boid, slot)).collect();
+    let mut parents: Vec<_> = (0..entities.len()).collect();
+
+    // Each edge is seen from both ends, joining it once is enough
+    for (slot, (_, transform)) in entities.iter().enumerate() {
+        for (_, neighbor) in index.within_distance(transform.translation.xy(), settings.vis_range) {
+            let Some(&other) = neighbor.and_then(|neighbor| slots.get(&neighbor)) else {
+                continue;
+            };
+            if other > slot {
+                let (a, b) = (find(&mut parents, slot), find(&mut parents, other));
+                parents[a.max(b)] = a.min(b);
+            }
+        }
+    }
+
+    let mut sizes = vec![0; entities.len()];
+    let mut labels = vec![u32::MAX; entities.len()];
+    for (slot, (boid, _)) in entities.iter().enumerate() {
+        let root = find(&mut parents, slot);
+        sizes[root] += 1;
+        labels[root] = labels[root].min(boid.index());
+    }
+
+    clusters.of.clear();
+    for (slot, (boid, _)) in entities.iter().enumerate() {
+        let root = find(&mut parents, slot);
+        if sizes[root] > 1 {
+            clusters.of.insert(*boid, labels[root]);
+        }
+    }
+
+    stats.clusters = sizes.iter().filter(|&&size| size > 1).count();
+    stats.largest_cluster = sizes.iter().copied().filter(|&size| size > 1).max().unwrap_or_default();
+    stats.strays = sizes.iter().filter(|&&size| size == 1).count();
+}
@@ -0,0 +1,128 @@
+use bevy::prelude::*;
+
+use crate::{
+    input::{Action, Actions},
+    attach_boid_meshes,
+    clusters::Clusters,
+    settings::{BoidSettings, ColorMode},
+    BoidAssets,
+    BoidColor,
+    SpatialEntity,
+    Velocity,
+};
+
+// Colors are quantized to this many steps, so boids share a handful of materials and still batch
+const HEADING_STEPS: usize = 36;
+const SPEED_STEPS: usize = 32;
+// Sub-flocks cycle through this many hues, a golden angle apart so neighbouring labels differ
+const CLUSTER_HUES: usize = 12;
+const GOLDEN_ANGLE: f32 = 137.5;
+const STRAY_COLOR: Color = Color::rgb(0.45, 0.45, 0.45);
+
+// `N` cycles what boids are colored by: species, heading, speed or sub-flock
+pub struct ColoringPlugin;
+
+impl Plugin for ColoringPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, cycle_color_mode)
+            .add_systems(PostUpdate, color_boids.after(attach_boid_meshes));
+    }
+}
+
+fn cycle_color_mode(actions: Actions, mut settings: ResMut<BoidSettings>) {
+    if actions.just_pressed(Action::CycleColors) {
+        settings.color_mode = settings.color_mode.next();
+        info!("coloring boids by {:?}", settings.color_mode);
+    }
+}
+
+fn palette(mode: ColorMode) -> Vec<Color> {
+    match mode {
+        ColorMode::Species => vec![],
+        ColorMode::Heading => (0..HEADING_STEPS)
+            .map(|i| Color::hsl(i as f32 / HEADING_STEPS as f32 * 360., 0.8, 0.6))
+            .collect(),
+        // Blue through to red
+        ColorMode::Speed => (0..SPEED_STEPS)
+            .map(|i| Color::hsl(240. * (1.0 - i as f32 / (SPEED_STEPS - 1) as f32), 0.9, 0.55))
+            .collect(),
+        // Strays last
+        ColorMode::Cluster => (0..CLUSTER_HUES)
+            .map(|i| Color::hsl((i as f32 * GOLDEN_ANGLE) % 360., 0.75, 0.6))
+            .chain([STRAY_COLOR])
+            .collect(),
+    }
+}
+
+// Which palette entry a boid flying at this velocity in this sub-flock gets
+fn shade(mode: ColorMode, velocity: Vec2, cluster: Option<u32>, settings: &BoidSettings) -> usize {
+    match mode {
+        ColorMode::Species => 0,
+        ColorMode::Heading => {
+            let turns = velocity.y.atan2(velocity.x) / std::f32::consts::TAU;
+            (turns.rem_euclid(1.0) * HEADING_STEPS as f32).round() as usize % HEADING_STEPS
+        }
+        ColorMode::Speed => {
+            let range = (settings.max_speed - settings.min_speed).max(f32::EPSILON);
+            let t = ((velocity.length() - settings.min_speed) / range).clamp(0.0, 1.0);
+            (t * (SPEED_STEPS - 1) as f32).round() as usize
+        }
+        ColorMode::Cluster => cluster.map_or(CLUSTER_HUES, |label| label as usize % CLUSTER_HUES),
+    }
+}
+
+type ColoredBoid<'a> = (
+    Entity,
+    &'a Velocity,
+    &'a BoidColor,
+    Option<&'a mut Handle<ColorMaterial>>,
+    Option<&'a mut Sprite>,
+);
+
+// Meshes swap between the palette's shared materials, only touched when their shade changes;
+// sprites are tinted directly. Boids get their species color back on returning to `Species`
+fn color_boids(
+    mut assets: ResMut<BoidAssets>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    settings: Res<BoidSettings>,
+    clusters: Res<Clusters>,
+    mut boids: Query<ColoredBoid, With<SpatialEntity>>,
+    mut current: Local<ColorMode>,
+    mut palette_materials: Local<Vec<(Color, Handle<ColorMaterial>)>>,
+) {
+    let mode = settings.color_mode;
+    let changed = mode != *current;
+    if changed {
+        *current = mode;
+        *palette_materials = palette(mode).into_iter()
+            .map(|color| (color, assets.material(&mut materials, color)))
+            .collect();
+    }
+
+    if mode == ColorMode::Species {
+        if !changed {
+            return;
+        }
+        for (_, _, color, material, sprite) in boids.iter_mut() {
+            if let Some(mut material) = material {
+                *material = assets.material(&mut materials, color.0);
+            }
+            if let Some(mut sprite) = sprite {
+                sprite.color = color.0;
+            }
+        }
+        return;
+    }
+
+    for (boid, velocity, _, material, sprite) in boids.iter_mut() {
+        let cluster = clusters.of.get(&boid).copied();
+        let (color, handle) = &palette_materials[shade(mode, velocity.0, cluster, &settings)];
+
+        if let Some(mut material) = material.filter(|material| **material != *handle) {
+            *material = handle.clone();
+        }
+        if let Some(mut sprite) = sprite.filter(|sprite| sprite.color != *color) {
+            sprite.color = *color;
+        }
+    }
+}
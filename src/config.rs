@@ -0,0 +1,189 @@
+use std::error::Error;
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+    utils::BoxedFuture,
+};
+use serde::de::DeserializeOwned;
+
+use crate::{cli::Cli, input::InputMap, settings::BoidSettings, species::SpeciesInteractions};
+
+const CONFIG_PATH: &str = "boids.ron";
+const SPECIES_PATH: &str = "species.ron";
+const INPUT_PATH: &str = "input.ron";
+
+// `BoidSettings` as loaded from `assets/boids.ron`; missing fields keep their defaults
+#[derive(Asset, TypePath, Debug)]
+pub struct BoidConfig(pub BoidSettings);
+
+// `SpeciesInteractions` as loaded from `assets/species.ron`
+#[derive(Asset, TypePath, Debug)]
+pub struct SpeciesConfig(pub SpeciesInteractions);
+
+// Rebindings as loaded from `assets/input.ron`; actions it leaves out keep their default inputs
+#[derive(Asset, TypePath, Debug)]
+pub struct InputConfig(pub InputMap);
+
+async fn read_ron<T: DeserializeOwned>(reader: &mut Reader<'_>) -> Result<T, Box<dyn Error + Send + Sync>> {
+    let mut bytes = Vec::new();
+    reader.read_to_end(&mut bytes).await?;
+    Ok(ron::de::from_bytes(&bytes)?)
+}
+
+#[derive(Default)]
+struct BoidConfigLoader;
+
+impl AssetLoader for BoidConfigLoader {
+    type Asset = BoidConfig;
+    type Settings = ();
+    type Error = Box<dyn Error + Send + Sync>;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<BoidConfig, Self::Error>> {
+        Box::pin(async move { Ok(BoidConfig(read_ron(reader).await?)) })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ron"]
+    }
+}
+
+#[derive(Default)]
+struct SpeciesConfigLoader;
+
+impl AssetLoader for SpeciesConfigLoader {
+    type Asset = SpeciesConfig;
+    type Settings = ();
+    type Error = Box<dyn Error + Send + Sync>;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<SpeciesConfig, Self::Error>> {
+        Box::pin(async move { Ok(SpeciesConfig(read_ron(reader).await?)) })
+    }
+
+    // Matched ahead of plain `ron`
+    fn extensions(&self) -> &[&str] {
+        &["species.ron"]
+    }
+}
+
+#[derive(Default)]
+struct InputConfigLoader;
+
+impl AssetLoader for InputConfigLoader {
+    type Asset = InputConfig;
+    type Settings = ();
+    type Error = Box<dyn Error + Send + Sync>;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<InputConfig, Self::Error>> {
+        Box::pin(async move { Ok(InputConfig(read_ron(reader).await?)) })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["input.ron"]
+    }
+}
+
+#[derive(Resource)]
+struct BoidConfigHandle(Handle<BoidConfig>);
+
+#[derive(Resource)]
+struct SpeciesConfigHandle(Handle<SpeciesConfig>);
+
+#[derive(Resource)]
+struct InputConfigHandle(Handle<InputConfig>);
+
+// Populates `BoidSettings`, `SpeciesInteractions` and `InputMap` from the config files at startup, and
+// again whenever they change on disk when built with the `hot_reload` feature
+pub struct ConfigPlugin;
+
+impl Plugin for ConfigPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<BoidConfig>()
+            .init_asset::<SpeciesConfig>()
+            .init_asset::<InputConfig>()
+            .init_asset_loader::<BoidConfigLoader>()
+            .init_asset_loader::<SpeciesConfigLoader>()
+            .init_asset_loader::<InputConfigLoader>()
+            .add_systems(PreStartup, load_config)
+            .add_systems(Update, (apply_config, apply_species_config, apply_input_config));
+    }
+}
+
+fn load_config(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(BoidConfigHandle(asset_server.load(CONFIG_PATH)));
+    commands.insert_resource(SpeciesConfigHandle(asset_server.load(SPECIES_PATH)));
+    commands.insert_resource(InputConfigHandle(asset_server.load(INPUT_PATH)));
+}
+
+fn apply_config(
+    mut events: EventReader<AssetEvent<BoidConfig>>,
+    handle: Res<BoidConfigHandle>,
+    configs: Res<Assets<BoidConfig>>,
+    mut settings: ResMut<BoidSettings>,
+    cli: Option<Res<Cli>>,
+) {
+    for event in events.read() {
+        if !event.is_loaded_with_dependencies(&handle.0) && !event.is_modified(&handle.0) {
+            continue;
+        }
+
+        if let Some(config) = configs.get(&handle.0) {
+            info!("applying settings from {CONFIG_PATH}");
+            let mut config = config.0.clone();
+            if let Some(cli) = &cli {
+                cli.apply(&mut config);
+            }
+            settings.set_if_neq(config);
+        }
+    }
+}
+
+fn apply_species_config(
+    mut events: EventReader<AssetEvent<SpeciesConfig>>,
+    handle: Res<SpeciesConfigHandle>,
+    configs: Res<Assets<SpeciesConfig>>,
+    mut interactions: ResMut<SpeciesInteractions>,
+) {
+    for event in events.read() {
+        if !event.is_loaded_with_dependencies(&handle.0) && !event.is_modified(&handle.0) {
+            continue;
+        }
+
+        if let Some(config) = configs.get(&handle.0) {
+            info!("applying species interactions from {SPECIES_PATH}");
+            interactions.set_if_neq(config.0.clone());
+        }
+    }
+}
+
+fn apply_input_config(
+    mut events: EventReader<AssetEvent<InputConfig>>,
+    handle: Res<InputConfigHandle>,
+    configs: Res<Assets<InputConfig>>,
+    mut map: ResMut<InputMap>,
+) {
+    for event in events.read() {
+        if !event.is_loaded_with_dependencies(&handle.0) && !event.is_modified(&handle.0) {
+            continue;
+        }
+
+        if let Some(config) = configs.get(&handle.0) {
+            info!("applying key bindings from {INPUT_PATH}");
+            map.set_if_neq(InputMap::rebinding(&config.0));
+        }
+    }
+}
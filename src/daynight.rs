@@ -0,0 +1,166 @@
+use std::{borrow::Cow, f32::consts::TAU};
+use bevy::prelude::*;
+
+use crate::{input::{Action, Actions}, settings::BoidSettings, BoidSet};
+
+// Width of dawn and dusk, in how far the sun is above or below the horizon out of 1 at noon
+const TWILIGHT: f32 = 0.25;
+// Where the clock starts, a little after sunrise
+const START_TIME: f32 = 0.3;
+
+const DUSK_SKY: Color = Color::rgb(0.42, 0.24, 0.2);
+const NIGHT_SKY: Color = Color::rgb(0.02, 0.02, 0.06);
+
+// Time of day, and the settings boids drift towards as it gets dark. By day they fly by their own
+// settings, at night by `night`'s, and halfway between the two at dawn and dusk
+#[derive(Resource, Reflect)]
+#[reflect(Resource)]
+pub struct DayNight {
+    pub running: bool,
+    // Fraction of a day since midnight
+    pub time_of_day: f32,
+    pub night: BoidSettings,
+}
+
+impl Default for DayNight {
+    fn default() -> Self {
+        // Huddled close and drifting little, with most of the flock sitting it out on a roost
+        let night = BoidSettings {
+            center_factor: 3.6,
+            matching_factor: 5.0,
+            prot_range: 6.,
+            wander_factor: 20.,
+            perch_chance: 0.4,
+            perch_min: 20.,
+            perch_max: 40.,
+            ..default()
+        };
+        Self { running: false, time_of_day: START_TIME, night }
+    }
+}
+
+impl DayNight {
+    // 0 through the day, 1 through the night, easing between the two over twilight
+    pub fn darkness(&self) -> f32 {
+        if !self.running {
+            return 0.0;
+        }
+        let sun = -(self.time_of_day * TAU).cos();
+        let t = ((TWILIGHT - sun) / (2.0 * TWILIGHT)).clamp(0.0, 1.0);
+        t * t * (3.0 - 2.0 * t)
+    }
+
+    // `day` as shifted towards the night profile by how dark it is. Only the flocking and
+    // roosting settings change, so speeds, ranges and the world stay as configured
+    pub fn settings<'a>(&self, day: &'a BoidSettings) -> Cow<'a, BoidSettings> {
+        let t = self.darkness();
+        if t == 0.0 {
+            return Cow::Borrowed(day);
+        }
+
+        let night = &self.night;
+        let lerp = |day: f32, night: f32| day + (night - day) * t;
+        Cow::Owned(BoidSettings {
+            center_factor: lerp(day.center_factor, night.center_factor),
+            matching_factor: lerp(day.matching_factor, night.matching_factor),
+            avoid_factor: lerp(day.avoid_factor, night.avoid_factor),
+            prot_range: lerp(day.prot_range, night.prot_range),
+            wander_factor: lerp(day.wander_factor, night.wander_factor),
+            perch_chance: lerp(day.perch_chance, night.perch_chance),
+            perch_min: lerp(day.perch_min, night.perch_min),
+            perch_max: lerp(day.perch_max, night.perch_max),
+            ..day.clone()
+        })
+    }
+
+    // Hours and minutes on a 24 hour clock
+    fn clock(&self) -> (u32, u32) {
+        let minutes = (self.time_of_day * 24.0 * 60.0) as u32;
+        (minutes / 60, minutes % 60)
+    }
+}
+
+// A day passing every `day_length` seconds of simulated time once started, boids roosting
+// through the night and flocking tighter as the light changes
+pub struct DayNightPlugin;
+
+impl Plugin for DayNightPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DayNight>()
+            .register_type::<DayNight>()
+            .add_systems(FixedUpdate, advance_clock.in_set(BoidSet::Index).run_if(cycle_running));
+    }
+}
+
+fn cycle_running(day_night: Res<DayNight>) -> bool {
+    day_night.running
+}
+
+fn advance_clock(mut day_night: ResMut<DayNight>, settings: Res<BoidSettings>, time: Res<Time>) {
+    let step = time.delta_seconds() / settings.day_length.max(1.0);
+    day_night.time_of_day = (day_night.time_of_day + step).rem_euclid(1.0);
+}
+
+// `E` starts and stops the cycle, the sky darkening with it
+pub struct DayNightViewPlugin;
+
+impl Plugin for DayNightViewPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (toggle_day_night, paint_sky).chain());
+    }
+}
+
+fn toggle_day_night(actions: Actions, mut day_night: ResMut<DayNight>) {
+    if !actions.just_pressed(Action::DayNight) {
+        return;
+    }
+
+    day_night.running = !day_night.running;
+    if day_night.running {
+        let (hours, minutes) = day_night.clock();
+        info!("day/night cycle running from {hours:02}:{minutes:02}");
+    } else {
+        info!("day/night cycle stopped");
+    }
+}
+
+// Day through dusk to night, and back to the plain background when stopped
+fn paint_sky(day_night: Res<DayNight>, mut clear_color: ResMut<ClearColor>) {
+    if !day_night.is_changed() {
+        return;
+    }
+
+    let day = ClearColor::default().0;
+    let t = day_night.darkness();
+    let mix = |a: Color, b: Color, t: f32| Color::rgb(
+        a.r() + (b.r() - a.r()) * t,
+        a.g() + (b.g() - a.g()) * t,
+        a.b() + (b.b() - a.b()) * t,
+    );
+    clear_color.0 = if t < 0.5 {
+        mix(day, DUSK_SKY, t * 2.0)
+    } else {
+        mix(DUSK_SKY, NIGHT_SKY, t * 2.0 - 1.0)
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn night_settings_follow_the_light() {
+        let at = |time_of_day| DayNight { running: true, time_of_day, ..default() };
+        let day = BoidSettings::default();
+
+        assert_eq!(at(0.5).darkness(), 0.0);
+        assert_eq!(at(0.0).darkness(), 1.0);
+        assert!((at(0.25).darkness() - 0.5).abs() < 1e-4, "halfway at sunrise");
+
+        assert!(matches!(at(0.5).settings(&day), Cow::Borrowed(_)));
+        let midnight = at(0.0).settings(&day).into_owned();
+        assert_eq!(midnight.center_factor, at(0.0).night.center_factor);
+        assert_eq!(midnight.max_speed, day.max_speed);
+        assert_eq!(DayNight { running: false, ..at(0.0) }.darkness(), 0.0);
+    }
+}
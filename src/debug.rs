@@ -0,0 +1,113 @@
+use bevy::{math::Vec3Swizzles, prelude::*};
+use crate::{
+    index::BoidIndex,
+    input::{Action, Actions},
+    settings::BoidSettings,
+    spatial::partitions2,
+    variation::{with_traits, BoidTraits, Mass},
+    SpatialEntity,
+    WorldBounds,
+};
+
+// Vision cones, perception radii, obstacle whiskers and the neighbor links flocking_system actually used
+#[derive(Resource, Default)]
+pub struct DebugOverlay {
+    pub enabled: bool,
+    // From boid to neighbor, refreshed every tick while enabled
+    pub links: Vec<(Vec2, Vec2)>,
+}
+
+// How the kd-tree currently partitions the flock
+#[derive(Resource, Default)]
+pub struct TreeOverlay(pub bool);
+
+// `F3` toggles the debug overlay, `K` the kd-tree partitions
+pub struct DebugOverlayPlugin;
+
+impl Plugin for DebugOverlayPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DebugOverlay>()
+            .init_resource::<TreeOverlay>()
+            .add_systems(Update, (
+                toggle_debug_overlay,
+                draw_debug_overlay.run_if(|overlay: Res<DebugOverlay>| overlay.enabled),
+                draw_tree_overlay.run_if(|overlay: Res<TreeOverlay>| overlay.0),
+            ));
+    }
+}
+
+fn toggle_debug_overlay(
+    actions: Actions,
+    mut overlay: ResMut<DebugOverlay>,
+    mut tree: ResMut<TreeOverlay>,
+) {
+    if actions.just_pressed(Action::DebugOverlay) {
+        overlay.enabled = !overlay.enabled;
+        overlay.links.clear();
+    }
+    if actions.just_pressed(Action::TreeOverlay) {
+        tree.0 = !tree.0;
+    }
+}
+
+type OverlaidBoid<'a> = (&'a Transform, Option<&'a BoidTraits>, Option<&'a Mass>);
+
+fn draw_debug_overlay(
+    overlay: Res<DebugOverlay>,
+    boids: Query<OverlaidBoid, With<SpatialEntity>>,
+    settings: Res<BoidSettings>,
+    mut gizmos: Gizmos,
+) {
+    for (transform, traits, mass) in boids.iter() {
+        let settings = with_traits(&settings, traits, mass);
+        let position = transform.translation.xy();
+        let heading = (transform.rotation * Vec3::X).xy();
+        let angle = heading.y.atan2(heading.x);
+
+        gizmos.circle_2d(position, settings.prot_range, Color::rgba(1.0, 0.3, 0.3, 0.4));
+
+        // arc_2d measures its direction clockwise from +Y
+        gizmos.arc_2d(
+            position,
+            std::f32::consts::FRAC_PI_2 - angle,
+            settings.fov * 2.0,
+            settings.vis_range,
+            Color::rgba(0.3, 0.6, 1.0, 0.4),
+        );
+        for edge in [angle - settings.fov, angle + settings.fov] {
+            gizmos.line_2d(
+                position,
+                position + Vec2::from_angle(edge) * settings.vis_range,
+                Color::rgba(0.3, 0.6, 1.0, 0.4),
+            );
+        }
+        if settings.whisker_factor != 0.0 {
+            for side in [0.0, -1.0, 1.0] {
+                let ray = Vec2::from_angle(angle + side * settings.whisker_angle);
+                gizmos.line_2d(position, position + ray * settings.whisker_length, Color::rgba(1.0, 0.6, 0.2, 0.3));
+            }
+        }
+    }
+
+    for (from, to) in overlay.links.iter() {
+        gizmos.line_2d(*from, *to, Color::rgba(0.4, 1.0, 0.4, 0.6));
+    }
+}
+
+fn draw_tree_overlay(
+    index: Res<BoidIndex>,
+    bounds: Res<WorldBounds>,
+    mut gizmos: Gizmos,
+) {
+    // Only drawn while the kd-tree is the active index
+    let Some(tree) = index.kdtree() else {
+        return;
+    };
+    let area = Rect::from_center_size(Vec2::ZERO, **bounds);
+
+    // Splits near the root cut the most space, so draw them strongest
+    for (from, to, depth) in partitions2(tree, area) {
+        let alpha = 0.8 / (1.0 + depth as f32 * 0.5);
+        gizmos.line_2d(from, to, Color::rgba(1.0, 0.8, 0.2, alpha));
+    }
+}
@@ -0,0 +1,181 @@
+use std::f32::consts::PI;
+use rand::prelude::*;
+use bevy::{math::Vec3Swizzles, prelude::*};
+
+use crate::{
+    cli::Cli,
+    grab::Held,
+    input::{Action, Actions},
+    player::Player,
+    rng::SimRng,
+    settings::BoidSettings,
+    spawn_boid,
+    sync_boid_count,
+    AnyBoid,
+    BoidSet,
+    BoidSpawn,
+    CursorPosition,
+    WorldBounds,
+};
+
+// How far either side of its direction a nest sends boids off, and how far in from the edge it sits
+const NEST_SPREAD: f32 = PI / 6.0;
+const NEST_INSET: f32 = 20.;
+// Length of each side of the cone drawn for an emitter
+const CONE_LENGTH: f32 = 40.;
+
+// Spawns `rate` boids a second at its position, each flying off at `speed` within `spread`
+// radians of `direction`. Beyond a `cap` on the whole population, the oldest boids are despawned
+#[derive(Component, Clone, Debug, Reflect)]
+#[reflect(Component)]
+pub struct Emitter {
+    pub rate: f32,
+    pub direction: f32,
+    pub spread: f32,
+    pub speed: f32,
+    pub cap: Option<usize>,
+    // Boids owed so far, one spawned each time this passes 1
+    due: f32,
+}
+
+impl Emitter {
+    pub fn new(rate: f32, direction: f32, spread: f32, speed: f32, cap: Option<usize>) -> Self {
+        Self { rate, direction, spread, speed, cap, due: 0.0 }
+    }
+}
+
+// A nest on the left edge of the world, streaming boids in to the right
+fn spawn_nest(commands: &mut Commands, bounds: Vec2, rate: f32, cap: Option<usize>, speed: f32) {
+    let position = Vec3::new(-bounds.x / 2.0 + NEST_INSET, 0.0, 0.0);
+    commands.spawn((
+        Emitter::new(rate, 0.0, NEST_SPREAD, speed, cap),
+        TransformBundle::from_transform(Transform::from_translation(position)),
+    ));
+}
+
+// `--emit <rate> [--emit-cap <boids>]` streams boids in from a nest from the start
+fn spawn_cli_nest(mut commands: Commands, cli: Option<Res<Cli>>, bounds: Res<WorldBounds>, settings: Res<BoidSettings>) {
+    let Some(cli) = cli else {
+        return;
+    };
+    if let Some(rate) = cli.emit {
+        spawn_nest(&mut commands, **bounds, rate, cli.emit_cap.or(settings.emitter_cap), settings.max_speed);
+    }
+}
+
+// When an emitted boid hatched, in the order they did; the starting flock has none and counts
+// as oldest
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Reflect)]
+#[reflect(Component)]
+pub struct Emitted(pub u64);
+
+// Emitters keep boids streaming in, headless or not
+pub struct EmitterPlugin;
+
+impl Plugin for EmitterPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Emitter>()
+            .register_type::<Emitted>()
+            .add_systems(Startup, spawn_cli_nest)
+            .add_systems(FixedUpdate, emit_boids.in_set(BoidSet::Index));
+    }
+}
+
+fn emit_boids(
+    mut commands: Commands,
+    mut rng: ResMut<SimRng>,
+    mut settings: ResMut<BoidSettings>,
+    mut emitters: Query<(&mut Emitter, &Transform)>,
+    boids: Query<(Entity, Option<&Emitted>), AnyBoid>,
+    // Never culled to make room
+    kept: Query<(), Or<(With<Player>, With<Held>)>>,
+    time: Res<Time>,
+    mut hatched: Local<u64>,
+) {
+    if emitters.is_empty() {
+        return;
+    }
+
+    let mut count = boids.iter().len();
+    let mut cap: Option<usize> = None;
+
+    for (mut emitter, transform) in emitters.iter_mut() {
+        emitter.due += emitter.rate * time.delta_seconds();
+        cap = match (cap, emitter.cap) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
+
+        while emitter.due >= 1.0 {
+            emitter.due -= 1.0;
+
+            let heading = emitter.direction + rng.random_range(-emitter.spread..=emitter.spread);
+            let mut spawn = BoidSpawn::random(&mut **rng, &settings, transform.translation.xy());
+            spawn.velocity = Vec2::from_angle(heading) * emitter.speed;
+
+            let boid = spawn_boid(&mut commands, &settings, spawn);
+            commands.entity(boid).insert(Emitted(*hatched));
+            *hatched += 1;
+            count += 1;
+        }
+    }
+
+    if let Some(cap) = cap.filter(|cap| count > *cap) {
+        let mut oldest: Vec<_> = boids.iter().filter(|(boid, _)| !kept.contains(*boid)).collect();
+        oldest.sort_unstable_by_key(|(_, emitted)| emitted.copied());
+        for (boid, _) in oldest.into_iter().take(count - cap) {
+            commands.entity(boid).despawn();
+            count -= 1;
+        }
+    }
+
+    sync_boid_count(&mut settings, count);
+}
+
+// `J` puts a nest at the cursor, aimed at the middle of the world; `Shift` + `J` clears them all
+pub struct EmitterViewPlugin;
+
+impl Plugin for EmitterViewPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (place_emitter, draw_emitters));
+    }
+}
+
+fn place_emitter(
+    mut commands: Commands,
+    actions: Actions,
+    cursor: Res<CursorPosition>,
+    settings: Res<BoidSettings>,
+    emitters: Query<Entity, With<Emitter>>,
+) {
+    if !actions.just_pressed(Action::Emitter) {
+        return;
+    }
+
+    if actions.pressed(Action::Alternate) {
+        for emitter in emitters.iter() {
+            commands.entity(emitter).despawn();
+        }
+        return;
+    }
+
+    let Some(position) = cursor.0 else {
+        return;
+    };
+    let direction = (-position.y).atan2(-position.x);
+    commands.spawn((
+        Emitter::new(settings.emitter_rate, direction, NEST_SPREAD, settings.max_speed, settings.emitter_cap),
+        TransformBundle::from_transform(Transform::from_translation(position.extend(0.0))),
+    ));
+}
+
+fn draw_emitters(emitters: Query<(&Emitter, &Transform)>, mut gizmos: Gizmos) {
+    for (emitter, transform) in emitters.iter() {
+        let position = transform.translation.xy();
+        for side in [-emitter.spread, emitter.spread] {
+            let edge = Vec2::from_angle(emitter.direction + side) * CONE_LENGTH;
+            gizmos.line_2d(position, position + edge, Color::YELLOW);
+        }
+        gizmos.circle_2d(position, 6.0, Color::YELLOW);
+    }
+}
@@ -0,0 +1,41 @@
+use bevy::prelude::*;
+
+use crate::settings::BoidSettings;
+
+// Below this boids slow down and go looking for food
+pub const TIRED: f32 = 0.3;
+
+// 1 rested, 0 exhausted; spent flying fast and turning hard, recovered coasting
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+pub struct Energy(pub f32);
+
+impl Default for Energy {
+    fn default() -> Self {
+        Self(1.0)
+    }
+}
+
+impl Energy {
+    pub fn tired(&self) -> bool {
+        self.0 < TIRED
+    }
+
+    // Exhausted boids manage half their usual top speed
+    pub fn max_speed_scale(&self) -> f32 {
+        0.5 + 0.5 * (self.0 / TIRED).min(1.0)
+    }
+
+    // Update for a tick that took velocity from `before` to `after`
+    pub fn spend(&mut self, settings: &BoidSettings, before: Vec2, after: Vec2, dt: f32) {
+        let span = (settings.max_speed - settings.min_speed).max(f32::EPSILON);
+        let effort = ((after.length() - settings.min_speed) / span).clamp(0.0, 1.0);
+        let turn = before.angle_between(after).abs();
+        let turn = if turn.is_finite() { turn } else { 0.0 };
+
+        let recovered = settings.energy_recovery * (1.0 - effort) * dt;
+        let spent = settings.energy_drain * effort * dt + settings.turn_cost * turn;
+
+        self.0 = (self.0 + recovered - spent).clamp(0.0, 1.0);
+    }
+}
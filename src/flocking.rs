@@ -0,0 +1,497 @@
+use std::ops::{Add, AddAssign, Div, Mul, Sub, SubAssign};
+
+use bevy::{math::BVec4A, prelude::*};
+
+use crate::settings::{BoidSettings, Falloff, Integrator};
+
+// Vector math the flocking rules need, shared by the 2D and 3D modes
+pub trait FlockVector:
+    Copy
+    + Default
+    + Send
+    + Sync
+    + Add<Output = Self>
+    + Sub<Output = Self>
+    + Mul<f32, Output = Self>
+    + Div<f32, Output = Self>
+    + AddAssign
+    + SubAssign
+{
+    fn length_squared(self) -> f32;
+
+    // Unsigned angle between two vectors, in radians
+    fn angle_to(self, other: Self) -> f32;
+
+    // Turned `angle` radians towards `other`, both non-zero
+    fn turn_towards(self, other: Self, angle: f32) -> Self;
+
+    // Turned and scaled as it takes to get from `from` to `to`, `from` non-zero
+    fn turned_like(self, from: Self, to: Self) -> Self;
+}
+
+impl FlockVector for Vec2 {
+    fn length_squared(self) -> f32 {
+        Vec2::length_squared(self)
+    }
+
+    fn angle_to(self, other: Self) -> f32 {
+        self.angle_between(other).abs()
+    }
+
+    fn turn_towards(self, other: Self, angle: f32) -> Self {
+        Vec2::from_angle(angle.copysign(self.angle_between(other))).rotate(self)
+    }
+
+    // As complex numbers, `self * to / from`
+    fn turned_like(self, from: Self, to: Self) -> Self {
+        let change = to.rotate(Vec2::new(from.x, -from.y)) / from.length_squared();
+        change.rotate(self)
+    }
+}
+
+impl FlockVector for Vec3 {
+    fn length_squared(self) -> f32 {
+        Vec3::length_squared(self)
+    }
+
+    fn angle_to(self, other: Self) -> f32 {
+        self.angle_between(other)
+    }
+
+    // About whichever axis it takes, any one square to it when turning right round
+    fn turn_towards(self, other: Self, angle: f32) -> Self {
+        let axis = self.cross(other).try_normalize().unwrap_or_else(|| self.any_orthonormal_vector());
+        Quat::from_axis_angle(axis, angle) * self
+    }
+
+    fn turned_like(self, from: Self, to: Self) -> Self {
+        match to.try_normalize() {
+            Some(heading) => Quat::from_rotation_arc(from.normalize(), heading) * self * (to.length() / from.length()),
+            None => Vec3::ZERO,
+        }
+    }
+}
+
+// Smooth noise in [-1, 1], a few incommensurate sines so it never visibly repeats
+pub fn wander_noise(t: f32) -> f32 {
+    0.5 * t.sin() + 0.3 * (2.3 * t + 1.7).sin() + 0.2 * (4.1 * t + 2.9).sin()
+}
+
+// Another boid as seen from the boid being steered
+pub struct Neighbor<V> {
+    pub offset: V,
+    pub velocity: V,
+}
+
+// Whether a boid heading along `heading` can see a neighbor at `offset`
+pub fn perceives<V: FlockVector>(settings: &BoidSettings, heading: V, offset: V) -> bool {
+    let dist_sq = offset.length_squared();
+
+    // Don't evaluate boids out of range
+    if dist_sq > settings.vis_range_sq() {
+        return false;
+    }
+
+    // Don't evaluate boids behind
+    dist_sq == 0.0 || heading.angle_to(offset) <= settings.fov
+}
+
+// What each of the three classic rules contributes to a boid's steering
+#[derive(Clone, Copy, Default, Debug)]
+pub struct RuleTerms<V> {
+    pub cohesion: V,
+    pub alignment: V,
+    pub separation: V,
+}
+
+impl<V: FlockVector> RuleTerms<V> {
+    pub fn total(&self) -> V {
+        self.cohesion + self.alignment + self.separation
+    }
+}
+
+// Neighbors in view but outside the protected range, the ones a boid flocks towards
+fn flockmates<'a, V: FlockVector>(settings: &BoidSettings, neighbors: &'a [Neighbor<V>]) -> impl Iterator<Item = &'a Neighbor<V>> {
+    let prot_range_sq = settings.prot_range_sq();
+    neighbors.iter().filter(move |neighbor| neighbor.offset.length_squared() >= prot_range_sq)
+}
+
+// Each neighbor's vector weighted by `falloff` of its distance, summed over how many there are
+fn falloff_average<'a, V: FlockVector + 'a>(
+    falloff: Falloff,
+    range: f32,
+    neighbors: impl Iterator<Item = (&'a Neighbor<V>, V)>,
+) -> V {
+    let (sum, count) = neighbors.fold((V::default(), 0), |(sum, count), (neighbor, v)| {
+        let weight = falloff.weight(neighbor.offset.length_squared().sqrt(), range);
+        (sum + v * weight, count + 1)
+    });
+
+    if count > 0 { sum / count as f32 } else { sum }
+}
+
+// https://vanhunteradams.com/Pico/Animal_Movement/Boids-algorithm.html
+// Each rule takes only the neighbors the boid perceives
+
+// Towards the middle of the flockmates
+pub fn cohesion<V: FlockVector>(settings: &BoidSettings, neighbors: &[Neighbor<V>]) -> V {
+    let offsets = flockmates(settings, neighbors).map(|neighbor| (neighbor, neighbor.offset));
+    falloff_average(settings.cohesion_falloff, settings.vis_range, offsets) * settings.center_factor
+}
+
+// Match the flockmates' velocity
+pub fn alignment<V: FlockVector>(settings: &BoidSettings, neighbors: &[Neighbor<V>]) -> V {
+    let velocities = flockmates(settings, neighbors).map(|neighbor| (neighbor, neighbor.velocity));
+    falloff_average(settings.alignment_falloff, settings.vis_range, velocities) * settings.matching_factor
+}
+
+// Away from anyone inside the protected range
+pub fn separation<V: FlockVector>(settings: &BoidSettings, neighbors: &[Neighbor<V>]) -> V {
+    let prot_range_sq = settings.prot_range_sq();
+    let away = neighbors.iter()
+        .filter(|neighbor| neighbor.offset.length_squared() < prot_range_sq)
+        .map(|neighbor| (neighbor, V::default() - neighbor.offset));
+
+    falloff_average(settings.separation_falloff, settings.prot_range, away) * settings.avoid_factor
+}
+
+// Cohesion, alignment and separation for one boid given its candidate neighbors
+pub fn flocking_rules<V: FlockVector>(
+    settings: &BoidSettings,
+    heading: V,
+    neighbors: impl IntoIterator<Item = Neighbor<V>>,
+) -> RuleTerms<V> {
+    let perceived: Vec<_> = neighbors.into_iter()
+        .filter(|neighbor| perceives(settings, heading, neighbor.offset))
+        .collect();
+
+    RuleTerms {
+        cohesion: cohesion(settings, &perceived),
+        alignment: alignment(settings, &perceived),
+        separation: separation(settings, &perceived),
+    }
+}
+
+// The steering force and what a boid flies against, for one tick of flight
+#[derive(Clone, Copy)]
+pub struct Flight<V> {
+    pub force: V,
+    pub mass: f32,
+    pub max_speed: f32,
+    pub drag: f32,
+}
+
+impl<V: FlockVector> Flight<V> {
+    // Thrust along the velocity, balancing the quadratic drag at `max_speed` in level flight so
+    // steering can briefly push past it
+    fn thrust(&self, velocity: V) -> V {
+        let speed = velocity.length_squared().sqrt();
+        if speed > 0.0 { velocity * (self.drag * self.max_speed * self.max_speed / speed) } else { V::default() }
+    }
+
+    pub fn acceleration(&self, velocity: V) -> V {
+        let drag = velocity * (self.drag * velocity.length_squared().sqrt());
+        (self.force + self.thrust(velocity) - drag) / self.mass
+    }
+
+    // The velocity `dt` on, and how far the boid travels meanwhile
+    pub fn step(&self, integrator: Integrator, velocity: V, dt: f32) -> (V, V) {
+        match integrator {
+            Integrator::Euler => (velocity + self.acceleration(velocity) * dt, velocity * dt),
+            Integrator::SemiImplicit => {
+                let after = fly(velocity, self, dt);
+                (after, after * dt)
+            }
+            Integrator::Rk4 => {
+                let k1 = self.acceleration(velocity);
+                let k2 = self.acceleration(velocity + k1 * (dt / 2.0));
+                let k3 = self.acceleration(velocity + k2 * (dt / 2.0));
+                let k4 = self.acceleration(velocity + k3 * dt);
+
+                let after = velocity + (k1 + k2 * 2.0 + k3 * 2.0 + k4) * (dt / 6.0);
+                let travelled = (velocity * 6.0 + (k1 + k2 + k3) * dt) * (dt / 6.0);
+                (after, travelled)
+            }
+        }
+    }
+}
+
+// `after`, as far as the turn rate and speed limits let a boid get from `before` over `dt`
+pub fn limit_velocity<V: FlockVector>(settings: &BoidSettings, before: V, after: V, max_speed: f32, dt: f32) -> V {
+    let velocity = limit_turn(before, after, settings.max_turn_rate.to_radians() * dt);
+
+    // Never stalls, and without drag never goes past its top speed either
+    let speed = velocity.length_squared().sqrt();
+    if speed < settings.min_speed {
+        return velocity * (settings.min_speed / speed);
+    }
+    if settings.drag <= 0.0 && speed > max_speed {
+        return velocity * (max_speed / speed);
+    }
+    velocity
+}
+
+// `after`, swung back towards `before` if it turned by more than `max_angle` radians. Starting
+// from rest, or without a limit, any heading goes
+pub fn limit_turn<V: FlockVector>(before: V, after: V, max_angle: f32) -> V {
+    let (before_sq, after_sq) = (before.length_squared(), after.length_squared());
+    if max_angle <= 0.0 || before_sq == 0.0 || after_sq == 0.0 || before.angle_to(after) <= max_angle {
+        return after;
+    }
+    before.turn_towards(after, max_angle) * (after_sq / before_sq).sqrt()
+}
+
+// `travelled`, turned and scaled as the limits turned and scaled the velocity from `after` to
+// `limited`, and no further than `top_speed` allows if there is one
+pub fn limit_travel<V: FlockVector>(travelled: V, after: V, limited: V, top_speed: Option<f32>, dt: f32) -> V {
+    let travelled = if after.length_squared() > 0.0 { travelled.turned_like(after, limited) } else { limited * dt };
+    let distance = travelled.length_squared().sqrt();
+    match top_speed {
+        Some(top_speed) if distance > top_speed * dt => travelled * (top_speed * dt / distance),
+        _ => travelled,
+    }
+}
+
+// One semi-implicit tick of `flight`: thrust and steering from the start of it, the drag
+// solved for at the end so a long tick never overshoots. Without drag, the force is simply
+// applied
+pub fn fly<V: FlockVector>(velocity: V, flight: &Flight<V>, dt: f32) -> V {
+    let Flight { force, mass, drag, .. } = *flight;
+    if drag <= 0.0 {
+        return velocity + force / mass * dt;
+    }
+    let thrust = flight.thrust(velocity);
+
+    // Solving for the speed that, slowed by its own drag over the tick, is what's left
+    let velocity = velocity + (force + thrust) / mass * dt;
+    let (speed, k) = (velocity.length_squared().sqrt(), drag / mass * dt);
+    if speed == 0.0 {
+        return velocity;
+    }
+    velocity * (((1.0 + 4.0 * k * speed).sqrt() - 1.0) / (2.0 * k) / speed)
+}
+
+// Width of the chunks flockmates are processed in, matching glam's SIMD `Vec4`
+const LANES: usize = 4;
+
+// Flockmate candidates for a 2D boid with a component per array, so the three rules run over
+// them four at a time rather than branching on each in turn
+#[derive(Default)]
+pub struct NeighborLanes {
+    offset_x: Vec<f32>,
+    offset_y: Vec<f32>,
+    velocity_x: Vec<f32>,
+    velocity_y: Vec<f32>,
+}
+
+// Each lane's weight under `falloff`, as `Falloff::weight` does one at a time
+fn falloff_weights(falloff: Falloff, distance: Vec4, range: f32) -> Vec4 {
+    let t = if range > 0.0 { (distance / range).clamp(Vec4::ZERO, Vec4::ONE) } else { Vec4::ZERO };
+
+    match falloff {
+        Falloff::Constant => Vec4::ONE,
+        Falloff::Linear => Vec4::ONE - t,
+        Falloff::InverseSquare => {
+            let softened = Vec4::ONE + 3.0 * t;
+            (softened * softened).recip()
+        }
+        Falloff::Smoothstep => Vec4::ONE - t * t * (Vec4::splat(3.0) - 2.0 * t),
+    }
+}
+
+fn lane(values: &[f32], chunk: usize) -> Vec4 {
+    Vec4::from_slice(&values[chunk..chunk + LANES])
+}
+
+fn masked(mask: BVec4A, value: Vec4) -> Vec4 {
+    Vec4::select(mask, value, Vec4::ZERO)
+}
+
+impl NeighborLanes {
+    pub fn clear(&mut self) {
+        self.offset_x.clear();
+        self.offset_y.clear();
+        self.velocity_x.clear();
+        self.velocity_y.clear();
+    }
+
+    pub fn push(&mut self, offset: Vec2, velocity: Vec2) {
+        self.offset_x.push(offset.x);
+        self.offset_y.push(offset.y);
+        self.velocity_x.push(velocity.x);
+        self.velocity_y.push(velocity.y);
+    }
+
+    // Cohesion, alignment and separation over the candidates the boid perceives, matching
+    // `flocking_rules`; those perceived are also copied out to `perceived`
+    pub fn rules(&mut self, settings: &BoidSettings, heading: Vec2, perceived: &mut Vec<Neighbor<Vec2>>) -> RuleTerms<Vec2> {
+        let count = self.offset_x.len();
+
+        // The last chunk is filled out with boids too far off to be seen
+        let far = Vec2::new(settings.vis_range * 2.0 + 1.0, 0.0);
+        while !self.offset_x.len().is_multiple_of(LANES) {
+            self.push(far, Vec2::ZERO);
+        }
+
+        let vis_range_sq = Vec4::splat(settings.vis_range_sq());
+        let prot_range_sq = Vec4::splat(settings.prot_range_sq());
+        // `angle_to(offset) <= fov` without the acos, comparing squared cosines on the right
+        // side of perpendicular
+        let cos_fov = settings.fov.cos();
+        let cos_fov_sq = Vec4::splat(cos_fov * cos_fov);
+        let (heading_x, heading_y) = (Vec4::splat(heading.x), Vec4::splat(heading.y));
+        let distances = [settings.cohesion_falloff, settings.alignment_falloff, settings.separation_falloff]
+            .iter()
+            .any(|falloff| *falloff != Falloff::Constant);
+
+        let (mut cohesion_x, mut cohesion_y) = (Vec4::ZERO, Vec4::ZERO);
+        let (mut alignment_x, mut alignment_y) = (Vec4::ZERO, Vec4::ZERO);
+        let (mut separation_x, mut separation_y) = (Vec4::ZERO, Vec4::ZERO);
+        let (mut flockmates, mut crowding) = (Vec4::ZERO, Vec4::ZERO);
+
+        for chunk in (0..self.offset_x.len()).step_by(LANES) {
+            let (offset_x, offset_y) = (lane(&self.offset_x, chunk), lane(&self.offset_y, chunk));
+            let (velocity_x, velocity_y) = (lane(&self.velocity_x, chunk), lane(&self.velocity_y, chunk));
+
+            let dist_sq = offset_x * offset_x + offset_y * offset_y;
+            let dot = heading_x * offset_x + heading_y * offset_y;
+            let ahead = dot.cmpge(Vec4::ZERO);
+            let within_cos = (dot * dot).cmpge(cos_fov_sq * dist_sq);
+            let in_view = if cos_fov >= 0.0 { ahead & within_cos } else { ahead | !within_cos };
+
+            let seen = dist_sq.cmple(vis_range_sq) & (in_view | dist_sq.cmpeq(Vec4::ZERO));
+            let flocking = seen & dist_sq.cmpge(prot_range_sq);
+            let crowded = seen & dist_sq.cmplt(prot_range_sq);
+
+            // glam has no wide square root, but each lane's is a single instruction
+            let distance = if distances { Vec4::from_array(dist_sq.to_array().map(f32::sqrt)) } else { Vec4::ZERO };
+            let cohesion = masked(flocking, falloff_weights(settings.cohesion_falloff, distance, settings.vis_range));
+            let alignment = masked(flocking, falloff_weights(settings.alignment_falloff, distance, settings.vis_range));
+            let separation = masked(crowded, falloff_weights(settings.separation_falloff, distance, settings.prot_range));
+
+            cohesion_x += offset_x * cohesion;
+            cohesion_y += offset_y * cohesion;
+            alignment_x += velocity_x * alignment;
+            alignment_y += velocity_y * alignment;
+            separation_x -= offset_x * separation;
+            separation_y -= offset_y * separation;
+            flockmates += masked(flocking, Vec4::ONE);
+            crowding += masked(crowded, Vec4::ONE);
+
+            let mut bits = seen.bitmask();
+            while bits != 0 {
+                let i = chunk + bits.trailing_zeros() as usize;
+                bits &= bits - 1;
+                perceived.push(Neighbor {
+                    offset: Vec2::new(self.offset_x[i], self.offset_y[i]),
+                    velocity: Vec2::new(self.velocity_x[i], self.velocity_y[i]),
+                });
+            }
+        }
+
+        self.offset_x.truncate(count);
+        self.offset_y.truncate(count);
+        self.velocity_x.truncate(count);
+        self.velocity_y.truncate(count);
+
+        let average = |x: Vec4, y: Vec4, count: Vec4| {
+            // Summing the lanes
+            let count = count.dot(Vec4::ONE);
+            let sum = Vec2::new(x.dot(Vec4::ONE), y.dot(Vec4::ONE));
+            if count > 0.0 { sum / count } else { sum }
+        };
+
+        RuleTerms {
+            cohesion: average(cohesion_x, cohesion_y, flockmates) * settings.center_factor,
+            alignment: average(alignment_x, alignment_y, flockmates) * settings.matching_factor,
+            separation: average(separation_x, separation_y, crowding) * settings.avoid_factor,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::f32::consts::PI;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    use super::*;
+
+    #[test]
+    fn lanes_match_flocking_rules() {
+        let mut rng = StdRng::seed_from_u64(7);
+        let falloffs = [Falloff::Constant, Falloff::Linear, Falloff::InverseSquare, Falloff::Smoothstep];
+
+        for (i, fov) in [0.6, 1.2, 2.4, 3.0].into_iter().enumerate() {
+            let settings = BoidSettings {
+                fov,
+                cohesion_falloff: falloffs[i],
+                alignment_falloff: falloffs[(i + 1) % 4],
+                separation_falloff: falloffs[(i + 2) % 4],
+                ..default()
+            };
+            let heading = Vec2::from_angle(rng.random_range(-PI..PI));
+
+            // Any count, so the last chunk is padded in all the ways it can be
+            for count in 0..23 {
+                let range = settings.vis_range * 1.5;
+                let neighbors: Vec<_> = (0..count)
+                    .map(|_| (
+                        Vec2::new(rng.random_range(-range..range), rng.random_range(-range..range)),
+                        Vec2::new(rng.random_range(-100.0..100.0), rng.random_range(-100.0..100.0)),
+                    ))
+                    .collect();
+
+                let expected = flocking_rules(&settings, heading, neighbors.iter()
+                    .map(|&(offset, velocity)| Neighbor { offset, velocity }));
+
+                let mut lanes = NeighborLanes::default();
+                for &(offset, velocity) in &neighbors {
+                    lanes.push(offset, velocity);
+                }
+                let mut perceived = vec![];
+                let rules = lanes.rules(&settings, heading, &mut perceived);
+
+                let seen = neighbors.iter().filter(|(offset, _)| perceives(&settings, heading, *offset)).count();
+                assert_eq!(perceived.len(), seen);
+                assert!(rules.cohesion.abs_diff_eq(expected.cohesion, 1e-3));
+                assert!(rules.alignment.abs_diff_eq(expected.alignment, 1e-3));
+                assert!(rules.separation.abs_diff_eq(expected.separation, 1e-3));
+            }
+        }
+    }
+
+    #[test]
+    fn drag_levels_out_at_max_speed() {
+        let (dt, max_speed) = (1.0 / 64.0, 240.0);
+        for (drag, mass) in [(0.01, 1.0), (0.05, 0.7), (0.002, 1.3)] {
+            let mut velocity = Vec2::new(0.0, 50.0);
+            let flight = Flight { force: Vec2::ZERO, mass, max_speed, drag };
+            for _ in 0..(30.0 / dt) as usize {
+                velocity = fly(velocity, &flight, dt);
+            }
+            assert!((velocity.length() - max_speed).abs() < 1.0, "{drag} {mass}: {velocity}");
+            assert!(velocity.x.abs() < 1e-3);
+
+            // And a steady push settles faster, not without bound
+            let flight = Flight { force: Vec2::Y * 600.0, ..flight };
+            let pushed = (0..(30.0 / dt) as usize).fold(velocity, |velocity, _| fly(velocity, &flight, dt));
+            assert!(pushed.length() > max_speed && pushed.length().is_finite());
+        }
+    }
+
+    #[test]
+    fn rk4_tracks_long_ticks_closest() {
+        let flight = Flight { force: Vec2::new(400.0, -150.0), mass: 1.0, max_speed: 240.0, drag: 0.01 };
+        let start = Vec2::new(-60.0, 130.0);
+        let run = |integrator, dt: f32| (0..(1.0 / dt).round() as usize)
+            .fold((start, Vec2::ZERO), |(velocity, position), _| {
+                let (velocity, travelled) = flight.step(integrator, velocity, dt);
+                (velocity, position + travelled)
+            });
+
+        let (_, exact) = run(Integrator::Rk4, 1.0 / 4096.0);
+        let error = |integrator| run(integrator, 1.0 / 8.0).1.distance(exact);
+        let (euler, semi, rk4) = (error(Integrator::Euler), error(Integrator::SemiImplicit), error(Integrator::Rk4));
+        assert!(rk4 < 0.1 && rk4 < semi && rk4 < euler, "{euler} {semi} {rk4}");
+    }
+}
@@ -0,0 +1,200 @@
+use bevy::prelude::*;
+
+use crate::{input::{Action, Actions}, CursorPosition, WorldBounds};
+
+// Side of a flow field cell, in world units
+const FLOW_CELL: f32 = 40.;
+// How far from the cursor painting reaches
+const BRUSH_RADIUS: f32 = 60.;
+
+// Grid of wind directions over the world, each at most unit length, added to every boid's steering
+#[derive(Resource, Clone, Debug)]
+pub struct FlowField {
+    // World position of the bottom-left corner
+    origin: Vec2,
+    cols: usize,
+    rows: usize,
+    vectors: Vec<Vec2>,
+}
+
+impl FlowField {
+    pub fn new(size: Vec2) -> Self {
+        let cols = (size.x / FLOW_CELL).ceil().max(1.0) as usize;
+        let rows = (size.y / FLOW_CELL).ceil().max(1.0) as usize;
+
+        Self {
+            origin: -Vec2::new(cols as f32, rows as f32) * FLOW_CELL / 2.0,
+            cols,
+            rows,
+            vectors: vec![Vec2::ZERO; cols * rows],
+        }
+    }
+
+    // A grid covering `size`, keeping the wind painted in cells that still fit
+    fn resized(&self, size: Vec2) -> Self {
+        let mut field = Self::new(size);
+        for (center, wind) in self.winds() {
+            if let Some(i) = field.index(center) {
+                field.vectors[i] = wind;
+            }
+        }
+        field
+    }
+
+    fn index(&self, position: Vec2) -> Option<usize> {
+        let cell = ((position - self.origin) / FLOW_CELL).floor();
+
+        if cell.x < 0.0 || cell.y < 0.0 || cell.x >= self.cols as f32 || cell.y >= self.rows as f32 {
+            return None;
+        }
+
+        Some(cell.y as usize * self.cols + cell.x as usize)
+    }
+
+    // Wind in the cell containing `position`, zero outside the grid
+    pub fn sample(&self, position: Vec2) -> Vec2 {
+        self.index(position).map_or(Vec2::ZERO, |i| self.vectors[i])
+    }
+
+    // Set every cell whose center lies within `radius` of `position`
+    pub fn paint(&mut self, position: Vec2, radius: f32, wind: Vec2) {
+        let wind = wind.clamp_length_max(1.0);
+
+        for i in 0..self.vectors.len() {
+            if self.center(i).distance(position) <= radius {
+                self.vectors[i] = wind;
+            }
+        }
+    }
+
+    // Cell centers with their wind, skipping calm cells
+    pub fn winds(&self) -> impl Iterator<Item = (Vec2, Vec2)> + '_ {
+        (0..self.vectors.len())
+            .filter(|i| self.vectors[*i] != Vec2::ZERO)
+            .map(|i| (self.center(i), self.vectors[i]))
+    }
+
+    fn center(&self, i: usize) -> Vec2 {
+        let cell = Vec2::new((i % self.cols) as f32, (i / self.cols) as f32);
+        self.origin + (cell + 0.5) * FLOW_CELL
+    }
+}
+
+impl FromWorld for FlowField {
+    fn from_world(world: &mut World) -> Self {
+        Self::new(**world.resource::<WorldBounds>())
+    }
+}
+
+// Layers of swirling cells summed into the turbulence's stream function: each turned by an
+// angle, with cells `1 / wavenumber` of `wind_scale` across, drifting at its own rate
+const TURBULENCE_LAYERS: [(f32, f32, f32, f32); 3] = [
+    // angle, wavenumber, amplitude, rate
+    (0.0, 1.0, 0.5, 1.0),
+    (1.1, 2.1, 0.2, 1.7),
+    (2.3, 4.3, 0.08, 2.9),
+];
+
+fn stream(p: Vec2, t: f32) -> f32 {
+    TURBULENCE_LAYERS.iter()
+        .map(|&(angle, wavenumber, amplitude, rate)| {
+            let q = Vec2::from_angle(angle).rotate(p) * wavenumber;
+            amplitude * (q.x + t * rate).sin() * (q.y - t * rate * 0.6 + angle).sin()
+        })
+        .sum()
+}
+
+// Wind that sways the whole world, apart from anything painted: the curl of a slowly changing
+// noise, so it swirls without ever gathering boids together or spreading them apart. Features
+// are around `scale` world units across, and at most about unit length
+pub fn turbulence(position: Vec2, time: f32, scale: f32) -> Vec2 {
+    const EPS: f32 = 0.01;
+    let p = position / scale.max(f32::EPSILON);
+    let d = |offset: Vec2| stream(p + offset, time);
+
+    let gradient = Vec2::new(d(Vec2::X * EPS) - d(-Vec2::X * EPS), d(Vec2::Y * EPS) - d(-Vec2::Y * EPS)) / (2.0 * EPS);
+    Vec2::new(gradient.y, -gradient.x)
+}
+
+#[derive(Resource, Default)]
+pub struct PaintingWind(pub bool);
+
+// Regrow the field to cover the play area as the window is resized
+pub fn resize_flow_field(bounds: Res<WorldBounds>, mut field: ResMut<FlowField>) {
+    if bounds.is_changed() && !bounds.is_added() {
+        *field = field.resized(**bounds);
+    }
+}
+
+// `G` toggles paint wind mode: left-drag paints along the drag, right-drag calms
+pub struct FlowPaintPlugin;
+
+impl Plugin for FlowPaintPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PaintingWind>()
+            .add_systems(Update, (paint_wind_system, draw_flow_field));
+    }
+}
+
+fn paint_wind_system(
+    actions: Actions,
+    cursor: Res<CursorPosition>,
+    mut painting: ResMut<PaintingWind>,
+    mut field: ResMut<FlowField>,
+    mut last: Local<Option<Vec2>>,
+) {
+    if actions.just_pressed(Action::PaintWind) {
+        painting.0 = !painting.0;
+        info!("paint wind mode {}", if painting.0 { "on" } else { "off" });
+    }
+
+    let previous = last.take();
+
+    let Some(position) = cursor.0.filter(|_| painting.0) else {
+        return;
+    };
+
+    if actions.pressed(Action::Repel) {
+        field.paint(position, BRUSH_RADIUS, Vec2::ZERO);
+    } else if actions.pressed(Action::Select) {
+        // Drag direction sets the wind, faster strokes don't make it stronger
+        if let Some(wind) = previous.and_then(|previous| (position - previous).try_normalize()) {
+            field.paint(position, BRUSH_RADIUS, wind);
+        }
+        *last = Some(position);
+    }
+}
+
+fn draw_flow_field(
+    field: Res<FlowField>,
+    painting: Res<PaintingWind>,
+    cursor: Res<CursorPosition>,
+    mut gizmos: Gizmos,
+) {
+    for (center, wind) in field.winds() {
+        let half = wind * FLOW_CELL * 0.4;
+        gizmos.arrow_2d(center - half, center + half, Color::CYAN);
+    }
+
+    if let Some(position) = cursor.0.filter(|_| painting.0) {
+        gizmos.circle_2d(position, BRUSH_RADIUS, Color::CYAN.with_a(0.3));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn turbulence_neither_gathers_nor_spreads() {
+        const H: f32 = 0.5;
+        let wind = |p: Vec2| turbulence(p, 3.7, 400.);
+
+        for p in [Vec2::ZERO, Vec2::new(130., -75.), Vec2::new(-410., 260.)] {
+            assert!(wind(p).length() > 0.0);
+            let divergence = (wind(p + Vec2::X * H).x - wind(p - Vec2::X * H).x
+                + wind(p + Vec2::Y * H).y - wind(p - Vec2::Y * H).y) / (2.0 * H);
+            assert!(divergence.abs() < 1e-4, "{divergence} at {p}");
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use rand::prelude::*;
+use bevy::{
+    ecs::system::SystemParam,
+    math::Vec3Swizzles,
+    prelude::*,
+    utils::HashSet,
+};
+use bevy_spatial::{kdtree::KDTree2, SpatialAccess};
+
+use crate::{
+    energy::Energy,
+    input::{Action, Actions},
+    rng::SimRng,
+    settings::BoidSettings,
+    spatial::rebuild_tree2,
+    BoidSet,
+    CursorPosition,
+    SpatialEntity,
+    WorldBounds,
+};
+
+// Boids above this much hunger look for food
+const HUNGRY: f32 = 0.5;
+
+// Hungry or tired boids look for food
+fn wants_food(hunger: &Hunger, energy: Option<&Energy>) -> bool {
+    hunger.0 >= HUNGRY || energy.is_some_and(Energy::tired)
+}
+
+// Eaten by the first boid to touch it
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
+pub struct Food;
+
+// 0 just fed, 1 starving; rises at `hunger_rate` per second
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
+pub struct Hunger(pub f32);
+
+#[derive(Resource)]
+struct FoodTimer(Timer);
+
+impl FromWorld for FoodTimer {
+    fn from_world(world: &mut World) -> Self {
+        let interval = world.resource::<BoidSettings>().food_interval;
+        Self(Timer::from_seconds(interval.max(0.01), TimerMode::Repeating))
+    }
+}
+
+// Food pellets appear every `food_interval` seconds, hungry boids steer for the nearest one
+pub struct FoodPlugin;
+
+impl Plugin for FoodPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<KDTree2<Food>>()
+            .init_resource::<FoodTimer>()
+            .register_type::<Food>()
+            .register_type::<Hunger>()
+            .add_systems(FixedUpdate, (
+                rebuild_tree2::<Food>.in_set(BoidSet::Index),
+                respawn_food.in_set(BoidSet::Index),
+                (hunger_system, eat_system).chain().in_set(BoidSet::Movement),
+            ));
+    }
+}
+
+fn spawn_food(commands: &mut Commands, position: Vec2) {
+    commands.spawn((
+        Food,
+        TransformBundle::from_transform(Transform::from_translation(position.extend(0.0))),
+    ));
+}
+
+fn respawn_food(
+    mut commands: Commands,
+    mut timer: ResMut<FoodTimer>,
+    mut rng: ResMut<SimRng>,
+    food: Query<(), With<Food>>,
+    bounds: Res<WorldBounds>,
+    time: Res<Time>,
+    settings: Res<BoidSettings>,
+) {
+    let interval = std::time::Duration::from_secs_f32(settings.food_interval.max(0.01));
+    if timer.0.duration() != interval {
+        timer.0.set_duration(interval);
+    }
+
+    if !timer.0.tick(time.delta()).just_finished() || food.iter().len() >= settings.food_max {
+        return;
+    }
+
+    let half = **bounds / 2.0;
+    let position = Vec2::new(rng.random_range(-half.x..half.x),
+                             rng.random_range(-half.y..half.y));
+    spawn_food(&mut commands, position);
+}
+
+fn hunger_system(
+    mut boids: Query<&mut Hunger>,
+    time: Res<Time>,
+    settings: Res<BoidSettings>,
+) {
+    for mut hunger in boids.iter_mut() {
+        hunger.0 = (hunger.0 + settings.hunger_rate * time.delta_seconds()).min(1.0);
+    }
+}
+
+fn eat_system(
+    mut commands: Commands,
+    mut boids: Query<(&Transform, &mut Hunger, Option<&mut Energy>), With<SpatialEntity>>,
+    tree: Res<KDTree2<Food>>,
+    food: Query<(), With<Food>>,
+    settings: Res<BoidSettings>,
+    mut eaten: Local<HashSet<Entity>>,
+) {
+    eaten.clear();
+
+    for (transform, mut hunger, energy) in boids.iter_mut() {
+        if !wants_food(&hunger, energy.as_deref()) {
+            continue;
+        }
+
+        // The tree can still hold pellets eaten since its last rebuild, earlier this tick included
+        let pellet = tree.within_distance(transform.translation.xy(), settings.size)
+            .into_iter()
+            .filter_map(|(_, entity)| entity)
+            .find(|entity| food.contains(*entity) && !eaten.contains(entity));
+
+        if let Some(pellet) = pellet {
+            commands.entity(pellet).despawn();
+            eaten.insert(pellet);
+            hunger.0 = 0.0;
+            if let Some(mut energy) = energy {
+                energy.0 = 1.0;
+            }
+        }
+    }
+}
+
+// Read access to food for the flocking pass
+#[derive(SystemParam)]
+pub struct Foraging<'w, 's> {
+    tree: Res<'w, KDTree2<Food>>,
+    hunger: Query<'w, 's, (&'static Hunger, Option<&'static Energy>)>,
+}
+
+impl Foraging<'_, '_> {
+    // Steering towards the nearest pellet in vision range, for hungry or tired boids only
+    pub fn wants_food(&self, boid: Entity) -> bool {
+        self.hunger.get(boid).is_ok_and(|(hunger, energy)| wants_food(hunger, energy))
+    }
+
+    pub fn in_sight(&self, settings: &BoidSettings, position: Vec2) -> bool {
+        self.tree.nearest_neighbour(position).is_some_and(|(pellet, _)| pellet.distance(position) <= settings.vis_range)
+    }
+
+    pub fn steering(&self, settings: &BoidSettings, boid: Entity, position: Vec2) -> Vec2 {
+        if !self.hunger.get(boid).is_ok_and(|(hunger, energy)| wants_food(hunger, energy)) {
+            return Vec2::ZERO;
+        }
+
+        match self.tree.nearest_neighbour(position) {
+            Some((pellet, _)) if pellet.distance(position) <= settings.vis_range => {
+                (pellet - position).normalize_or_zero() * settings.forage_factor
+            }
+            _ => Vec2::ZERO,
+        }
+    }
+}
+
+// `F` drops a food pellet at the cursor
+pub struct FoodViewPlugin;
+
+impl Plugin for FoodViewPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (place_food, draw_food));
+    }
+}
+
+fn place_food(
+    mut commands: Commands,
+    actions: Actions,
+    cursor: Res<CursorPosition>,
+) {
+    if let Some(position) = cursor.0.filter(|_| actions.just_pressed(Action::Food)) {
+        spawn_food(&mut commands, position);
+    }
+}
+
+fn draw_food(
+    food: Query<&Transform, With<Food>>,
+    mut gizmos: Gizmos,
+) {
+    for transform in food.iter() {
+        gizmos.circle_2d(transform.translation.xy(), 3.0, Color::YELLOW_GREEN);
+    }
+}
@@ -0,0 +1,292 @@
+use bevy::{math::Vec3Swizzles, prelude::*};
+use rand::Rng;
+
+use crate::{
+    input::{Action, Actions},
+    rng::SimRng,
+    settings::BoidSettings,
+    spawn_boid,
+    species::{Species, SHADES},
+    AnyBoid,
+    BoidSet,
+    BoidSpawn,
+    SpatialEntity,
+    WorldBounds,
+};
+
+const TEAMS: usize = 2;
+
+// A team's home, worth a point per second for each of its boids inside
+#[derive(Component, Clone, Copy, Debug, Reflect)]
+#[reflect(Component)]
+pub struct CaptureZone {
+    pub team: u8,
+}
+
+// Capture-the-zone: two flocks, a zone each, and a round of `round_length` seconds to herd as
+// many of each into their own zone as possible
+#[derive(Resource, Default, Reflect)]
+#[reflect(Resource)]
+pub struct CaptureGame {
+    // Zones are out, from the first round until the game is left
+    pub active: bool,
+    // Seconds of the round left, 0 once it's over
+    pub time_left: f32,
+    pub scores: [f32; TEAMS],
+    // Species there were before the game split the flock into teams, put back on leaving
+    pub species_count: u8,
+}
+
+impl CaptureGame {
+    pub fn playing(&self) -> bool {
+        self.active && self.time_left > 0.0
+    }
+
+    fn start(&mut self, round_length: f32, species_count: u8) {
+        *self = Self { active: true, time_left: round_length.max(1.0), species_count, ..default() };
+    }
+
+    fn leave(&mut self, settings: &mut BoidSettings) {
+        settings.species_count = self.species_count;
+        *self = default();
+    }
+
+    // Points for `inside` boids of each team over `dt` seconds, cut short when the round ends
+    fn score(&mut self, inside: [usize; TEAMS], dt: f32) {
+        let dt = dt.min(self.time_left);
+        for (score, inside) in self.scores.iter_mut().zip(inside) {
+            *score += inside as f32 * dt;
+        }
+        self.time_left -= dt;
+    }
+
+    // The team ahead, None on a tie
+    fn leader(&self) -> Option<usize> {
+        let [a, b] = self.scores;
+        (a != b).then_some(if a > b { 0 } else { 1 })
+    }
+}
+
+pub fn team_color(team: u8) -> Color {
+    Species(team).color(TEAMS as u8, SHADES - 1)
+}
+
+// Scores a round once `start_round` has set one going, headless or not
+pub struct GamePlugin;
+
+impl Plugin for GamePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CaptureGame>()
+            .register_type::<CaptureGame>()
+            .register_type::<CaptureZone>()
+            .add_systems(FixedUpdate, score_zones.after(BoidSet::Movement).run_if(round_playing));
+    }
+}
+
+fn round_playing(game: Res<CaptureGame>) -> bool {
+    game.playing()
+}
+
+fn score_zones(
+    mut game: ResMut<CaptureGame>,
+    zones: Query<(&Transform, &CaptureZone)>,
+    boids: Query<(&Transform, &Species), With<SpatialEntity>>,
+    settings: Res<BoidSettings>,
+    time: Res<Time>,
+) {
+    let radius_sq = settings.zone_radius * settings.zone_radius;
+    let mut inside = [0; TEAMS];
+
+    for (zone, CaptureZone { team }) in zones.iter() {
+        let center = zone.translation.xy();
+        inside[*team as usize] += boids.iter()
+            .filter(|(boid, species)| species.0 == *team && boid.translation.xy().distance_squared(center) <= radius_sq)
+            .count();
+    }
+
+    game.score(inside, time.delta_seconds());
+    if !game.playing() {
+        let [a, b] = game.scores;
+        match game.leader() {
+            Some(team) => info!("round over, team {} wins {a:.0} to {b:.0}", team + 1),
+            None => info!("round over, a draw at {a:.0} each"),
+        }
+    }
+}
+
+// The flock respawned as two teams spread over the world, and a zone for each a quarter of
+// the way in from either side
+pub fn start_round(
+    commands: &mut Commands,
+    rng: &mut SimRng,
+    game: &mut CaptureGame,
+    settings: &mut BoidSettings,
+    bounds: Vec2,
+    boids: impl Iterator<Item = Entity>,
+    zones: impl Iterator<Item = Entity>,
+) {
+    for entity in boids.chain(zones) {
+        commands.entity(entity).despawn();
+    }
+
+    // Only the first round takes the count over, later ones keep what it was before that
+    let species_count = std::mem::replace(&mut settings.species_count, TEAMS as u8);
+    let species_count = if game.active { game.species_count } else { species_count };
+    let half = bounds / 2.0;
+    for _ in 0..settings.count {
+        let position = Vec2::new(rng.random_range(-half.x..half.x), rng.random_range(-half.y..half.y));
+        spawn_boid(commands, settings, BoidSpawn::random(&mut **rng, settings, position));
+    }
+
+    for team in 0..TEAMS as u8 {
+        let x = if team == 0 { -bounds.x / 4.0 } else { bounds.x / 4.0 };
+        commands.spawn((CaptureZone { team }, TransformBundle::from_transform(Transform::from_xyz(x, 0.0, 0.0))));
+    }
+
+    game.start(settings.round_length, species_count);
+}
+
+#[derive(Component)]
+struct ScoreHud;
+
+#[derive(Component)]
+struct ScoreText;
+
+// `Z` starts a round, and pressed again, mid-round or once it's over, leaves the game
+pub struct GameViewPlugin;
+
+impl Plugin for GameViewPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_score_hud)
+            .add_systems(Update, (toggle_game, draw_zones, update_score_hud).chain());
+    }
+}
+
+fn spawn_score_hud(mut commands: Commands) {
+    let style = |color| TextStyle { font_size: 24.0, color, ..default() };
+
+    // Centred across the top of the window
+    commands.spawn((
+        NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                top: Val::Px(8.0),
+                width: Val::Percent(100.0),
+                justify_content: JustifyContent::Center,
+                ..default()
+            },
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        ScoreHud,
+    ))
+    .with_children(|hud| {
+        hud.spawn((
+            TextBundle::from_sections([
+                TextSection::new("", style(team_color(0))),
+                TextSection::new("", style(Color::WHITE)),
+                TextSection::new("", style(team_color(1))),
+            ]),
+            ScoreText,
+        ));
+    });
+}
+
+#[allow(clippy::too_many_arguments)]
+fn toggle_game(
+    mut commands: Commands,
+    actions: Actions,
+    mut rng: ResMut<SimRng>,
+    mut game: ResMut<CaptureGame>,
+    mut settings: ResMut<BoidSettings>,
+    bounds: Res<WorldBounds>,
+    boids: Query<Entity, AnyBoid>,
+    zones: Query<Entity, With<CaptureZone>>,
+) {
+    if !actions.just_pressed(Action::Game) {
+        return;
+    }
+
+    if game.active {
+        for zone in zones.iter() {
+            commands.entity(zone).despawn();
+        }
+        game.leave(&mut settings);
+        info!("left capture-the-zone");
+        return;
+    }
+
+    // Respawned here, so the count system has nothing to make up
+    let settings = settings.bypass_change_detection();
+    start_round(&mut commands, &mut rng, &mut game, settings, **bounds, boids.iter(), zones.iter());
+    info!("capture-the-zone: {:.0} seconds to herd each team into its zone", settings.round_length);
+}
+
+fn draw_zones(zones: Query<(&Transform, &CaptureZone)>, settings: Res<BoidSettings>, mut gizmos: Gizmos) {
+    for (transform, zone) in zones.iter() {
+        gizmos.circle_2d(transform.translation.xy(), settings.zone_radius, team_color(zone.team));
+    }
+}
+
+fn update_score_hud(
+    game: Res<CaptureGame>,
+    mut hud: Query<&mut Visibility, With<ScoreHud>>,
+    mut text: Query<&mut Text, With<ScoreText>>,
+) {
+    if !game.is_changed() {
+        return;
+    }
+
+    for mut visibility in hud.iter_mut() {
+        *visibility = if game.active { Visibility::Visible } else { Visibility::Hidden };
+    }
+
+    let [a, b] = game.scores;
+    let middle = if game.playing() {
+        format!("  {:.0}s  ", game.time_left.ceil())
+    } else {
+        match game.leader() {
+            Some(team) => format!("  team {} wins, Z to leave  ", team + 1),
+            None => "  draw, Z to leave  ".into(),
+        }
+    };
+    for mut text in text.iter_mut() {
+        text.sections[0].value = format!("{a:.0}");
+        text.sections[1].value = middle.clone();
+        text.sections[2].value = format!("{b:.0}");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rounds_score_until_time_runs_out() {
+        let mut game = CaptureGame::default();
+        game.start(10.0, 3);
+        assert!(game.playing());
+
+        game.score([3, 1], 4.0);
+        assert_eq!(game.scores, [12.0, 4.0]);
+        assert_eq!(game.leader(), Some(0));
+
+        // Only the last 6 seconds count
+        game.score([0, 5], 8.0);
+        assert_eq!(game.scores, [12.0, 34.0]);
+        assert!(!game.playing() && game.active);
+        assert_eq!(game.leader(), Some(1));
+    }
+
+    #[test]
+    fn leaving_puts_the_species_back() {
+        let mut settings = BoidSettings { species_count: 3, ..default() };
+        let mut game = CaptureGame::default();
+        game.start(10.0, settings.species_count);
+        settings.species_count = TEAMS as u8;
+
+        game.leave(&mut settings);
+        assert_eq!(settings.species_count, 3);
+        assert!(!game.active);
+    }
+}
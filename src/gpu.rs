@@ -0,0 +1,406 @@
+use std::borrow::Cow;
+use bevy::{
+    prelude::*,
+    render::{
+        extract_resource::{ExtractResource, ExtractResourcePlugin},
+        render_asset::{RenderAssetUsages, RenderAssets},
+        render_graph::{self, RenderGraph, RenderLabel},
+        render_resource::{binding_types::*, *},
+        renderer::{RenderContext, RenderDevice, RenderQueue},
+        texture::ImageSampler,
+        Render,
+        RenderApp,
+        RenderSet,
+    },
+};
+
+use crate::{
+    cli::Cli,
+    rng::SimRng,
+    settings::BoidSettings,
+    simulation::SimulationState,
+    WINDOW_BOUNDS,
+};
+
+const SHADER: &str = "shaders/gpu_boids.wgsl";
+const DEFAULT_COUNT: usize = 100_000;
+// Matches `@workgroup_size` in the shader
+const WORKGROUP: u32 = 64;
+const IMAGE_WORKGROUP: u32 = 8;
+// Smallest grid cell, which bounds how many cells the buffers need
+const MIN_CELL: f32 = 10.;
+
+// Flocking run entirely in compute shaders; boids never leave the GPU, they're drawn
+// straight into a storage texture shown as a sprite
+pub struct GpuBoidsPlugin {
+    pub count: usize,
+}
+
+impl GpuBoidsPlugin {
+    // `--gpu [--boids <n>]`
+    pub fn from_cli(cli: &Cli) -> Self {
+        Self { count: cli.boid_count().unwrap_or(DEFAULT_COUNT) }
+    }
+}
+
+impl Plugin for GpuBoidsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(GpuParams { count: self.count as u32, ..default() })
+            .add_plugins((
+                ExtractResourcePlugin::<GpuBoidsImage>::default(),
+                ExtractResourcePlugin::<GpuParams>::default(),
+            ))
+            .add_systems(Startup, setup)
+            .add_systems(Update, update_params);
+
+        let render_app = app.sub_app_mut(RenderApp);
+        render_app.add_systems(Render, prepare_bind_groups.in_set(RenderSet::PrepareBindGroups));
+
+        let mut graph = render_app.world.resource_mut::<RenderGraph>();
+        graph.add_node(GpuBoidsLabel, GpuBoidsNode::default());
+        graph.add_node_edge(GpuBoidsLabel, bevy::render::graph::CameraDriverLabel);
+    }
+
+    fn finish(&self, app: &mut App) {
+        let render_app = app.sub_app_mut(RenderApp);
+        let device = render_app.world.resource::<RenderDevice>().clone();
+
+        render_app.insert_resource(GpuBoidsBuffers::new(&device, self.count))
+            .init_resource::<GpuBoidsPipeline>();
+    }
+}
+
+// Mirrors `Params` in the shader. Its own module, as the derive's generated field
+// checks count as dead code
+#[allow(dead_code)]
+mod params {
+    use bevy::{prelude::*, render::{extract_resource::ExtractResource, render_resource::ShaderType}};
+
+    #[derive(Resource, Clone, Copy, Default, ExtractResource, ShaderType)]
+    pub struct GpuParams {
+        pub count: u32,
+        pub cols: u32,
+        pub rows: u32,
+        pub seed: u32,
+        pub bounds: Vec2,
+        pub cell: f32,
+        pub dt: f32,
+        pub vis_range: f32,
+        pub prot_range: f32,
+        pub fov: f32,
+        pub center_factor: f32,
+        pub matching_factor: f32,
+        pub avoid_factor: f32,
+        pub turn_factor: f32,
+        pub boundary_size: f32,
+        pub min_speed: f32,
+        pub max_speed: f32,
+    }
+}
+
+use params::GpuParams;
+
+#[derive(Resource, Clone, ExtractResource)]
+struct GpuBoidsImage(Handle<Image>);
+
+fn setup(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let mut image = Image::new_fill(
+        Extent3d {
+            width: WINDOW_BOUNDS.x as u32,
+            height: WINDOW_BOUNDS.y as u32,
+            depth_or_array_layers: 1,
+        },
+        TextureDimension::D2,
+        &[0, 0, 0, 255],
+        TextureFormat::Rgba8Unorm,
+        RenderAssetUsages::RENDER_WORLD,
+    );
+    image.texture_descriptor.usage =
+        TextureUsages::COPY_DST | TextureUsages::STORAGE_BINDING | TextureUsages::TEXTURE_BINDING;
+    image.sampler = ImageSampler::nearest();
+
+    let image = images.add(image);
+
+    commands.spawn(Camera2dBundle::default());
+    commands.spawn(SpriteBundle {
+        texture: image.clone(),
+        ..default()
+    });
+    commands.insert_resource(GpuBoidsImage(image));
+}
+
+fn update_params(
+    mut params: ResMut<GpuParams>,
+    settings: Res<BoidSettings>,
+    state: Res<SimulationState>,
+    rng: Res<SimRng>,
+    time: Res<Time>,
+) {
+    let cell = settings.vis_range.max(MIN_CELL);
+
+    // Paused boids hold still, a single step advances one fixed tick
+    let dt = if !state.paused {
+        time.delta_seconds().min(1.0 / 30.0)
+    } else if state.step {
+        1.0 / 60.0
+    } else {
+        0.0
+    };
+
+    *params = GpuParams {
+        count: params.count,
+        cols: (WINDOW_BOUNDS.x / cell).ceil() as u32,
+        rows: (WINDOW_BOUNDS.y / cell).ceil() as u32,
+        seed: rng.seed as u32,
+        bounds: WINDOW_BOUNDS,
+        cell,
+        dt,
+        vis_range: settings.vis_range,
+        prot_range: settings.prot_range,
+        fov: settings.fov,
+        center_factor: settings.center_factor,
+        matching_factor: settings.matching_factor,
+        avoid_factor: settings.avoid_factor,
+        turn_factor: settings.turn_factor,
+        boundary_size: settings.boundary_size,
+        min_speed: settings.min_speed,
+        max_speed: settings.max_speed,
+    };
+}
+
+// Boid state ping-pongs between two buffers, each frame reading one and writing the other
+#[derive(Resource)]
+struct GpuBoidsBuffers {
+    params: UniformBuffer<GpuParams>,
+    boids: [Buffer; 2],
+    cell_counts: Buffer,
+    cell_starts: Buffer,
+    sorted: Buffer,
+}
+
+impl GpuBoidsBuffers {
+    fn new(device: &RenderDevice, count: usize) -> Self {
+        let cells = ((WINDOW_BOUNDS.x / MIN_CELL).ceil() * (WINDOW_BOUNDS.y / MIN_CELL).ceil()) as u64;
+        let buffer = |label: &str, size: u64| device.create_buffer(&BufferDescriptor {
+            label: Some(label),
+            size,
+            usage: BufferUsages::STORAGE,
+            mapped_at_creation: false,
+        });
+
+        // Position and velocity, two vec2<f32>s per boid
+        let boid_size = 16 * count.max(1) as u64;
+
+        Self {
+            params: UniformBuffer::default(),
+            boids: [buffer("gpu_boids_a", boid_size), buffer("gpu_boids_b", boid_size)],
+            cell_counts: buffer("gpu_boids_cell_counts", 4 * cells),
+            cell_starts: buffer("gpu_boids_cell_starts", 4 * (cells + 1)),
+            sorted: buffer("gpu_boids_sorted", 4 * count.max(1) as u64),
+        }
+    }
+}
+
+#[derive(Resource)]
+struct GpuBoidsPipeline {
+    layout: BindGroupLayout,
+    init: CachedComputePipelineId,
+    clear_cells: CachedComputePipelineId,
+    count_cells: CachedComputePipelineId,
+    prefix_sum: CachedComputePipelineId,
+    scatter: CachedComputePipelineId,
+    simulate: CachedComputePipelineId,
+    clear_image: CachedComputePipelineId,
+    draw: CachedComputePipelineId,
+}
+
+impl GpuBoidsPipeline {
+    fn all(&self) -> [CachedComputePipelineId; 8] {
+        [
+            self.init,
+            self.clear_cells,
+            self.count_cells,
+            self.prefix_sum,
+            self.scatter,
+            self.simulate,
+            self.clear_image,
+            self.draw,
+        ]
+    }
+}
+
+impl FromWorld for GpuBoidsPipeline {
+    fn from_world(world: &mut World) -> Self {
+        let layout = world.resource::<RenderDevice>().create_bind_group_layout(
+            "gpu_boids_layout",
+            &BindGroupLayoutEntries::sequential(ShaderStages::COMPUTE, (
+                uniform_buffer::<GpuParams>(false),
+                storage_buffer_read_only_sized(false, None),
+                storage_buffer_sized(false, None),
+                storage_buffer_sized(false, None),
+                storage_buffer_sized(false, None),
+                storage_buffer_sized(false, None),
+                texture_storage_2d(TextureFormat::Rgba8Unorm, StorageTextureAccess::WriteOnly),
+            )),
+        );
+        let shader = world.resource::<AssetServer>().load(SHADER);
+        let cache = world.resource::<PipelineCache>();
+
+        let queue = |entry_point: &'static str| {
+            cache.queue_compute_pipeline(ComputePipelineDescriptor {
+                label: Some(Cow::Owned(format!("gpu_boids_{entry_point}"))),
+                layout: vec![layout.clone()],
+                push_constant_ranges: vec![],
+                shader: shader.clone(),
+                shader_defs: vec![],
+                entry_point: Cow::Borrowed(entry_point),
+            })
+        };
+
+        Self {
+            init: queue("init"),
+            clear_cells: queue("clear_cells"),
+            count_cells: queue("count_cells"),
+            prefix_sum: queue("prefix_sum"),
+            scatter: queue("scatter"),
+            simulate: queue("simulate"),
+            clear_image: queue("clear_image"),
+            draw: queue("draw"),
+            layout,
+        }
+    }
+}
+
+// Reading buffer `i` and writing the other, for i in 0..2
+#[derive(Resource)]
+struct GpuBoidsBindGroups([BindGroup; 2]);
+
+#[allow(clippy::too_many_arguments)]
+fn prepare_bind_groups(
+    mut commands: Commands,
+    mut buffers: ResMut<GpuBoidsBuffers>,
+    pipeline: Res<GpuBoidsPipeline>,
+    image: Res<GpuBoidsImage>,
+    params: Res<GpuParams>,
+    gpu_images: Res<RenderAssets<Image>>,
+    device: Res<RenderDevice>,
+    queue: Res<RenderQueue>,
+) {
+    let Some(image) = gpu_images.get(&image.0) else {
+        return;
+    };
+
+    buffers.params.set(*params);
+    buffers.params.write_buffer(&device, &queue);
+
+    let buffers = &*buffers;
+    let Some(params) = buffers.params.binding() else {
+        return;
+    };
+
+    let bind_group = |i: usize| device.create_bind_group(
+        "gpu_boids_bind_group",
+        &pipeline.layout,
+        &BindGroupEntries::sequential((
+            params.clone(),
+            buffers.boids[i].as_entire_binding(),
+            buffers.boids[1 - i].as_entire_binding(),
+            buffers.cell_counts.as_entire_binding(),
+            buffers.cell_starts.as_entire_binding(),
+            buffers.sorted.as_entire_binding(),
+            &image.texture_view,
+        )),
+    );
+
+    commands.insert_resource(GpuBoidsBindGroups([bind_group(0), bind_group(1)]));
+}
+
+#[derive(Debug, Hash, PartialEq, Eq, Clone, RenderLabel)]
+struct GpuBoidsLabel;
+
+#[derive(Default)]
+enum GpuBoidsState {
+    #[default]
+    Loading,
+    // Scatter the flock, writing the buffer the first frame reads
+    Init,
+    Running(usize),
+}
+
+#[derive(Default)]
+struct GpuBoidsNode {
+    state: GpuBoidsState,
+}
+
+impl render_graph::Node for GpuBoidsNode {
+    fn update(&mut self, world: &mut World) {
+        let pipeline = world.resource::<GpuBoidsPipeline>();
+        let cache = world.resource::<PipelineCache>();
+
+        self.state = match self.state {
+            GpuBoidsState::Loading => {
+                for id in pipeline.all() {
+                    if let CachedPipelineState::Err(err) = cache.get_compute_pipeline_state(id) {
+                        panic!("failed to compile {SHADER}: {err}");
+                    }
+                }
+
+                let ready = pipeline.all().into_iter().all(|id| cache.get_compute_pipeline(id).is_some());
+                if ready { GpuBoidsState::Init } else { GpuBoidsState::Loading }
+            }
+            GpuBoidsState::Init => GpuBoidsState::Running(1),
+            GpuBoidsState::Running(frame) => GpuBoidsState::Running(frame + 1),
+        };
+    }
+
+    fn run(
+        &self,
+        _graph: &mut render_graph::RenderGraphContext,
+        render_context: &mut RenderContext,
+        world: &World,
+    ) -> Result<(), render_graph::NodeRunError> {
+        let Some(bind_groups) = world.get_resource::<GpuBoidsBindGroups>() else {
+            return Ok(());
+        };
+        let pipeline = world.resource::<GpuBoidsPipeline>();
+        let cache = world.resource::<PipelineCache>();
+        let params = world.resource::<GpuParams>();
+
+        let (bind_group, passes) = match self.state {
+            GpuBoidsState::Loading => return Ok(()),
+            GpuBoidsState::Init => (&bind_groups.0[0], vec![pipeline.init]),
+            GpuBoidsState::Running(frame) => (&bind_groups.0[frame % 2], vec![
+                pipeline.clear_cells,
+                pipeline.count_cells,
+                pipeline.prefix_sum,
+                pipeline.scatter,
+                pipeline.simulate,
+            ]),
+        };
+
+        let boids = params.count.div_ceil(WORKGROUP);
+        let cells = (params.cols * params.rows).div_ceil(WORKGROUP);
+        let image = (WINDOW_BOUNDS / IMAGE_WORKGROUP as f32).ceil().as_uvec2();
+
+        let mut pass = render_context
+            .command_encoder()
+            .begin_compute_pass(&ComputePassDescriptor::default());
+        pass.set_bind_group(0, bind_group, &[]);
+
+        for id in passes.into_iter().chain([pipeline.clear_image, pipeline.draw]) {
+            let Some(compute) = cache.get_compute_pipeline(id) else {
+                return Ok(());
+            };
+            pass.set_pipeline(compute);
+
+            match id {
+                id if id == pipeline.prefix_sum => pass.dispatch_workgroups(1, 1, 1),
+                id if id == pipeline.clear_cells => pass.dispatch_workgroups(cells, 1, 1),
+                id if id == pipeline.clear_image => pass.dispatch_workgroups(image.x, image.y, 1),
+                _ => pass.dispatch_workgroups(boids, 1, 1),
+            }
+        }
+
+        Ok(())
+    }
+}
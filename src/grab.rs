@@ -0,0 +1,54 @@
+use bevy::{math::Vec3Swizzles, prelude::*};
+
+use crate::{angle_towards, settings::BoidSettings, BoidSet, Velocity};
+
+// Share of each tick's drag velocity a held boid takes on, smoothing out jittery mouse motion
+const FLING_SMOOTHING: f32 = 0.5;
+// Fastest a boid can be flung, as a multiple of `max_speed`
+const MAX_FLING: f32 = 4.0;
+
+// Held by the mouse: out of the flocking and flight systems, just carried to `Grab::target`
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
+pub struct Held;
+
+// Where the held boid is being dragged to, in world space
+#[derive(Resource, Default)]
+pub struct Grab {
+    pub target: Vec2,
+}
+
+// Carries held boids, which leave with the velocity they were last dragged at once let go
+pub struct GrabPlugin;
+
+impl Plugin for GrabPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Grab>()
+            .register_type::<Held>()
+            .add_systems(FixedUpdate, carry_held.in_set(BoidSet::Movement));
+    }
+}
+
+fn carry_held(
+    grab: Res<Grab>,
+    mut held: Query<(&mut Transform, &mut Velocity), With<Held>>,
+    settings: Res<BoidSettings>,
+    time: Res<Time>,
+) {
+    let dt = time.delta_seconds().max(f32::EPSILON);
+
+    for (mut transform, mut velocity) in held.iter_mut() {
+        let dragged = (grab.target - transform.translation.xy()) / dt;
+        let fling = velocity.0.lerp(dragged, FLING_SMOOTHING).clamp_length_max(settings.max_speed * MAX_FLING);
+
+        // Held still it keeps its heading, crawling along it rather than stopping dead
+        if fling.length() > 1.0 {
+            velocity.0 = fling;
+            transform.rotation = Quat::from_axis_angle(Vec3::Z, angle_towards(Vec2::ZERO, fling));
+        } else {
+            velocity.0 = transform.local_x().xy();
+        }
+        transform.translation.x = grab.target.x;
+        transform.translation.y = grab.target.y;
+    }
+}
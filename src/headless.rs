@@ -0,0 +1,117 @@
+use bevy::{math::Vec3Swizzles, prelude::*};
+
+use crate::{bench::headless_app, settings::BoidSettings, SpatialEntity, Velocity, WorldBounds};
+
+// The 2D flock without a window or renderer, stepped a tick at a time and read back boid by
+// boid, for tests and tools to drive the simulation as a whole
+pub struct BoidsSimulation {
+    app: App,
+}
+
+impl BoidsSimulation {
+    // The flock `settings` describe, spawned from `seed` so equal seeds run alike
+    pub fn new(settings: BoidSettings, seed: u64) -> Self {
+        Self { app: headless_app(settings, Some(seed), |_| {}) }
+    }
+
+    // Runs `ticks` fixed timesteps
+    pub fn step(&mut self, ticks: usize) {
+        for _ in 0..ticks {
+            self.app.update();
+        }
+    }
+
+    pub fn settings(&self) -> &BoidSettings {
+        self.app.world.resource::<BoidSettings>()
+    }
+
+    // Size of the world, centered on the origin
+    pub fn bounds(&self) -> Vec2 {
+        **self.app.world.resource::<WorldBounds>()
+    }
+
+    // Every flocking boid's position, ordered by entity
+    pub fn positions(&mut self) -> Vec<Vec2> {
+        self.boids(|transform, _| transform.translation.xy())
+    }
+
+    // Every flocking boid's velocity, in the same order as `positions`
+    pub fn velocities(&mut self) -> Vec<Vec2> {
+        self.boids(|_, velocity| velocity.0)
+    }
+
+    // For anything the accessors don't cover
+    pub fn app(&mut self) -> &mut App {
+        &mut self.app
+    }
+
+    fn boids<T>(&mut self, read: impl Fn(&Transform, &Velocity) -> T) -> Vec<T> {
+        let mut boids = self.app.world.query_filtered::<(Entity, &Transform, &Velocity), With<SpatialEntity>>();
+        let mut boids: Vec<_> = boids.iter(&self.app.world).collect();
+        boids.sort_unstable_by_key(|(entity, _, _)| *entity);
+        boids.into_iter().map(|(_, transform, velocity)| read(transform, velocity)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::BoundaryMode;
+
+    const TICKS: usize = 120;
+
+    fn settings() -> BoidSettings {
+        BoidSettings { count: 200, ..default() }
+    }
+
+    #[test]
+    fn speeds_stay_within_the_limits() {
+        let mut sim = BoidsSimulation::new(BoidSettings { drag: 0.0, ..settings() }, 1);
+        sim.step(TICKS);
+
+        let (min, max) = (sim.settings().min_speed, sim.settings().max_speed);
+        let velocities = sim.velocities();
+        assert_eq!(velocities.len(), 200);
+        for speed in velocities.iter().map(|velocity| velocity.length()) {
+            assert!(speed >= min * 0.999 && speed <= max * 1.001, "{speed} outside {min}..{max}");
+        }
+    }
+
+    // Runs the flock in `boundary_mode`, checking along the way it stays in the world shrunk by
+    // `inset` across, given the settings and timestep
+    fn stays_within(boundary_mode: BoundaryMode, seed: u64, inset: impl Fn(&BoidSettings, f32) -> f32) {
+        let mut sim = BoidsSimulation::new(BoidSettings { boundary_mode, ..settings() }, seed);
+        let dt = sim.app().world.resource::<Time<Fixed>>().timestep().as_secs_f32();
+        for _ in 0..4 {
+            sim.step(TICKS / 4);
+
+            let half = (sim.bounds() - inset(sim.settings(), dt)) / 2.0 + 1e-3;
+            for position in sim.positions() {
+                assert!(position.abs().cmple(half).all(), "{boundary_mode:?}: {position} outside {half}");
+            }
+        }
+    }
+
+    #[test]
+    fn boids_stay_within_the_world() {
+        // Across the edge and straight back in at the other
+        stays_within(BoundaryMode::Wrap, 2, |_, _| 0.0);
+        // Reflected off the boundary, so never past it
+        stays_within(BoundaryMode::Bounce, 5, |settings, _| settings.boundary_size);
+        // Turned back gradually once inside the margin, by the edge at the latest, give or take a
+        // tick of flight
+        stays_within(BoundaryMode::Steer, 6, |settings, dt| -2.0 * settings.max_speed * dt);
+    }
+
+    #[test]
+    fn equal_seeds_run_alike() {
+        let run = |seed| {
+            let mut sim = BoidsSimulation::new(settings(), seed);
+            sim.step(TICKS);
+            sim.positions()
+        };
+
+        assert_eq!(run(3), run(3));
+        assert_ne!(run(3), run(4));
+    }
+}
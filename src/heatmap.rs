@@ -0,0 +1,151 @@
+use bevy::{
+    math::Vec3Swizzles,
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+    },
+};
+
+use crate::{input::{Action, Actions}, BoidSet, SpatialEntity, WorldBounds};
+
+// Side of a heatmap cell, in world units
+const HEAT_CELL: f32 = 20.;
+
+// Boids per cell binned on the last tick, row 0 at the bottom of the world
+#[derive(Resource, Default)]
+pub struct DensityMap {
+    pub enabled: bool,
+    pub cols: usize,
+    pub rows: usize,
+    pub counts: Vec<u32>,
+}
+
+impl DensityMap {
+    fn bin(&mut self, bounds: Vec2, positions: impl Iterator<Item = Vec2>) {
+        self.cols = (bounds.x / HEAT_CELL).ceil().max(1.0) as usize;
+        self.rows = (bounds.y / HEAT_CELL).ceil().max(1.0) as usize;
+        self.counts.clear();
+        self.counts.resize(self.cols * self.rows, 0);
+
+        let origin = -Vec2::new(self.cols as f32, self.rows as f32) * HEAT_CELL / 2.0;
+
+        for position in positions {
+            let cell = ((position - origin) / HEAT_CELL).floor();
+            if cell.x < 0.0 || cell.y < 0.0 || cell.x >= self.cols as f32 || cell.y >= self.rows as f32 {
+                continue;
+            }
+            self.counts[cell.y as usize * self.cols + cell.x as usize] += 1;
+        }
+    }
+}
+
+#[derive(Component)]
+struct Heatmap;
+
+// `M` toggles a heatmap of how crowded each part of the world is
+pub struct HeatmapPlugin;
+
+impl Plugin for HeatmapPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DensityMap>()
+            .add_systems(Startup, spawn_heatmap)
+            .add_systems(FixedUpdate, bin_density
+                .after(BoidSet::Movement)
+                .run_if(|map: Res<DensityMap>| map.enabled))
+            .add_systems(Update, (
+                toggle_heatmap,
+                paint_heatmap.run_if(|map: Res<DensityMap>| map.enabled),
+            ).chain());
+    }
+}
+
+fn spawn_heatmap(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let image = Image::new_fill(
+        Extent3d { width: 1, height: 1, depth_or_array_layers: 1 },
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+
+    // Behind the boids
+    commands.spawn((
+        SpriteBundle {
+            texture: images.add(image),
+            transform: Transform::from_xyz(0.0, 0.0, -1.0),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        Heatmap,
+    ));
+}
+
+fn toggle_heatmap(
+    actions: Actions,
+    mut map: ResMut<DensityMap>,
+    mut heatmap: Query<&mut Visibility, With<Heatmap>>,
+) {
+    if actions.just_pressed(Action::Heatmap) {
+        map.enabled = !map.enabled;
+
+        for mut visibility in heatmap.iter_mut() {
+            *visibility = if map.enabled { Visibility::Visible } else { Visibility::Hidden };
+        }
+    }
+}
+
+fn bin_density(
+    boids: Query<&Transform, With<SpatialEntity>>,
+    bounds: Res<WorldBounds>,
+    mut map: ResMut<DensityMap>,
+) {
+    map.bin(**bounds, boids.iter().map(|transform| transform.translation.xy()));
+}
+
+// Transparent where empty, through blue and red to yellow at the busiest cell
+fn heat_color(t: f32) -> [u8; 4] {
+    let (r, g, b) = if t < 0.5 {
+        (t * 2.0, 0.0, 1.0 - t * 2.0)
+    } else {
+        (1.0, (t - 0.5) * 2.0, 0.0)
+    };
+    let a = if t > 0.0 { 0.2 + 0.5 * t } else { 0.0 };
+
+    [r, g, b, a].map(|c| (c * 255.0) as u8)
+}
+
+fn paint_heatmap(
+    map: Res<DensityMap>,
+    mut images: ResMut<Assets<Image>>,
+    mut heatmap: Query<(&Handle<Image>, &mut Sprite), With<Heatmap>>,
+) {
+    if map.counts.is_empty() {
+        return;
+    }
+
+    let busiest = map.counts.iter().copied().max().unwrap_or(0).max(1) as f32;
+
+    for (handle, mut sprite) in heatmap.iter_mut() {
+        let Some(image) = images.get_mut(handle) else {
+            continue;
+        };
+
+        image.resize(Extent3d {
+            width: map.cols as u32,
+            height: map.rows as u32,
+            depth_or_array_layers: 1,
+        });
+
+        // Images store their top row first
+        for row in 0..map.rows {
+            for col in 0..map.cols {
+                let count = map.counts[row * map.cols + col];
+                let pixel = ((map.rows - 1 - row) * map.cols + col) * 4;
+                image.data[pixel..pixel + 4].copy_from_slice(&heat_color(count as f32 / busiest));
+            }
+        }
+
+        sprite.custom_size = Some(Vec2::new(map.cols as f32, map.rows as f32) * HEAT_CELL);
+    }
+}
@@ -0,0 +1,334 @@
+use std::{cell::Cell, time::Duration};
+use bevy::{math::Vec3Swizzles, prelude::*, utils::Instant};
+use bevy_spatial::{kdtree::KDTree2, point::Point2, SpatialAccess};
+use thread_local::ThreadLocal;
+
+use crate::{
+    input::{Action, Actions},
+    settings::{BoidSettings, NeighborQuery, SpatialIndex},
+    spatial::build_kdtree2,
+    SpatialEntity,
+    Velocity,
+};
+
+// Every boid's position, answering neighbor queries from whichever structure
+// `BoidSettings::spatial_index` picks; only that one is rebuilt, whenever boids have drifted
+// far enough from the positions it holds
+#[derive(Resource, Default)]
+pub(crate) struct BoidIndex {
+    kind: SpatialIndex,
+    kdtree: KDTree2<SpatialEntity>,
+    flat: Vec<(Vec2, Entity)>,
+    grid: Grid,
+    // How far any boid may have moved since the last rebuild, and ticks since
+    drift: f32,
+    age: u32,
+    timings: IndexTimings,
+}
+
+impl BoidIndex {
+    // The kd-tree, while it's the active structure
+    pub fn kdtree(&self) -> Option<&KDTree2<SpatialEntity>> {
+        (self.kind == SpatialIndex::KdTree).then_some(&self.kdtree)
+    }
+
+    // Candidate neighbors of `position` for flocking, as `query` picks them from those within
+    // `range` or the `cap` nearest; the flocking rules drop any out of range
+    pub fn neighbors(&self, position: Vec2, range: f32, cap: usize, query: NeighborQuery) -> Vec<Hit> {
+        // Both this boid and the neighbor may have drifted since it was built, so widened enough
+        // to still find every one really within range
+        let range = range + 2.0 * self.drift;
+
+        match query {
+            NeighborQuery::Nearest => self.k_nearest_neighbour(position, cap),
+            NeighborQuery::Radius => self.within_distance(position, range),
+            NeighborQuery::Hybrid => {
+                let hits = self.within_distance(position, range);
+                if hits.len() <= cap {
+                    return hits;
+                }
+                nearest_k(position, cap, hits.into_iter().filter_map(|(point, entity)| Some((point, entity?))))
+            }
+        }
+    }
+
+    // Timed only while `log_index_timings` asks, queries being the hottest loop there is
+    fn timed<T>(&self, query: impl FnOnce() -> T) -> T {
+        if !self.timings.queried {
+            return query();
+        }
+
+        let start = Instant::now();
+        let result = query();
+
+        // Tallied per thread, so parallel steering doesn't contend over shared counters
+        let tally = self.timings.queries.get_or_default();
+        let (nanos, queries) = tally.get();
+        tally.set((nanos + start.elapsed().as_nanos() as u64, queries + 1));
+        result
+    }
+}
+
+// Build and query time spent by the active structure since it was picked
+#[derive(Default)]
+struct IndexTimings {
+    build: Duration,
+    builds: u32,
+    ticks: u32,
+    queried: bool,
+    // Nanoseconds spent querying and queries made, on each thread
+    queries: ThreadLocal<Cell<(u64, u64)>>,
+}
+
+impl IndexTimings {
+    fn summary(&mut self) -> String {
+        let builds = self.builds.max(1) as f64;
+        let (query_nanos, queries) = self.queries.iter_mut()
+            .map(|tally| tally.get())
+            .fold((0, 0), |(nanos, queries), (n, q)| (nanos + n, queries + q));
+        let query_nanos = query_nanos as f64;
+
+        let build = format!(
+            "build {:.3} ms, rebuilt on {} of {} ticks",
+            self.build.as_secs_f64() * 1000.0 / builds,
+            self.builds,
+            self.ticks,
+        );
+        if queries == 0 {
+            return build;
+        }
+
+        format!(
+            "{build}, query {:.2} µs over {queries} queries, {:.3} ms/tick querying",
+            query_nanos / 1000.0 / queries as f64,
+            query_nanos / 1e6 / self.ticks.max(1) as f64,
+        )
+    }
+}
+
+type Hit = (Vec2, Option<Entity>);
+
+impl SpatialAccess for BoidIndex {
+    type Point = Point2;
+    type Comp = SpatialEntity;
+    type ResultT = Hit;
+
+    fn nearest_neighbour(&self, loc: Vec2) -> Option<Hit> {
+        self.k_nearest_neighbour(loc, 1).into_iter().next()
+    }
+
+    fn k_nearest_neighbour(&self, loc: Vec2, k: usize) -> Vec<Hit> {
+        self.timed(|| match self.kind {
+            SpatialIndex::KdTree => self.kdtree.k_nearest_neighbour(loc, k),
+            SpatialIndex::Flat => nearest_k(loc, k, self.flat.iter().copied()),
+            SpatialIndex::Grid => self.grid.k_nearest(loc, k),
+        })
+    }
+
+    fn within_distance(&self, loc: Vec2, distance: f32) -> Vec<Hit> {
+        self.timed(|| match self.kind {
+            SpatialIndex::KdTree => self.kdtree.within_distance(loc, distance),
+            SpatialIndex::Flat => self.flat.iter()
+                .filter(|(position, _)| position.distance_squared(loc) <= distance * distance)
+                .map(|(position, entity)| (*position, Some(*entity)))
+                .collect(),
+            SpatialIndex::Grid => self.grid.within(loc, distance),
+        })
+    }
+}
+
+// The `k` closest of `points` to `loc`, closest first
+fn nearest_k(loc: Vec2, k: usize, points: impl Iterator<Item = (Vec2, Entity)>) -> Vec<Hit> {
+    let mut points = points.collect::<Vec<_>>();
+    let by_distance = |a: &(Vec2, Entity), b: &(Vec2, Entity)| {
+        a.0.distance_squared(loc).total_cmp(&b.0.distance_squared(loc))
+    };
+
+    if k == 0 {
+        return vec![];
+    }
+    if points.len() > k {
+        points.select_nth_unstable_by(k - 1, by_distance);
+        points.truncate(k);
+    }
+    points.sort_unstable_by(by_distance);
+
+    points.into_iter().map(|(position, entity)| (position, Some(entity))).collect()
+}
+
+// Spatial hash of square cells: points are bucketed by a hash of their cell, so the
+// grid is unbounded and covers wrapped ghosts and boids off the edge alike
+#[derive(Default)]
+struct Grid {
+    cell: f32,
+    // Points sorted by bucket, bucket `i` holding `points[starts[i]..starts[i + 1]]`
+    starts: Vec<usize>,
+    points: Vec<(Vec2, Entity)>,
+}
+
+impl Grid {
+    fn build(&mut self, cell: f32, points: Vec<(Vec2, Entity)>) {
+        self.cell = cell.max(1.0);
+        // About two buckets per point keeps collisions rare
+        let buckets = (points.len() * 2).next_power_of_two();
+
+        // Counting sort by bucket
+        let keys = points.iter().map(|(position, _)| self.bucket(self.cell_of(*position), buckets)).collect::<Vec<_>>();
+        self.starts.clear();
+        self.starts.resize(buckets + 1, 0);
+        for key in keys.iter() {
+            self.starts[key + 1] += 1;
+        }
+        for i in 1..self.starts.len() {
+            self.starts[i] += self.starts[i - 1];
+        }
+
+        let mut next = self.starts.clone();
+        self.points.clear();
+        self.points.resize(points.len(), (Vec2::ZERO, Entity::PLACEHOLDER));
+        for (point, key) in points.into_iter().zip(keys) {
+            self.points[next[key]] = point;
+            next[key] += 1;
+        }
+    }
+
+    fn cell_of(&self, position: Vec2) -> IVec2 {
+        (position / self.cell).floor().as_ivec2()
+    }
+
+    fn bucket(&self, cell: IVec2, buckets: usize) -> usize {
+        // https://matthias-research.github.io/pages/tenMinutePhysics/11-hashing.pdf
+        let hash = (cell.x.wrapping_mul(92837111)) ^ (cell.y.wrapping_mul(689287499));
+        hash as u32 as usize & (buckets - 1)
+    }
+
+    // Points in `cell` only, skipping other cells sharing its bucket
+    fn points_in(&self, cell: IVec2) -> impl Iterator<Item = &(Vec2, Entity)> {
+        let buckets = self.starts.len().saturating_sub(1);
+        let points = if self.points.is_empty() {
+            &[]
+        } else {
+            let i = self.bucket(cell, buckets);
+            &self.points[self.starts[i]..self.starts[i + 1]]
+        };
+
+        points.iter().filter(move |(position, _)| self.cell_of(*position) == cell)
+    }
+
+    fn within(&self, loc: Vec2, distance: f32) -> Vec<Hit> {
+        let (min, max) = (self.cell_of(loc - distance), self.cell_of(loc + distance));
+        let mut hits = vec![];
+
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                hits.extend(self.points_in(IVec2::new(x, y))
+                    .filter(|(position, _)| position.distance_squared(loc) <= distance * distance)
+                    .map(|(position, entity)| (*position, Some(*entity))));
+            }
+        }
+
+        hits
+    }
+
+    // Search rings of cells outward until the closest `k` can't be beaten further out
+    fn k_nearest(&self, loc: Vec2, k: usize) -> Vec<Hit> {
+        let center = self.cell_of(loc);
+        let mut found = vec![];
+
+        for ring in 0.. {
+            for y in center.y - ring..=center.y + ring {
+                for x in center.x - ring..=center.x + ring {
+                    if (x - center.x).abs() == ring || (y - center.y).abs() == ring {
+                        found.extend(self.points_in(IVec2::new(x, y)).copied());
+                    }
+                }
+            }
+
+            // Anything in the next ring is at least this far away
+            let reach = ring as f32 * self.cell;
+            let nearest = (found.len() >= k || found.len() == self.points.len())
+                .then(|| nearest_k(loc, k, found.iter().copied()));
+
+            match nearest {
+                Some(nearest) if found.len() == self.points.len() => return nearest,
+                Some(nearest) if nearest.last().is_none_or(|(position, _)| position.distance(loc) <= reach) => {
+                    return nearest;
+                }
+                _ => {}
+            }
+        }
+
+        unreachable!()
+    }
+}
+
+// Rebuild the active structure once it has drifted too far from the boids, switching and
+// reporting timings when the setting changed
+pub fn rebuild_index(
+    mut index: ResMut<BoidIndex>,
+    boids: Query<(Entity, &Transform, &Velocity), With<SpatialEntity>>,
+    spawned: Query<(), Added<SpatialEntity>>,
+    settings: Res<BoidSettings>,
+    time: Res<Time>,
+    real: Res<Time<Real>>,
+) {
+    let index = &mut *index;
+    let switched = index.kind != settings.spatial_index;
+
+    if switched {
+        info!("{:?} index: {}", index.kind, index.timings.summary());
+        *index = BoidIndex { kind: settings.spatial_index, ..default() };
+    }
+    index.timings.ticks += 1;
+    index.timings.queried = settings.log_index_timings;
+
+    // The fastest boid bounds how far any moved last tick
+    let fastest = boids.iter().map(|(_, _, velocity)| velocity.0.length_squared()).fold(0.0, f32::max).sqrt();
+    index.drift += fastest * time.delta_seconds();
+    index.age += 1;
+
+    let budget = settings.frame_budget_ms / 1000.0;
+    let over_budget = if budget > 0.0 { (real.delta_seconds() / budget).clamp(1.0, 4.0) } else { 1.0 };
+
+    // Newly spawned boids aren't found until it's rebuilt, however many died meanwhile; despawned
+    // ones are skipped by everyone
+    let due = switched
+        || !spawned.is_empty()
+        || index.age >= settings.index_max_ticks
+        || index.drift > settings.index_drift * over_budget;
+    if !due {
+        return;
+    }
+    index.drift = 0.0;
+    index.age = 0;
+
+    let start = Instant::now();
+    let points = boids.iter().map(|(entity, transform, _)| (transform.translation.xy(), entity));
+
+    match index.kind {
+        SpatialIndex::KdTree => {
+            index.kdtree.tree = build_kdtree2(points.map(|(position, entity)| (position, entity).into()).collect());
+        }
+        SpatialIndex::Flat => {
+            index.flat.clear();
+            index.flat.extend(points);
+        }
+        // Vision range cells keep a neighbor query to the few cells around a boid
+        SpatialIndex::Grid => index.grid.build(settings.vis_range, points.collect()),
+    }
+
+    index.timings.build += start.elapsed();
+    index.timings.builds += 1;
+}
+
+// `I` cycles through the spatial index structures
+pub fn cycle_index(actions: Actions, mut settings: ResMut<BoidSettings>) {
+    if actions.just_pressed(Action::CycleIndex) {
+        settings.spatial_index = match settings.spatial_index {
+            SpatialIndex::Grid => SpatialIndex::KdTree,
+            SpatialIndex::KdTree => SpatialIndex::Flat,
+            SpatialIndex::Flat => SpatialIndex::Grid,
+        };
+        info!("spatial index {:?}", settings.spatial_index);
+    }
+}
@@ -0,0 +1,245 @@
+use bevy::{ecs::system::SystemParam, prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
+
+// Everything the keyboard and mouse do, each bound in `InputMap`
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize, Reflect)]
+pub enum Action {
+    Quit,
+    Pause,
+    Step,
+    // Held to run the simulation backwards
+    Rewind,
+    SlowDown,
+    SpeedUp,
+    // Select or drag a boid, paint wind, scrub a replay's timeline
+    Select,
+    // Push boids away from the cursor, erase painted wind
+    Repel,
+    // Place or remove an attractor by clicking, pan the camera by dragging
+    Place,
+    // Held for the other half of an action: a repeller, a single boid, clearing emitters
+    Alternate,
+    // Held while selecting to append a waypoint
+    Route,
+    ClearRoute,
+    SpawnBatch,
+    DespawnBatch,
+    Food,
+    Leader,
+    Emitter,
+    Game,
+    Player,
+    PlayerUp,
+    PlayerDown,
+    PlayerLeft,
+    PlayerRight,
+    FollowNext,
+    StopFollowing,
+    SplitScreen,
+    CycleBoundary,
+    CycleIndex,
+    CycleColors,
+    DayNight,
+    Heatmap,
+    Pheromones,
+    Minimap,
+    Plot,
+    Trails,
+    Stats,
+    DebugOverlay,
+    Labels,
+    TreeOverlay,
+    PaintWind,
+    Audio,
+    Record,
+    Screenshot,
+    Gif,
+    SaveSnapshot,
+    LoadSnapshot,
+    Preset1,
+    Preset2,
+    Preset3,
+    Preset4,
+    Inspector,
+    ReplayBack,
+    ReplayForward,
+    ReplayStart,
+    ReplayEnd,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize, Reflect)]
+pub enum Binding {
+    Key(KeyCode),
+    Mouse(MouseButton),
+}
+
+// The inputs bound to each action, from `assets/input.ron` over the defaults
+#[derive(Resource, Clone, Debug, PartialEq, Serialize, Deserialize, Reflect)]
+#[reflect(Resource)]
+#[serde(transparent)]
+pub struct InputMap(pub HashMap<Action, Vec<Binding>>);
+
+impl Default for InputMap {
+    fn default() -> Self {
+        use Binding::{Key, Mouse};
+        use KeyCode::*;
+
+        let shift = vec![Key(ShiftLeft), Key(ShiftRight)];
+        let ctrl = vec![Key(ControlLeft), Key(ControlRight)];
+
+        Self(HashMap::from_iter([
+            (Action::Quit, vec![Key(Escape)]),
+            (Action::Pause, vec![Key(Space)]),
+            (Action::Step, vec![Key(Period)]),
+            (Action::Rewind, vec![Key(Comma)]),
+            (Action::SlowDown, vec![Key(BracketLeft)]),
+            (Action::SpeedUp, vec![Key(BracketRight)]),
+            (Action::Select, vec![Mouse(MouseButton::Left)]),
+            (Action::Repel, vec![Mouse(MouseButton::Right)]),
+            (Action::Place, vec![Mouse(MouseButton::Middle)]),
+            (Action::Alternate, shift),
+            (Action::Route, ctrl),
+            (Action::ClearRoute, vec![Key(Backspace)]),
+            (Action::SpawnBatch, vec![Key(Equal), Key(NumpadAdd)]),
+            (Action::DespawnBatch, vec![Key(Minus), Key(NumpadSubtract)]),
+            (Action::Food, vec![Key(KeyF)]),
+            (Action::Leader, vec![Key(KeyL)]),
+            (Action::Emitter, vec![Key(KeyJ)]),
+            (Action::Game, vec![Key(KeyZ)]),
+            (Action::Player, vec![Key(KeyP)]),
+            (Action::PlayerUp, vec![Key(KeyW), Key(ArrowUp)]),
+            (Action::PlayerDown, vec![Key(KeyS), Key(ArrowDown)]),
+            (Action::PlayerLeft, vec![Key(KeyA), Key(ArrowLeft)]),
+            (Action::PlayerRight, vec![Key(KeyD), Key(ArrowRight)]),
+            (Action::FollowNext, vec![Key(Tab)]),
+            (Action::StopFollowing, vec![Key(KeyC)]),
+            (Action::SplitScreen, vec![Key(KeyX)]),
+            (Action::CycleBoundary, vec![Key(KeyB)]),
+            (Action::CycleIndex, vec![Key(KeyI)]),
+            (Action::CycleColors, vec![Key(KeyN)]),
+            (Action::DayNight, vec![Key(KeyE)]),
+            (Action::Heatmap, vec![Key(KeyM)]),
+            (Action::Pheromones, vec![Key(KeyO)]),
+            (Action::Minimap, vec![Key(KeyU)]),
+            (Action::Plot, vec![Key(KeyY)]),
+            (Action::Trails, vec![Key(KeyT)]),
+            (Action::Stats, vec![Key(KeyH)]),
+            (Action::DebugOverlay, vec![Key(F3)]),
+            (Action::Labels, vec![Key(F2)]),
+            (Action::TreeOverlay, vec![Key(KeyK)]),
+            (Action::PaintWind, vec![Key(KeyG)]),
+            (Action::Audio, vec![Key(KeyV)]),
+            (Action::Record, vec![Key(KeyR)]),
+            (Action::Screenshot, vec![Key(F12)]),
+            (Action::Gif, vec![Key(F8)]),
+            (Action::SaveSnapshot, vec![Key(F5)]),
+            (Action::LoadSnapshot, vec![Key(F9)]),
+            (Action::Preset1, vec![Key(Digit1)]),
+            (Action::Preset2, vec![Key(Digit2)]),
+            (Action::Preset3, vec![Key(Digit3)]),
+            (Action::Preset4, vec![Key(Digit4)]),
+            (Action::Inspector, vec![Key(F1)]),
+            (Action::ReplayBack, vec![Key(ArrowLeft)]),
+            (Action::ReplayForward, vec![Key(ArrowRight)]),
+            (Action::ReplayStart, vec![Key(Home)]),
+            (Action::ReplayEnd, vec![Key(End)]),
+        ]))
+    }
+}
+
+impl InputMap {
+    // The defaults with `rebound`'s actions replaced, so a config only lists what it changes
+    pub fn rebinding(rebound: &InputMap) -> Self {
+        let mut map = Self::default();
+        map.0.extend(rebound.0.iter().map(|(action, bindings)| (*action, bindings.clone())));
+        map
+    }
+
+    pub fn bindings(&self, action: Action) -> &[Binding] {
+        self.0.get(&action).map_or(&[], Vec::as_slice)
+    }
+}
+
+// Whether actions are held or were just pressed or released, through whatever they're bound to
+#[derive(SystemParam)]
+pub struct Actions<'w> {
+    map: Res<'w, InputMap>,
+    keys: Res<'w, ButtonInput<KeyCode>>,
+    mouse: Res<'w, ButtonInput<MouseButton>>,
+}
+
+impl Actions<'_> {
+    fn any(
+        &self,
+        action: Action,
+        key: impl Fn(&ButtonInput<KeyCode>, KeyCode) -> bool,
+        button: impl Fn(&ButtonInput<MouseButton>, MouseButton) -> bool,
+    ) -> bool {
+        self.map.bindings(action).iter().any(|binding| match *binding {
+            Binding::Key(code) => key(&self.keys, code),
+            Binding::Mouse(mouse) => button(&self.mouse, mouse),
+        })
+    }
+
+    pub fn pressed(&self, action: Action) -> bool {
+        self.any(action, |keys, code| keys.pressed(code), |mouse, button| mouse.pressed(button))
+    }
+
+    pub fn just_pressed(&self, action: Action) -> bool {
+        self.any(action, |keys, code| keys.just_pressed(code), |mouse, button| mouse.just_pressed(button))
+    }
+
+    pub fn just_released(&self, action: Action) -> bool {
+        self.any(action, |keys, code| keys.just_released(code), |mouse, button| mouse.just_released(button))
+    }
+}
+
+// Every app's bindings, and quitting by them rather than always on `Esc`
+pub struct InputMapPlugin;
+
+impl Plugin for InputMapPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InputMap>()
+            .register_type::<InputMap>()
+            .add_systems(Update, close_focused_window);
+    }
+}
+
+fn close_focused_window(mut commands: Commands, actions: Actions, windows: Query<(Entity, &Window)>) {
+    if !actions.just_pressed(Action::Quit) {
+        return;
+    }
+
+    for (window, focus) in windows.iter() {
+        if focus.focused {
+            commands.entity(window).despawn();
+        }
+    }
+}
+
+// Toggled by an action, for a run condition like the world inspector's
+#[cfg(feature = "inspector")]
+pub fn action_toggle_active(default: bool, action: Action) -> impl FnMut(Actions, Local<Option<bool>>) -> bool {
+    move |actions: Actions, mut active: Local<Option<bool>>| {
+        let active = active.get_or_insert(default);
+        if actions.just_pressed(action) {
+            *active = !*active;
+        }
+        *active
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rebinding_keeps_the_other_defaults() {
+        let rebound: InputMap = ron::from_str("{ Pause: [Key(KeyQ), Mouse(Back)] }").unwrap();
+        let map = InputMap::rebinding(&rebound);
+
+        assert_eq!(map.bindings(Action::Pause), [Binding::Key(KeyCode::KeyQ), Binding::Mouse(MouseButton::Back)]);
+        assert_eq!(map.bindings(Action::Step), InputMap::default().bindings(Action::Step));
+        assert_eq!(map.0.len(), InputMap::default().0.len());
+    }
+}
@@ -0,0 +1,13 @@
+use bevy::prelude::*;
+use bevy_inspector_egui::quick::WorldInspectorPlugin;
+
+use crate::input::{action_toggle_active, Action};
+
+// `F1` toggles a world inspector over every entity and resource
+pub struct InspectorPlugin;
+
+impl Plugin for InspectorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_plugins(WorldInspectorPlugin::new().run_if(action_toggle_active(false, Action::Inspector)));
+    }
+}
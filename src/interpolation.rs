@@ -0,0 +1,73 @@
+use bevy::{prelude::*, transform::TransformSystem};
+
+use crate::settings::BoidSettings;
+
+// Simulated transforms from the last two ticks; the rendered Transform is blended between them
+#[derive(Component, Clone, Copy)]
+pub struct TickTransforms {
+    previous: Transform,
+    current: Transform,
+}
+
+// Smooths boid motion on displays refreshing faster than the fixed tick rate
+pub struct InterpolationPlugin;
+
+impl Plugin for InterpolationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(FixedFirst, restore_tick_transforms)
+            .add_systems(FixedPostUpdate, record_tick_transforms)
+            .add_systems(PostUpdate, interpolate_transforms.before(TransformSystem::TransformPropagate));
+    }
+}
+
+type Untracked<C> = (With<C>, Without<TickTransforms>);
+
+// Start interpolating entities with `C`, e.g. `track_transforms::<Velocity>`
+pub fn track_transforms<C: Component>(
+    mut commands: Commands,
+    untracked: Query<(Entity, &Transform), Untracked<C>>,
+) {
+    for (entity, transform) in untracked.iter() {
+        commands.entity(entity).insert(TickTransforms {
+            previous: *transform,
+            current: *transform,
+        });
+    }
+}
+
+// The simulation only ever sees its own positions, never the blended ones
+fn restore_tick_transforms(mut query: Query<(&mut Transform, &TickTransforms)>) {
+    for (mut transform, ticks) in query.iter_mut() {
+        *transform = ticks.current;
+    }
+}
+
+// Runs even while paused, so previous and current converge and the flock holds still
+fn record_tick_transforms(mut query: Query<(&Transform, &mut TickTransforms)>) {
+    for (transform, mut ticks) in query.iter_mut() {
+        ticks.previous = ticks.current;
+        ticks.current = *transform;
+    }
+}
+
+pub fn interpolate_transforms(
+    mut query: Query<(&mut Transform, &TickTransforms)>,
+    time: Res<Time<Fixed>>,
+    settings: Res<BoidSettings>,
+) {
+    let alpha = time.overstep_fraction();
+    let max_step = settings.max_speed.max(settings.predator_max_speed) * time.timestep().as_secs_f32();
+
+    for (mut transform, ticks) in query.iter_mut() {
+        let (previous, current) = (ticks.previous, ticks.current);
+
+        // Don't sweep across the world after a wrap or a teleport
+        if previous.translation.distance(current.translation) > max_step * 2.0 {
+            *transform = current;
+            continue;
+        }
+
+        transform.translation = previous.translation.lerp(current.translation, alpha);
+        transform.rotation = previous.rotation.slerp(current.rotation, alpha);
+    }
+}
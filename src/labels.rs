@@ -0,0 +1,137 @@
+use bevy::{math::Vec3Swizzles, prelude::*, sprite::Anchor, utils::HashSet};
+use bevy_spatial::SpatialAccess;
+
+use crate::{
+    behavior::BoidState,
+    camera::OverviewCamera,
+    flocking::perceives,
+    index::BoidIndex,
+    input::{Action, Actions},
+    selection::Selection,
+    settings::BoidSettings,
+    variation::{with_traits, BoidTraits, Mass},
+    MainCamera,
+    SpatialEntity,
+    Velocity,
+};
+
+// Labelling every boid is only readable, and affordable, for so many of them
+const MAX_LABELS: usize = 200;
+// Screen pixels between a boid and the bottom of its label
+const LABEL_OFFSET: f32 = 12.;
+// Drawn over the boids, trails and overlays
+const LABEL_Z: f32 = 10.;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LabelMode {
+    #[default]
+    Off,
+    Selected,
+    All,
+}
+
+impl LabelMode {
+    fn next(self) -> Self {
+        match self {
+            LabelMode::Off => LabelMode::Selected,
+            LabelMode::Selected => LabelMode::All,
+            LabelMode::All => LabelMode::Off,
+        }
+    }
+}
+
+#[derive(Resource, Default)]
+pub struct BoidLabels(pub LabelMode);
+
+// Text following the boid it describes
+#[derive(Component)]
+struct BoidLabel(Entity);
+
+// `F2` cycles text labels with each boid's id, speed, neighbor count and state, above the
+// selected boid or all of them
+pub struct LabelPlugin;
+
+impl Plugin for LabelPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BoidLabels>()
+            .add_systems(Update, (cycle_labels, update_labels).chain());
+    }
+}
+
+fn cycle_labels(actions: Actions, mut labels: ResMut<BoidLabels>) {
+    if actions.just_pressed(Action::Labels) {
+        labels.0 = labels.0.next();
+        info!("boid labels: {:?}", labels.0);
+    }
+}
+
+type LabelledBoid<'a> = (
+    Entity,
+    &'a Transform,
+    &'a Velocity,
+    &'a BoidState,
+    Option<&'a BoidTraits>,
+    Option<&'a Mass>,
+);
+
+type LabelText<'a> = (Entity, &'a BoidLabel, &'a mut Text, &'a mut Transform, &'a mut Visibility);
+
+#[allow(clippy::too_many_arguments)]
+fn update_labels(
+    mut commands: Commands,
+    labels: Res<BoidLabels>,
+    selection: Option<Res<Selection>>,
+    boids: Query<LabelledBoid, With<SpatialEntity>>,
+    mut texts: Query<LabelText, Without<SpatialEntity>>,
+    camera: Query<&OrthographicProjection, (With<MainCamera>, Without<OverviewCamera>)>,
+    index: Res<BoidIndex>,
+    settings: Res<BoidSettings>,
+) {
+    let wanted: HashSet<Entity> = match labels.0 {
+        LabelMode::Off => default(),
+        LabelMode::Selected => selection.and_then(|selection| selection.boid).into_iter().collect(),
+        LabelMode::All => boids.iter().take(MAX_LABELS).map(|(boid, ..)| boid).collect(),
+    };
+
+    // Kept the same size on screen however far the camera zooms
+    let zoom = camera.iter().next().map_or(1.0, |projection| projection.scale);
+    let mut labelled = HashSet::new();
+
+    for (label, BoidLabel(boid), mut text, mut transform, mut visibility) in texts.iter_mut() {
+        let Some((_, t0, velocity, state, traits, mass)) = wanted.get(boid).and_then(|boid| boids.get(*boid).ok()) else {
+            commands.entity(label).despawn();
+            continue;
+        };
+        labelled.insert(*boid);
+
+        let position = t0.translation.xy();
+        let heading = t0.local_x().xy();
+        let settings = with_traits(&settings, traits, mass);
+
+        // Skipping the boid itself, and any despawned since the index was last rebuilt
+        let neighbors = index.within_distance(position, settings.vis_range)
+            .into_iter()
+            .filter(|(point, other)| other.is_some_and(|other| other != *boid && boids.contains(other))
+                && perceives(&settings, heading, *point - position))
+            .count();
+
+        text.sections[0].value = format!("{boid:?}\nspeed {:.0}\nneighbors {neighbors}\n{state:?}", velocity.0.length());
+        transform.translation = (position + Vec2::Y * (settings.size + LABEL_OFFSET * zoom)).extend(LABEL_Z);
+        transform.scale = Vec3::splat(zoom);
+        *visibility = Visibility::Visible;
+    }
+
+    // New labels are filled in and placed from the next frame on
+    for boid in wanted.difference(&labelled) {
+        commands.spawn((
+            Text2dBundle {
+                text: Text::from_section("", TextStyle { font_size: 12.0, color: Color::WHITE, ..default() })
+                    .with_justify(JustifyText::Center),
+                text_anchor: Anchor::BottomCenter,
+                visibility: Visibility::Hidden,
+                ..default()
+            },
+            BoidLabel(*boid),
+        ));
+    }
+}
@@ -0,0 +1,63 @@
+use bevy::{math::Vec3Swizzles, prelude::*};
+use bevy_spatial::SpatialAccess;
+
+use crate::{index::BoidIndex, input::{Action, Actions}, settings::BoidSettings, CursorPosition, SpatialEntity};
+
+// Followed by perceiving boids of its species, and ignores cohesion itself
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
+pub struct Leader;
+
+// Steering towards the spot `leader_distance` behind a leader at `offset` moving at `velocity`
+pub fn follow(settings: &BoidSettings, offset: Vec2, velocity: Vec2) -> Vec2 {
+    let behind = offset - velocity.normalize_or_zero() * settings.leader_distance;
+    behind * settings.leader_follow_factor
+}
+
+// `L` makes the boid nearest the cursor a leader, or demotes it
+pub struct LeaderPlugin;
+
+impl Plugin for LeaderPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (toggle_leader, draw_leaders));
+    }
+}
+
+fn toggle_leader(
+    mut commands: Commands,
+    actions: Actions,
+    cursor: Res<CursorPosition>,
+    index: Res<BoidIndex>,
+    boids: Query<Has<Leader>, With<SpatialEntity>>,
+) {
+    if !actions.just_pressed(Action::Leader) {
+        return;
+    }
+    let Some(position) = cursor.0 else {
+        return;
+    };
+
+    // The index may still hold boids despawned since its last rebuild
+    let Some((boid, leader)) = index.nearest_neighbour(position)
+        .and_then(|(_, entity)| entity)
+        .and_then(|entity| boids.get(entity).ok().map(|leader| (entity, leader)))
+    else {
+        return;
+    };
+
+    if leader {
+        commands.entity(boid).remove::<Leader>();
+    } else {
+        commands.entity(boid).insert(Leader);
+    }
+}
+
+fn draw_leaders(
+    leaders: Query<&Transform, With<Leader>>,
+    settings: Res<BoidSettings>,
+    mut gizmos: Gizmos,
+) {
+    for transform in leaders.iter() {
+        gizmos.circle_2d(transform.translation.xy(), settings.size * 1.5, Color::GOLD);
+    }
+}
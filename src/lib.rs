@@ -0,0 +1,1354 @@
+use std::{
+    cell::RefCell,
+    sync::{atomic::{AtomicUsize, Ordering}, Mutex},
+};
+use rand::prelude::*;
+use thread_local::ThreadLocal;
+use halton::Sequence;
+use bevy::{
+    ecs::system::SystemParam,
+    math::Vec3Swizzles,
+    prelude::*,
+    render::{mesh::*, render_asset::RenderAssetUsages},
+    sprite::{Anchor, Mesh2dHandle},
+    utils::HashMap,
+    window::WindowResized,
+};
+
+mod attractors;
+mod audio;
+mod behavior;
+mod bench;
+mod boundary;
+mod camera;
+mod cli;
+mod capture;
+mod clusters;
+mod coloring;
+mod config;
+mod daynight;
+mod debug;
+mod emitters;
+mod energy;
+mod flocking;
+mod flow;
+mod food;
+mod game;
+mod gpu;
+mod grab;
+mod headless;
+mod heatmap;
+mod index;
+mod input;
+mod interpolation;
+mod labels;
+mod leaders;
+mod lifecycle;
+mod lod;
+mod migration;
+mod minimap;
+mod net;
+mod obstacles;
+mod pheromone;
+mod plot;
+mod player;
+mod predators;
+mod presets;
+mod recording;
+mod rewind;
+mod rng;
+mod roost;
+mod seek;
+mod selection;
+mod senses;
+mod settings;
+mod simulation;
+mod snapshot;
+mod spatial;
+mod spawning;
+mod species;
+mod stats;
+pub mod steering;
+mod telemetry;
+mod three_d;
+mod trails;
+mod variation;
+mod waypoints;
+#[cfg(feature = "inspector")]
+mod inspector;
+#[cfg(feature = "physics")]
+mod physics;
+#[cfg(feature = "scripting")]
+mod scripting;
+#[cfg(feature = "egui")]
+mod preset_files;
+#[cfg(feature = "egui")]
+mod ui;
+
+pub use headless::BoidsSimulation;
+pub use settings::{BoidSettings, BoundaryMode};
+
+use attractors::{AttractorPlugin, Attractors};
+use audio::FlockAudioPlugin;
+use behavior::{update_states, BoidState};
+use camera::{camera_under, CameraFollowPlugin};
+use clap::Parser;
+use cli::Cli;
+use capture::CapturePlugin;
+use clusters::ClusterPlugin;
+use coloring::ColoringPlugin;
+use boundary::{cycle_boundary, resize_boundary, seam_ghosts, wrap_offset, wrap_position, Boundary};
+use config::ConfigPlugin;
+use debug::{DebugOverlay, DebugOverlayPlugin};
+use emitters::{EmitterPlugin, EmitterViewPlugin};
+use energy::Energy;
+use flocking::{limit_travel, limit_velocity, perceives, Flight, Neighbor, NeighborLanes};
+use flow::{resize_flow_field, FlowField, FlowPaintPlugin, PaintingWind};
+use daynight::{DayNight, DayNightPlugin, DayNightViewPlugin};
+use food::{Foraging, FoodPlugin, FoodViewPlugin, Hunger};
+use game::{GamePlugin, GameViewPlugin};
+use grab::{GrabPlugin, Held};
+use heatmap::HeatmapPlugin;
+use index::{cycle_index, rebuild_index, BoidIndex};
+use input::{Action, Actions, InputMapPlugin};
+use interpolation::{track_transforms, InterpolationPlugin};
+use labels::LabelPlugin;
+use leaders::{Leader, LeaderPlugin};
+use lifecycle::LifecyclePlugin;
+use lod::{track_detail_region, DetailRegion, LodSteering};
+use migration::{Migration, MigrationPlugin, MigrationViewPlugin};
+use minimap::MinimapPlugin;
+use obstacles::{draw_obstacle_gizmos, hidden, Obstacle, ObstaclePlugin, Obstacles};
+use pheromone::{
+    deposit_pheromone,
+    resize_pheromone_field,
+    spread_pheromone,
+    PheromoneField,
+    PheromoneViewPlugin,
+};
+use player::{Player, PlayerPlugin};
+use plot::OrderPlotPlugin;
+use predators::{CatchBurstPlugin, Predator, PredatorPlugin, Predators};
+use presets::PresetPlugin;
+use recording::RecorderPlugin;
+use rewind::RewindPlugin;
+use rng::SimRng;
+use roost::{draw_roosts, Perched, RoostPlugin};
+use seek::{SeekPlugin, SeekTargets};
+use selection::{Selection, SelectionPlugin};
+use senses::sense;
+use settings::BoidRender;
+use simulation::{simulation_running, SimulationControlsPlugin, SimulationPlugin};
+use snapshot::SnapshotPlugin;
+use trails::TrailPlugin;
+use variation::{with_traits, BoidTraits, Mass};
+use waypoints::{advance_waypoints, WaypointPlugin, Waypoints};
+use spawning::SpawningPlugin;
+use species::{Interaction, Species, SpeciesInteractions, SHADES};
+use stats::{FlockStats, StatsPlugin};
+use steering::{SteeringBehaviors, SteeringContext, SteeringTerms};
+
+const WINDOW_BOUNDS: Vec2 = Vec2::new(800., 400.);
+const BOID_SPRITE: &str = "bird.png";
+// The sprite covers the mesh's extent, its origin a third of the way back from the tail
+const SPRITE_SIZE: Vec2 = Vec2::splat(1.5);
+const SPRITE_ANCHOR: Vec2 = Vec2::new(-1. / 6., 0.);
+
+// The app `cargo run` starts, from the command line it was given
+pub fn run() {
+    // `cargo run -- --help` lists every option
+    let cli = Cli::parse();
+
+    // `cargo run --release -- --headless` times the simulation without a window
+    if cli.headless {
+        bench::run(&cli);
+        return;
+    }
+
+    let net = net::NetRole::from_cli(&cli);
+    let mut app = App::new();
+
+    app.add_plugins(
+        DefaultPlugins.set(WindowPlugin {
+            primary_window: Some(Window {
+                canvas: Some("#bevy_boids_canvas".into()),
+                resolution: (cli.width, cli.height).into(),
+                resizable: true,
+                ..default()
+            }),
+            ..default()
+        }),
+    )
+    .add_plugins(InputMapPlugin);
+
+    // `cargo run -- --connect <host:port>` only shows a flock served elsewhere, steering it by cursor
+    if let Some(net::NetRole::Client(server)) = net {
+        app.add_plugins(net::NetClientPlugin { server }).run();
+        return;
+    }
+
+    // `cargo run -- --replay boids_recording.bin` plays back a run recorded with `R`
+    if let Some(path) = cli.replay.clone() {
+        app.add_plugins(recording::ReplayPlugin { path }).run();
+        return;
+    }
+
+    // `cargo run -- --boids 5000 --width 1280 --height 720` in place of the defaults
+    let mut settings = BoidSettings::default();
+    cli.apply(&mut settings);
+
+    app.insert_resource(settings)
+        .insert_resource(WorldBounds(Vec2::new(cli.width, cli.height)))
+        .insert_resource(cli.clone())
+        .add_plugins((ConfigPlugin, SimulationPlugin, SimulationControlsPlugin, InterpolationPlugin));
+
+    // `cargo run -- --3d` flies the flock in a boxed volume instead
+    if cli.three_d {
+        app.add_plugins(three_d::Boids3dPlugin);
+    } else if cli.gpu {
+        // `cargo run --release -- --gpu --boids 100000` flocks in compute shaders
+        app.add_plugins(gpu::GpuBoidsPlugin::from_cli(&cli));
+    } else {
+        app.add_plugins((BoidsPlugin, BoidsViewPlugin))
+            .add_plugins((SnapshotPlugin, RewindPlugin, SpawningPlugin, StatsPlugin, TrailPlugin, DebugOverlayPlugin))
+            .add_plugins((AttractorPlugin, CatchBurstPlugin, FlowPaintPlugin, FoodViewPlugin, LeaderPlugin))
+            .add_plugins((CameraFollowPlugin, HeatmapPlugin, PheromoneViewPlugin, PlayerPlugin, SelectionPlugin))
+            .add_plugins((CapturePlugin, FlockAudioPlugin, PresetPlugin, RecorderPlugin, WaypointPlugin))
+            .add_plugins((ClusterPlugin, ColoringPlugin, DayNightViewPlugin, EmitterViewPlugin, GameViewPlugin, MigrationViewPlugin, MinimapPlugin, OrderPlotPlugin))
+            .add_plugins(LabelPlugin);
+
+        #[cfg(feature = "physics")]
+        app.add_plugins(physics::PhysicsPlugin);
+
+        // `cargo run -- --telemetry flock.csv` logs flock metrics for offline analysis
+        if let Some(config) = telemetry::TelemetryConfig::from_cli(&cli) {
+            app.add_plugins(telemetry::TelemetryPlugin(config));
+        }
+
+        // `cargo run -- --serve [port]` also shares the flock with `--connect`ed clients
+        if let Some(net::NetRole::Server(port)) = net {
+            app.add_plugins(net::NetServerPlugin { port });
+        }
+    }
+
+    #[cfg(feature = "egui")]
+    app.add_plugins(ui::TuningPanelPlugin);
+
+    #[cfg(feature = "inspector")]
+    app.add_plugins(inspector::InspectorPlugin);
+
+    #[cfg(feature = "scripting")]
+    app.add_plugins(scripting::ScriptingPlugin);
+
+    app.run();
+}
+
+// The 2D simulation alone, runs without a window or renderer
+struct BoidsPlugin;
+
+impl Plugin for BoidsPlugin {
+    fn build(&self, app: &mut App) {
+        // Track boids in the spatial index, `I` switches its structure
+        app.init_resource::<BoidIndex>()
+            .init_resource::<DetailRegion>()
+            .init_resource::<Boundary>()
+            .init_resource::<Waypoints>()
+            .register_type::<Waypoints>()
+            .init_resource::<WorldBounds>()
+            .init_resource::<CursorPosition>()
+            .init_resource::<CursorRepel>()
+            .init_resource::<FlowField>()
+            .init_resource::<PheromoneField>()
+            .init_resource::<SteeringBehaviors>()
+            .register_type::<WorldBounds>()
+            .register_type::<SpatialEntity>()
+            .register_type::<Velocity>()
+            .register_type::<Acceleration>()
+            .register_type::<Travel>()
+            .register_type::<Wander>()
+            .register_type::<BoidColor>()
+            .register_type::<Energy>()
+            .register_type::<Species>()
+            .register_type::<BoidTraits>()
+            .register_type::<Mass>()
+            .register_type::<Leader>()
+            .register_type::<BoidState>()
+            .add_plugins((DayNightPlugin, EmitterPlugin, FoodPlugin, GamePlugin, GrabPlugin, LifecyclePlugin, MigrationPlugin, ObstaclePlugin, PredatorPlugin, RoostPlugin, SeekPlugin))
+            .add_systems(Startup, setup)
+            .configure_sets(FixedUpdate, (
+                BoidSet::Index,
+                BoidSet::Steer,
+                BoidSet::Velocity,
+                BoidSet::Movement,
+            ).chain().run_if(simulation_running))
+            .add_systems(FixedUpdate, (
+                (rebuild_index, advance_waypoints).in_set(BoidSet::Index),
+                (update_states, flocking_system).chain().in_set(BoidSet::Steer),
+                velocity_system.in_set(BoidSet::Velocity),
+                (movement_system, boundary_system, deposit_pheromone, spread_pheromone)
+                    .chain()
+                    .in_set(BoidSet::Movement),
+            ))
+            .add_systems(Update, (boid_count_system, resize_flow_field, resize_pheromone_field));
+    }
+}
+
+// Camera, meshes and gizmos for the 2D simulation, feeds it the window size and cursor
+struct BoidsViewPlugin;
+
+impl Plugin for BoidsViewPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BoidAssets>()
+            .add_systems(Startup, spawn_camera)
+            .add_systems(PreUpdate, (sync_world_bounds, track_cursor, track_cursor_repel, track_detail_region))
+            .add_systems(PostUpdate, (attach_boid_meshes, track_transforms::<Velocity>))
+            .add_systems(Update, (
+                cycle_index,
+                (cycle_boundary, resize_boundary).chain(),
+                draw_boid_gizmos,
+                draw_obstacle_gizmos,
+                draw_roosts,
+            ));
+    }
+}
+
+// Stages of a FixedUpdate tick, in order
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+enum BoidSet {
+    // Rebuild spatial trees from the current positions
+    Index,
+    // Compute each boid's Acceleration
+    Steer,
+    // Apply and reset accelerations, boundary steering and speed limits
+    Velocity,
+    // Integrate positions
+    Movement,
+}
+
+// Every boid, including perched ones, which are out of the index
+type AnyBoid = Or<(With<SpatialEntity>, With<Perched>)>;
+// Boids the simulation flies, rather than the player or a mouse holding them
+type Flown = (Without<Player>, Without<Held>);
+
+// Marker for entities tracked by KDTree
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
+struct SpatialEntity;
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct Velocity(Vec2);
+
+// How far velocity_system's integrator moved the boid over the tick, for movement_system to
+// apply; boids it doesn't fly, like the player, just move at their velocity
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
+struct Travel(Option<Vec2>);
+
+// Steering force for the coming tick, written while steering and consumed by velocity_system,
+// which divides it by the boid's mass, or handed to rapier under the physics feature
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
+struct Acceleration(Vec2);
+
+// Phase into the wander noise, so boids don't all drift in step
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
+struct Wander(f32);
+
+// Candidate neighbors from the boid's last index query, reused until they go stale
+#[derive(Component, Default)]
+struct NeighborCache {
+    entities: Vec<Entity>,
+    // Those of them the boid perceived on its last steer, flockmates and others it reacts to
+    perceived: Vec<Neighbor<Vec2>>,
+    // Flockmate candidates on their way to being perceived
+    lanes: NeighborLanes,
+    others: Vec<(Interaction, Neighbor<Vec2>)>,
+    // Obstacles that might hide any of them
+    occluders: Vec<(Obstacle, Vec2)>,
+    // Where the boid was when queried, and ticks since
+    anchor: Vec2,
+    age: u32,
+}
+
+// Kept on the entity so the simulation runs without any render assets
+#[derive(Component, Clone, Copy, Reflect)]
+#[reflect(Component)]
+struct BoidColor(Color);
+
+#[derive(Bundle)]
+struct BoidBundle {
+    spatial: SpatialBundle,
+    velocity: Velocity,
+    acceleration: Acceleration,
+    travel: Travel,
+    wander: Wander,
+    hunger: Hunger,
+    energy: Energy,
+    species: Species,
+    color: BoidColor,
+    neighbors: NeighborCache,
+    lod: LodSteering,
+    state: BoidState,
+}
+
+impl Default for BoidBundle {
+    fn default() -> Self {
+        Self {
+            spatial: Default::default(),
+            velocity: Velocity(Vec2::default()),
+            acceleration: Acceleration::default(),
+            travel: Travel::default(),
+            wander: Wander::default(),
+            hunger: Hunger::default(),
+            energy: Energy::default(),
+            species: Species::default(),
+            color: BoidColor(Color::WHITE),
+            neighbors: NeighborCache::default(),
+            lod: LodSteering::default(),
+            state: BoidState::default(),
+        }
+    }
+}
+
+// One mesh for every boid and one material per distinct color, so draws batch; sprites all
+// share the one texture, so batch regardless
+#[derive(Resource)]
+struct BoidAssets {
+    mesh: Mesh2dHandle,
+    // Keyed by the bits of the linear RGBA color
+    materials: HashMap<[u32; 4], Handle<ColorMaterial>>,
+    sprite: Handle<Image>,
+}
+
+impl FromWorld for BoidAssets {
+    fn from_world(world: &mut World) -> Self {
+        Self {
+            mesh: Mesh2dHandle(world.resource_mut::<Assets<Mesh>>().add(boid_mesh())),
+            materials: HashMap::new(),
+            sprite: world.resource::<AssetServer>().load(BOID_SPRITE),
+        }
+    }
+}
+
+impl BoidAssets {
+    fn material(&mut self, materials: &mut Assets<ColorMaterial>, color: Color) -> Handle<ColorMaterial> {
+        self.materials
+            .entry(color.as_linear_rgba_f32().map(f32::to_bits))
+            .or_insert_with(|| materials.add(color))
+            .clone()
+    }
+
+    fn attach(
+        &mut self,
+        commands: &mut Commands,
+        materials: &mut Assets<ColorMaterial>,
+        boid: Entity,
+        color: Color,
+        render: BoidRender,
+    ) {
+        let mut boid = commands.entity(boid);
+
+        match render {
+            BoidRender::Mesh => {
+                let material = self.material(materials, color);
+                boid.remove::<(Sprite, Handle<Image>)>()
+                    .insert((self.mesh.clone(), material));
+            }
+            BoidRender::Sprite => {
+                let sprite = Sprite {
+                    color,
+                    custom_size: Some(SPRITE_SIZE),
+                    anchor: Anchor::Custom(SPRITE_ANCHOR),
+                    ..default()
+                };
+
+                boid.remove::<(Mesh2dHandle, Handle<ColorMaterial>)>()
+                    .insert((sprite, self.sprite.clone()));
+            }
+        }
+    }
+}
+
+// Size of the play area, centered on the origin
+#[derive(Resource, Clone, Copy, PartialEq, Deref, Reflect)]
+#[reflect(Resource)]
+struct WorldBounds(Vec2);
+
+impl Default for WorldBounds {
+    fn default() -> Self {
+        Self(WINDOW_BOUNDS)
+    }
+}
+
+// Cursor in world space, None outside the window or when headless
+#[derive(Resource, Default)]
+struct CursorPosition(Option<Vec2>);
+
+// Whether the cursor pushes boids away instead of drawing them in
+#[derive(Resource, Default)]
+struct CursorRepel(bool);
+
+fn setup(
+    mut commands: Commands,
+    mut rng: ResMut<SimRng>,
+    bounds: Res<WorldBounds>,
+    settings: Res<BoidSettings>,
+) {
+    // Halton sequence for Boid spawns
+    let seq = halton::Sequence::new(2).zip(Sequence::new(3))
+        .take(settings.count);
+
+    for (x, y) in seq {
+        let spawn_x = (x as f32 * bounds.x) - bounds.x / 2.0;
+        let spawn_y = (y as f32 * bounds.y) - bounds.y / 2.0;
+
+        let spawn = BoidSpawn::random(&mut **rng, &settings, Vec2::new(spawn_x, spawn_y));
+        spawn_boid(&mut commands, &settings, spawn);
+    }
+}
+
+// The camera looking at the world, as opposed to overlays like the minimap
+#[derive(Component)]
+struct MainCamera;
+
+// Also the one the HUDs are drawn over, rather than whichever camera is ordered last
+fn spawn_camera(mut commands: Commands) {
+    commands.spawn((Camera2dBundle::default(), MainCamera, IsDefaultUiCamera));
+}
+
+fn boid_mesh() -> Mesh {
+    Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, vec![
+            [-0.5, 0.5, 0.0],
+            [1.0, 0.0, 0.0],
+            [-0.5, -0.5, 0.0],
+            [0.0, 0.0, 0.0],
+        ])
+        .with_inserted_indices(Indices::U32(vec![
+            1, 3, 0,
+            1, 2, 3,
+        ]))
+}
+
+// Initial state for a new boid
+struct BoidSpawn {
+    position: Vec2,
+    velocity: Vec2,
+    species: Species,
+    color: Color,
+    wander_phase: f32,
+    traits: Option<BoidTraits>,
+    mass: Option<Mass>,
+}
+
+impl BoidSpawn {
+    // Random velocity and species at `position`
+    fn random(rng: &mut impl Rng, settings: &BoidSettings, position: Vec2) -> Self {
+        let velocity = Vec2::new(rng.random_range(-1.0..1.0),
+                                 rng.random_range(-1.0..1.0)) * settings.min_speed;
+
+        let species = Species(rng.random_range(0..settings.species_count.max(1)));
+
+        Self {
+            position,
+            velocity,
+            species,
+            // Species hue, random saturation for each boid
+            color: species.color(settings.species_count, rng.random_range(0..SHADES)),
+            wander_phase: rng.random_range(0.0..std::f32::consts::TAU),
+            traits: (settings.trait_variation > 0.0).then(|| BoidTraits::random(rng, settings)),
+            mass: (settings.mass_variation > 0.0).then(|| Mass::random(rng, settings)),
+        }
+    }
+}
+
+fn spawn_boid(
+    commands: &mut Commands,
+    settings: &BoidSettings,
+    spawn: BoidSpawn,
+) -> Entity {
+    let mut transform = Transform::from_translation(spawn.position.extend(0.0))
+        .with_scale(Vec3::splat(settings.size * spawn.mass.map_or(1.0, |mass| mass.scale())));
+
+    transform.rotate_z(angle_towards(Vec2::ZERO, spawn.velocity));
+
+    let mut boid = commands.spawn((
+        BoidBundle {
+            spatial: SpatialBundle::from_transform(transform),
+            velocity: Velocity(spawn.velocity),
+            acceleration: Acceleration::default(),
+            wander: Wander(spawn.wander_phase),
+            hunger: Hunger::default(),
+            energy: Energy::default(),
+            species: spawn.species,
+            color: BoidColor(spawn.color),
+            ..default()
+        },
+        SpatialEntity
+    ));
+
+    if let Some(traits) = spawn.traits {
+        boid.insert(traits);
+    }
+    if let Some(mass) = spawn.mass {
+        boid.insert(mass);
+    }
+
+    boid.id()
+}
+
+// Grow or shrink the flock whenever the configured boid count changes
+fn boid_count_system(
+    mut commands: Commands,
+    mut rng: ResMut<SimRng>,
+    boids: Query<Entity, AnyBoid>,
+    // The player and any boid being held are never among those cut
+    removable: Query<Entity, (AnyBoid, Flown)>,
+    bounds: Res<WorldBounds>,
+    settings: Res<BoidSettings>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    let current = boids.iter().len();
+
+    if current < settings.count {
+        let half = **bounds / 2.0;
+
+        for _ in current..settings.count {
+            let position = Vec2::new(rng.random_range(-half.x..half.x),
+                                     rng.random_range(-half.y..half.y));
+            let spawn = BoidSpawn::random(&mut **rng, &settings, position);
+            spawn_boid(&mut commands, &settings, spawn);
+        }
+    } else {
+        for boid in removable.iter().take(current - settings.count) {
+            commands.entity(boid).despawn();
+        }
+    }
+}
+
+// Records a flock grown or shrunk outside boid_count_system as the configured count, without
+// it taking that as a change to spawn or despawn the difference for
+fn sync_boid_count(settings: &mut ResMut<BoidSettings>, count: usize) {
+    settings.bypass_change_detection().count = count;
+}
+
+// Boids and predators get a mesh or sprite once spawned by the simulation, and every one is
+// redrawn the other way when `render` changes
+fn attach_boid_meshes(
+    mut commands: Commands,
+    mut assets: ResMut<BoidAssets>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    added: Query<(Entity, &BoidColor), Added<BoidColor>>,
+    boids: Query<(Entity, &BoidColor)>,
+    // Absent for replicas of a networked or recorded flock, which are always meshes
+    settings: Option<Res<BoidSettings>>,
+    mut current: Local<BoidRender>,
+) {
+    let render = settings.map_or(BoidRender::Mesh, |settings| settings.render);
+
+    if render != *current {
+        *current = render;
+        for (boid, color) in boids.iter() {
+            assets.attach(&mut commands, &mut materials, boid, color.0, render);
+        }
+        return;
+    }
+
+    for (boid, color) in added.iter() {
+        assets.attach(&mut commands, &mut materials, boid, color.0, render);
+    }
+}
+
+fn draw_boid_gizmos(
+    boundary: Res<Boundary>,
+    bounds: Res<WorldBounds>,
+    mut gizmos: Gizmos,
+    settings: Res<BoidSettings>,
+) {
+    // Wrapping boids have no margin to steer back from
+    if settings.boundary_mode == BoundaryMode::Wrap {
+        return;
+    }
+
+    boundary.draw(&mut gizmos, **bounds, settings.boundary_size);
+}
+
+// Only the latest size matters when several resizes arrive in one frame
+fn sync_world_bounds(mut resized: EventReader<WindowResized>, mut bounds: ResMut<WorldBounds>) {
+    if let Some(event) = resized.read().last() {
+        bounds.set_if_neq(WorldBounds(Vec2::new(event.width, event.height)));
+    }
+}
+
+// The mouse, or on touch screens the center of the fingers down
+fn track_cursor(
+    cameras: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    window: Query<&Window>,
+    touches: Res<Touches>,
+    mut cursor: ResMut<CursorPosition>,
+) {
+    let fingers = touches.iter().count();
+    let touch = (fingers > 0)
+        .then(|| touches.iter().map(|touch| touch.position()).sum::<Vec2>() / fingers as f32);
+
+    // Through whichever view it's over, when split
+    cursor.0 = window.single().cursor_position()
+        .or(touch)
+        .and_then(|c_window| camera_under(cameras.iter(), c_window))
+        .and_then(|(camera, t_camera, c_viewport)| camera.viewport_to_world_2d(t_camera, c_viewport));
+}
+
+type BoundedQuery = (
+    Entity,
+    &'static mut Velocity,
+    &'static mut Transform,
+    Has<SpatialEntity>,
+    Has<Player>,
+);
+
+// Bounce boids off the boundary, or replace the ones that crossed it
+fn boundary_system(
+    mut commands: Commands,
+    mut boids: Query<BoundedQuery, Without<Perched>>,
+    mut rng: ResMut<SimRng>,
+    boundary: Res<Boundary>,
+    bounds: Res<WorldBounds>,
+    settings: Res<BoidSettings>,
+) {
+    if !matches!(settings.boundary_mode, BoundaryMode::Bounce | BoundaryMode::Kill) {
+        return;
+    }
+    let world = **bounds;
+
+    for (entity, mut velocity, mut transform, prey, player) in boids.iter_mut() {
+        let position = transform.translation.xy();
+        let outside = boundary.distance(position, world, settings.boundary_size);
+        if outside <= 0.0 {
+            continue;
+        }
+
+        // Predators and the player bounce either way
+        if settings.boundary_mode == BoundaryMode::Kill && prey && !player {
+            commands.entity(entity).despawn();
+
+            let position = boundary.random_point(&mut **rng, world, settings.boundary_size);
+            let spawn = BoidSpawn::random(&mut **rng, &settings, position);
+            spawn_boid(&mut commands, &settings, spawn);
+            continue;
+        }
+
+        let normal = boundary.normal(position, world, settings.boundary_size);
+        let bounced = position - normal * 2.0 * outside;
+        transform.translation.x = bounced.x;
+        transform.translation.y = bounced.y;
+
+        let into_wall = velocity.0.dot(normal);
+        if into_wall > 0.0 {
+            velocity.0 -= 2.0 * into_wall * normal;
+            transform.rotation = Quat::from_axis_angle(Vec3::Z, angle_towards(Vec2::ZERO, velocity.0));
+        }
+    }
+}
+
+fn angle_towards(a: Vec2, b: Vec2) -> f32 {
+    // https://stackoverflow.com/a/68929139
+    let dir = b - a;
+    dir.y.atan2(dir.x)
+}
+
+// Held right button or two fingers repel, unless the button is busy despawning boids or clearing wind
+fn track_cursor_repel(
+    actions: Actions,
+    touches: Res<Touches>,
+    painting: Option<Res<PaintingWind>>,
+    mut repel: ResMut<CursorRepel>,
+) {
+    let shift = actions.pressed(Action::Alternate);
+    let painting = painting.is_some_and(|painting| painting.0);
+
+    repel.0 = (actions.pressed(Action::Repel) && !shift && !painting) || touches.iter().count() >= 2;
+}
+
+// Everything flocking_dv reads, shared by reference across the worker tasks
+#[derive(SystemParam)]
+struct FlockingParams<'w, 's> {
+    index: Res<'w, BoidIndex>,
+    boids: Query<'w, 's, BoidQuery, With<SpatialEntity>>,
+    behaviors: Res<'w, SteeringBehaviors>,
+    obstacles: Obstacles<'w, 's>,
+    predators: Predators<'w>,
+    flow: Res<'w, FlowField>,
+    pheromone: Res<'w, PheromoneField>,
+    attractors: Attractors<'w, 's>,
+    foraging: Foraging<'w, 's>,
+    leaders: Query<'w, 's, (), With<Leader>>,
+    time: Res<'w, Time>,
+    cursor: Res<'w, CursorPosition>,
+    repel: Res<'w, CursorRepel>,
+    waypoints: Res<'w, Waypoints>,
+    seek: SeekTargets<'w, 's>,
+    boundary: Res<'w, Boundary>,
+    bounds: Res<'w, WorldBounds>,
+    settings: Res<'w, BoidSettings>,
+    interactions: Res<'w, SpeciesInteractions>,
+    day_night: Res<'w, DayNight>,
+    migration: Res<'w, Migration>,
+    rng: Res<'w, SimRng>,
+    // Absent in the 3D and GPU modes
+    #[cfg(feature = "scripting")]
+    script: Option<Res<'w, scripting::ScriptedSteering>>,
+}
+
+type BoidQuery = (Entity, &'static Velocity, &'static Transform, &'static Species);
+
+#[allow(clippy::too_many_arguments)]
+fn flocking_dv(
+    params: &FlockingParams,
+    boid: &Entity,
+    t0: &Transform,
+    species: Species,
+    state: BoidState,
+    wander: &Wander,
+    traits: Option<&BoidTraits>,
+    mass: Option<&Mass>,
+    cache: &mut NeighborCache,
+    tick: u32,
+    // Filled with the offsets of neighbors that contributed, for the debug overlay and stats
+    used: Option<&mut Vec<Vec2>>,
+    // Filled with each behavior's share, for the selection panel
+    terms: Option<&mut SteeringTerms>,
+) -> Vec2 {
+    let settings = params.day_night.settings(&params.settings);
+    let settings = with_traits(&settings, traits, mass);
+    let settings = &*settings;
+    let position = t0.translation.xy();
+    let world = **params.bounds;
+    let wrap = settings.boundary_mode == BoundaryMode::Wrap;
+
+    let stale = cache.age == 0
+        || cache.age >= settings.neighbor_refresh_ticks
+        || position.distance(cache.anchor) > settings.neighbor_refresh_distance;
+
+    if stale {
+        // Reach a little further while caching, for boids closing in before the next query
+        let range = match settings.neighbor_refresh_ticks {
+            0 | 1 => settings.vis_range,
+            _ => settings.vis_range + settings.neighbor_refresh_distance,
+        };
+        let query = settings.neighbor_query;
+        let mut candidates = params.index.neighbors(position, range, settings.neighbor_cap, query);
+
+        if wrap {
+            // Flocks stay coherent across the seam
+            for ghost in seam_ghosts(position, world, range) {
+                candidates.extend(params.index.neighbors(ghost, range, settings.neighbor_cap, query));
+            }
+            candidates.sort_unstable_by_key(|(_, entity)| *entity);
+            candidates.dedup_by_key(|(_, entity)| *entity);
+        }
+
+        cache.entities.clear();
+        cache.entities.extend(candidates.into_iter().filter_map(|(_, entity)| entity));
+        cache.anchor = position;
+        cache.age = 0;
+    }
+    cache.age += 1;
+
+    let heading = (t0.rotation * Vec3::X).xy();
+    let mut nearest_leader: Option<(Vec2, Vec2)> = None;
+
+    // Reuses the cache's buffers, so steering doesn't allocate once warmed up
+    let NeighborCache { entities, perceived, others, lanes, occluders, .. } = cache;
+    perceived.clear();
+    others.clear();
+    lanes.clear();
+    params.obstacles.occluders(settings, position, occluders);
+
+    for (other, v1, t1, s1) in entities.iter().filter_map(|entity| params.boids.get(*entity).ok()) {
+        // Cached entries, like the index, lag behind despawns. Don't evaluate against itself
+        let interaction = params.interactions.get(species, *s1);
+        if other == *boid || interaction == Interaction::Ignore {
+            continue;
+        }
+
+        let offset = (t1.translation - t0.translation).xy();
+        let offset = if wrap { wrap_offset(offset, world) } else { offset };
+        if hidden(occluders, position, offset) {
+            continue;
+        }
+        // What the boid makes of it, if it notices it at all
+        let Some((offset, velocity)) = sense(settings, params.rng.seed, tick, *boid, other, offset, v1.0) else {
+            continue;
+        };
+
+        // Flockmates are checked for being perceived in bulk along with the rules, bar the
+        // few that lead
+        if interaction == Interaction::Flock {
+            lanes.push(offset, velocity);
+
+            let closer = nearest_leader.is_none_or(|(nearest, _)| offset.length_squared() < nearest.length_squared());
+            if closer && params.leaders.contains(other) && perceives(settings, heading, offset) {
+                nearest_leader = Some((offset, velocity));
+            }
+        } else if perceives(settings, heading, offset) {
+            others.push((interaction, Neighbor { offset, velocity }));
+        }
+    }
+    let rules = lanes.rules(settings, heading, perceived);
+
+    if let Some(used) = used {
+        used.extend(perceived.iter().map(|neighbor| neighbor.offset));
+    }
+
+    let ctx = SteeringContext {
+        params,
+        settings,
+        boid: *boid,
+        position,
+        velocity: params.boids.get(*boid).map_or(Vec2::ZERO, |(_, velocity, _, _)| velocity.0),
+        heading,
+        neighbors: perceived,
+        rules,
+        others,
+        nearest_leader,
+        leader: params.leaders.contains(*boid),
+        wander: wander.0,
+    };
+
+    params.behaviors.steer(&ctx, &state.weights(settings), terms)
+}
+
+type FlockerQuery = (
+    Entity,
+    &'static Transform,
+    &'static Species,
+    &'static BoidState,
+    &'static Wander,
+    Option<&'static BoidTraits>,
+    Option<&'static Mass>,
+    &'static mut NeighborCache,
+    &'static mut LodSteering,
+    &'static mut Acceleration,
+);
+
+// Reused by whichever boids a thread steers, and from tick to tick
+#[derive(Default)]
+struct SteeringScratch {
+    used: Vec<Vec2>,
+    links: Vec<(Vec2, Vec2)>,
+}
+
+#[allow(clippy::too_many_arguments)]
+fn flocking_system(
+    params: FlockingParams,
+    mut accelerations: Query<FlockerQuery, (With<SpatialEntity>, Flown)>,
+    // Absent when headless
+    mut overlay: Option<ResMut<DebugOverlay>>,
+    mut stats: Option<ResMut<FlockStats>>,
+    mut selection: Option<ResMut<Selection>>,
+    region: Res<DetailRegion>,
+    mut tick: Local<u32>,
+    mut scratch: Local<ThreadLocal<RefCell<SteeringScratch>>>,
+) {
+    *tick = tick.wrapping_add(1);
+    let debug = overlay.as_ref().is_some_and(|overlay| overlay.enabled);
+    let counting = stats.as_ref().is_some_and(|stats| stats.collecting());
+    let collect = debug || counting;
+    let selected = selection.as_ref().and_then(|selection| selection.boid);
+
+    let neighbors = AtomicUsize::new(0);
+    let candidates = AtomicUsize::new(0);
+    let inspected = Mutex::new(None);
+
+    // Each boid only writes its own Acceleration, reading everyone's Velocity and Transform
+    accelerations.par_iter_mut().for_each(|(boid, t0, species, state, wander, traits, mass, mut cache, mut lod, mut acceleration)| {
+        let inspecting = selected == Some(boid);
+
+        // Boids out of view coast on their last steering between updates
+        if !inspecting && !region.steers(&params.settings, boid, t0.translation.xy(), *tick) {
+            acceleration.0 = lod.0;
+            return;
+        }
+
+        let mut scratch = scratch.get_or_default().borrow_mut();
+        let SteeringScratch { used, links } = &mut *scratch;
+        used.clear();
+
+        let mut terms = SteeringTerms::default();
+        acceleration.0 = flocking_dv(
+            &params, &boid, t0, *species, *state, wander, traits, mass, &mut cache, *tick,
+            (collect || inspecting).then_some(used),
+            inspecting.then_some(&mut terms),
+        );
+        lod.0 = acceleration.0;
+
+        if inspecting {
+            *inspected.lock().unwrap() = Some((terms, used.len()));
+        }
+        if collect {
+            neighbors.fetch_add(used.len(), Ordering::Relaxed);
+            candidates.fetch_add(cache.entities.len(), Ordering::Relaxed);
+        }
+        if debug {
+            let position = t0.translation.xy();
+            links.extend(used.iter().map(|offset| (position, position + *offset)));
+        }
+    });
+
+    if let Some(overlay) = overlay.as_mut() {
+        overlay.links.clear();
+        for scratch in scratch.iter_mut() {
+            overlay.links.append(&mut scratch.get_mut().links);
+        }
+    }
+    if let Some(stats) = stats.as_mut() {
+        stats.neighbors = neighbors.into_inner();
+        stats.candidates = candidates.into_inner();
+    }
+    if let Some(selection) = selection.as_mut() {
+        // The player boid isn't steered here, so has nothing to report
+        let (terms, neighbors) = inspected.into_inner().unwrap().unwrap_or_default();
+        selection.terms = terms;
+        selection.neighbors = neighbors;
+    }
+}
+
+type SteeredQuery = (
+    &'static mut Velocity,
+    &'static mut Acceleration,
+    Option<&'static mut Travel>,
+    &'static Transform,
+    Option<&'static BoidTraits>,
+    Option<&'static Mass>,
+    Option<&'static mut Energy>,
+    Has<Predator>,
+);
+
+fn velocity_system(
+    mut boids: Query<SteeredQuery, (Flown, Without<Perched>, Unsimulated)>,
+    boundary: Res<Boundary>,
+    bounds: Res<WorldBounds>,
+    time: Res<Time>,
+    settings: Res<BoidSettings>,
+) {
+    let dt = time.delta_seconds();
+
+    // Like steering, each boid only touches its own components
+    boids.par_iter_mut().for_each(|(mut velocity, mut acceleration, travel, transform, traits, mass, energy, predator)| {
+        let settings = with_traits(&settings, traits, mass);
+        let position = transform.translation.xy();
+        let flight = steered_flight(&settings, &mut acceleration, position, mass, energy.as_deref(), predator, &boundary, **bounds);
+        let max_speed = flight.max_speed;
+
+        let before = velocity.0;
+        let (after, travelled) = flight.step(settings.integrator, before, dt);
+
+        // The limits apply to the velocity the boid ends the tick with
+        velocity.0 = limit_velocity(&settings, before, after, max_speed, dt);
+
+        // And so to the path it flies there by
+        if let Some(mut travel) = travel {
+            let top_speed = (settings.drag <= 0.0).then_some(max_speed);
+            travel.0 = Some(limit_travel(travelled, after, velocity.0, top_speed, dt));
+        }
+
+        if let Some(mut energy) = energy {
+            energy.spend(&settings, before, velocity.0, dt);
+        }
+    });
+}
+
+// What a flown boid makes of this tick's steering, which it consumes, against its mass and top
+// speed
+#[allow(clippy::too_many_arguments)]
+fn steered_flight(
+    settings: &BoidSettings,
+    acceleration: &mut Acceleration,
+    position: Vec2,
+    mass: Option<&Mass>,
+    energy: Option<&Energy>,
+    predator: bool,
+    boundary: &Boundary,
+    bounds: Vec2,
+) -> Flight<Vec2> {
+    let max_speed = top_speed(settings, energy, predator);
+
+    // Steering yields forces, so heavier boids answer them more slowly
+    let mut force = std::mem::take(&mut acceleration.0);
+
+    // Steer back into the boundary, wrapping boids are handled in movement_system. Every
+    // tick, even for boids coasting on older steering out of view, so none overshoot
+    if settings.boundary_mode == BoundaryMode::Steer {
+        force += boundary.turn(position, bounds, settings.boundary_size) * settings.turn_factor;
+    }
+
+    let mass = mass.map_or(1.0, |mass| mass.0.max(f32::EPSILON));
+    Flight { force, mass, max_speed, drag: settings.drag }
+}
+
+// How fast a flown boid can go, slower once it tires
+fn top_speed(settings: &BoidSettings, energy: Option<&Energy>, predator: bool) -> f32 {
+    let max_speed = if predator { settings.predator_max_speed } else { settings.max_speed };
+    energy.map_or(max_speed, |energy| {
+        (max_speed * energy.max_speed_scale()).max(settings.min_speed)
+    })
+}
+
+// Rapier bodies are moved by the physics step instead
+#[cfg(feature = "physics")]
+type Unsimulated = Without<bevy_rapier2d::prelude::RigidBody>;
+#[cfg(not(feature = "physics"))]
+type Unsimulated = ();
+
+type Moving = (Without<Perched>, Without<Held>, Unsimulated);
+
+fn movement_system(
+    mut query: Query<(&Velocity, &mut Transform, Option<&mut Travel>), Moving>,
+    bounds: Res<WorldBounds>,
+    time: Res<Time>,
+    settings: Res<BoidSettings>,
+) {
+    let world = **bounds;
+    let dt = time.delta_seconds();
+
+    query.par_iter_mut().for_each(|(velocity, mut transform, travel)| {
+        // Facing along the velocity, which velocity_system turns no faster than `max_turn_rate`
+        transform.rotation = Quat::from_axis_angle(
+            Vec3::Z, angle_towards(Vec2::ZERO, velocity.0)
+        );
+        let travelled = travel.and_then(|mut travel| travel.0.take()).unwrap_or(velocity.0 * dt);
+        transform.translation.x += travelled.x;
+        transform.translation.y += travelled.y;
+
+        if settings.boundary_mode == BoundaryMode::Wrap {
+            let wrapped = wrap_position(transform.translation.xy(), world);
+            transform.translation.x = wrapped.x;
+            transform.translation.y = wrapped.y;
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bench::headless_app;
+
+    const TICKS: usize = 30;
+
+    // Despawns every third boid every few ticks, from wherever in the tick it's scheduled
+    fn cull(mut commands: Commands, boids: Query<Entity, With<SpatialEntity>>, mut tick: Local<usize>) {
+        *tick += 1;
+        if tick.is_multiple_of(5) {
+            for boid in boids.iter().step_by(3) {
+                commands.entity(boid).despawn();
+            }
+        }
+    }
+
+    fn run(settings: BoidSettings, build: impl FnOnce(&mut App)) -> App {
+        let mut app = headless_app(settings, Some(1), build);
+        for _ in 0..TICKS {
+            app.update();
+        }
+        app
+    }
+
+    fn assert_flying(app: &mut App) {
+        let mut boids = app.world.query_filtered::<(&Velocity, &Transform), With<SpatialEntity>>();
+        for (velocity, transform) in boids.iter(&app.world) {
+            assert!(velocity.0.is_finite() && transform.translation.is_finite());
+        }
+    }
+
+    fn boid_count(app: &mut App) -> usize {
+        app.world.query_filtered::<(), With<SpatialEntity>>().iter(&app.world).len()
+    }
+
+    fn settings() -> BoidSettings {
+        BoidSettings { count: 300, ..default() }
+    }
+
+    #[test]
+    fn despawned_after_indexing_are_skipped() {
+        // The index still holds them when flocking queries it
+        let mut app = run(settings(), |app| {
+            app.add_systems(FixedUpdate, cull.after(BoidSet::Index).before(BoidSet::Steer));
+        });
+        assert!(boid_count(&mut app) < 300);
+        assert_flying(&mut app);
+    }
+
+    #[test]
+    fn despawned_while_cached_are_skipped() {
+        // Neighbor caches hold them for several ticks after the index has let go
+        let settings = BoidSettings { neighbor_refresh_ticks: 8, boundary_mode: BoundaryMode::Wrap, ..settings() };
+        let mut app = run(settings, |app| {
+            app.add_systems(FixedUpdate, cull.before(BoidSet::Index));
+        });
+        assert!(boid_count(&mut app) < 300);
+        assert_flying(&mut app);
+    }
+
+    #[test]
+    fn despawned_after_steering_are_skipped() {
+        let mut app = run(settings(), |app| {
+            app.add_systems(FixedUpdate, cull.after(BoidSet::Steer).before(BoidSet::Velocity));
+        });
+        assert_flying(&mut app);
+    }
+
+    #[test]
+    fn spawned_after_indexing_flock() {
+        // Not in the index yet, but queried for neighbors all the same
+        let spawn = |mut commands: Commands, settings: Res<BoidSettings>, mut rng: ResMut<SimRng>| {
+            let spawn = BoidSpawn::random(&mut **rng, &settings, Vec2::ZERO);
+            spawn_boid(&mut commands, &settings, spawn);
+        };
+        let mut app = run(settings(), |app| {
+            app.add_systems(FixedUpdate, spawn.after(BoidSet::Index).before(BoidSet::Steer));
+        });
+        assert_eq!(boid_count(&mut app), 300 + TICKS);
+        assert_flying(&mut app);
+    }
+
+    #[test]
+    fn shrinking_the_flock_mid_run() {
+        let mut app = run(settings(), |_| {});
+        app.world.resource_mut::<BoidSettings>().count = 100;
+        for _ in 0..TICKS {
+            app.update();
+        }
+        assert_eq!(boid_count(&mut app), 100);
+        assert_flying(&mut app);
+    }
+
+    #[test]
+    fn turns_are_limited_to_the_turn_rate() {
+        use crate::flocking::limit_turn;
+
+        let max = 10_f32.to_radians();
+
+        // Reversing swings only `max` round, either way, keeping the new speed
+        let reversed = limit_turn(Vec2::X * 100., Vec2::new(-200., 1.), max);
+        assert!((reversed.length() - 200.).abs() < 0.1);
+        assert!((Vec2::X.angle_between(reversed) - max).abs() < 1e-4);
+        let reversed = limit_turn(Vec2::X * 100., Vec2::new(-200., -1.), max);
+        assert!((Vec2::X.angle_between(reversed) + max).abs() < 1e-4);
+
+        let gentle = Vec2::from_angle(5_f32.to_radians()) * 100.;
+        assert_eq!(limit_turn(Vec2::X * 100., gentle, max), gentle);
+        assert_eq!(limit_turn(Vec2::X, -Vec2::X, 0.0), -Vec2::X);
+
+        // And in 3D about whichever axis it turns, even right round
+        let climbing = limit_turn(Vec3::X * 100., Vec3::new(0., 0., 200.), max);
+        assert!((climbing.length() - 200.).abs() < 0.1);
+        assert!((Vec3::X.angle_between(climbing) - max).abs() < 1e-4);
+        assert!(climbing.y.abs() < 1e-4 && climbing.z > 0.0);
+        let reversed = limit_turn(Vec3::X * 100., -Vec3::X * 100., max);
+        assert!((Vec3::X.angle_between(reversed) - max).abs() < 1e-4);
+    }
+
+    #[test]
+    fn boids_travel_no_faster_than_the_speed_cap() {
+        use crate::settings::Integrator;
+
+        for integrator in [Integrator::Euler, Integrator::SemiImplicit, Integrator::Rk4] {
+            let capped = BoidSettings { drag: 0.0, integrator, ..settings() };
+            let mut app = run(capped, |_| {});
+            let mut boids = app.world.query_filtered::<(Entity, &Transform), With<SpatialEntity>>();
+            let before: HashMap<Entity, Vec2> = boids.iter(&app.world).map(|(boid, t)| (boid, t.translation.xy())).collect();
+            app.update();
+
+            let dt = app.world.resource::<Time<Fixed>>().timestep().as_secs_f32();
+            let max = app.world.resource::<BoidSettings>().max_speed * dt;
+            for (boid, transform) in boids.iter(&app.world) {
+                let moved = transform.translation.xy().distance(before[&boid]);
+                assert!(moved <= max * 1.001, "{integrator:?} moved {moved} in a tick, past {max}");
+            }
+        }
+    }
+
+    #[test]
+    fn headless_boids_seek_any_target() {
+        let target = Vec2::new(300., 0.);
+        let mut app = run(settings(), |_| {});
+        let lure = app.world.spawn(TransformBundle::from_transform(Transform::from_translation(target.extend(0.0)))).id();
+
+        let mut boids = app.world.query_filtered::<(Entity, &Transform), With<SpatialEntity>>();
+        let centroid = |app: &mut App, boids: &mut QueryState<_, _>| {
+            let positions: Vec<Vec2> = boids.iter(&app.world).map(|(_, t): (Entity, &Transform)| t.translation.xy()).collect();
+            positions.iter().sum::<Vec2>() / positions.len() as f32
+        };
+        let before = centroid(&mut app, &mut boids).distance(target);
+
+        let seekers: Vec<Entity> = boids.iter(&app.world).map(|(boid, _)| boid).collect();
+        for boid in seekers {
+            app.world.entity_mut(boid).insert(crate::seek::SeekTarget(lure));
+        }
+        for _ in 0..TICKS * 2 {
+            app.update();
+        }
+        assert!(centroid(&mut app, &mut boids).distance(target) < before * 0.8);
+    }
+
+    #[test]
+    fn emitters_stream_boids_in_up_to_their_cap() {
+        use crate::emitters::{Emitted, Emitter};
+
+        let mut app = run(BoidSettings { count: 50, ..settings() }, |app| {
+            app.world.spawn((Emitter::new(600.0, 0.0, 0.5, 200.0, Some(80)), TransformBundle::default()));
+        });
+        assert_eq!(boid_count(&mut app), 80);
+        assert_eq!(app.world.resource::<BoidSettings>().count, 80);
+
+        // The starting flock went first, then the earliest hatched
+        let mut emitted = app.world.query::<&Emitted>();
+        let first = emitted.iter(&app.world).min().unwrap().0;
+        assert!(emitted.iter(&app.world).len() == 80 && first > 0);
+        assert_flying(&mut app);
+    }
+
+    #[test]
+    fn boids_die_of_age_and_breed() {
+        let mortal = BoidSettings { lifespan: 0.3, breed_chance: 0.0, ..settings() };
+        let mut app = run(mortal, |_| {});
+        assert_eq!(boid_count(&mut app), 0);
+
+        let breeding = BoidSettings { lifespan: 600.0, breed_chance: 50.0, breed_range: 40.0, ..settings() };
+        let mut app = run(breeding, |_| {});
+        let count = boid_count(&mut app);
+        assert!(count > 300, "{count} boids");
+        assert_eq!(app.world.resource::<BoidSettings>().count, count);
+        assert_flying(&mut app);
+    }
+
+    #[test]
+    fn held_boids_are_carried_and_flung() {
+        let mut app = run(settings(), |_| {});
+        let boid = app.world.query_filtered::<Entity, With<SpatialEntity>>().iter(&app.world).next().unwrap();
+        app.world.entity_mut(boid).insert(Held);
+
+        // Dragged 4 units right every tick
+        let step = Vec2::new(4.0, 0.0);
+        for tick in 0..TICKS {
+            app.world.resource_mut::<grab::Grab>().target = step * tick as f32;
+            app.update();
+        }
+        let position = app.world.get::<Transform>(boid).unwrap().translation.xy();
+        assert_eq!(position, step * (TICKS - 1) as f32);
+
+        let dt = app.world.resource::<Time<Fixed>>().timestep().as_secs_f32();
+        let fling = app.world.get::<Velocity>(boid).unwrap().0;
+        assert!((fling - step / dt).length() < 1.0, "flung at {fling}");
+
+        app.world.entity_mut(boid).remove::<Held>();
+        app.update();
+        let released = app.world.get::<Transform>(boid).unwrap().translation.xy();
+        assert!(released.x > position.x, "flies on from {position} to {released}");
+    }
+}
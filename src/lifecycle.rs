@@ -0,0 +1,156 @@
+use rand::prelude::*;
+use bevy::{math::Vec3Swizzles, prelude::*, utils::HashSet};
+use bevy_spatial::SpatialAccess;
+
+use crate::{
+    food::Hunger,
+    index::BoidIndex,
+    player::Player,
+    rng::SimRng,
+    settings::BoidSettings,
+    spawn_boid,
+    sync_boid_count,
+    species::{Species, SHADES},
+    variation::{BoidTraits, Mass},
+    AnyBoid,
+    BoidSet,
+    BoidSpawn,
+    Flown,
+    SpatialEntity,
+    Velocity,
+};
+
+// Boids this well fed or better can breed, and doing so leaves both parents this much hungrier
+const WELL_FED: f32 = 0.25;
+const BREED_COST: f32 = 0.5;
+// How far lifespans stray from `lifespan` either way, as a fraction
+const LIFESPAN_SPREAD: f32 = 0.2;
+
+// Seconds a boid has lived, and its share of `lifespan` to live for
+#[derive(Component, Clone, Copy, Debug, Reflect)]
+#[reflect(Component)]
+pub struct Age {
+    pub seconds: f32,
+    pub span: f32,
+}
+
+impl Age {
+    fn newborn(rng: &mut impl Rng) -> Self {
+        Self { seconds: 0.0, span: 1.0 + LIFESPAN_SPREAD * rng.random_range(-1.0..=1.0) }
+    }
+
+    fn expired(&self, lifespan: f32) -> bool {
+        self.seconds >= lifespan * self.span
+    }
+}
+
+// Once `lifespan` is set, boids grow old and die, and well-fed pairs breed replacements that
+// inherit their traits, so a flock can evolve
+pub struct LifecyclePlugin;
+
+impl Plugin for LifecyclePlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Age>()
+            .add_systems(FixedUpdate, (age_boids, breed_boids)
+                .chain()
+                .in_set(BoidSet::Movement)
+                .run_if(|settings: Res<BoidSettings>| settings.lifespan > 0.0));
+    }
+}
+
+// Boids that weren't born get an age somewhere in the first half of their lives, so a starting
+// flock dies off gradually rather than all at once. The player and held boids don't age
+fn age_boids(
+    mut commands: Commands,
+    mut rng: ResMut<SimRng>,
+    mut settings: ResMut<BoidSettings>,
+    mut boids: Query<(Entity, Option<&mut Age>), (AnyBoid, Flown)>,
+    everyone: Query<(), AnyBoid>,
+    time: Res<Time>,
+) {
+    let mut count = everyone.iter().len();
+
+    for (boid, age) in boids.iter_mut() {
+        let Some(mut age) = age else {
+            let mut age = Age::newborn(&mut **rng);
+            age.seconds = rng.random_range(0.0..0.5) * settings.lifespan * age.span;
+            commands.entity(boid).insert(age);
+            continue;
+        };
+
+        age.seconds += time.delta_seconds();
+        if age.expired(settings.lifespan) {
+            commands.entity(boid).despawn();
+            count -= 1;
+        }
+    }
+
+    sync_boid_count(&mut settings, count);
+}
+
+type Breeder<'a> = (
+    Entity,
+    &'a Transform,
+    &'a Velocity,
+    &'a Species,
+    &'a mut Hunger,
+    Option<&'a BoidTraits>,
+    Option<&'a Mass>,
+);
+
+fn breed_boids(
+    mut commands: Commands,
+    mut rng: ResMut<SimRng>,
+    mut settings: ResMut<BoidSettings>,
+    mut boids: Query<Breeder, (With<SpatialEntity>, Without<Player>)>,
+    index: Res<BoidIndex>,
+    time: Res<Time>,
+    mut bred: Local<HashSet<Entity>>,
+) {
+    bred.clear();
+    let chance = (settings.breed_chance * time.delta_seconds()).clamp(0.0, 1.0) as f64;
+    let mut pairs = vec![];
+
+    for (boid, transform, _, species, hunger, ..) in boids.iter() {
+        if hunger.0 > WELL_FED || bred.contains(&boid) || !rng.random_bool(chance) {
+            continue;
+        }
+
+        // The index can still hold boids despawned since its last rebuild, so check they exist
+        let partner = index.within_distance(transform.translation.xy(), settings.breed_range)
+            .into_iter()
+            .filter_map(|(_, other)| other)
+            .find(|other| *other != boid && !bred.contains(other) && boids.get(*other)
+                .is_ok_and(|(_, _, _, kind, hunger, ..)| kind == species && hunger.0 <= WELL_FED));
+
+        if let Some(partner) = partner {
+            bred.extend([boid, partner]);
+            pairs.push([boid, partner]);
+        }
+    }
+
+    let mut count = settings.count;
+    for pair in pairs {
+        let Ok([mut a, mut b]) = boids.get_many_mut(pair) else {
+            continue;
+        };
+
+        let position = (a.1.translation.xy() + b.1.translation.xy()) / 2.0;
+        let mut chick = BoidSpawn::random(&mut **rng, &settings, position);
+        chick.velocity = (a.2.0 + b.2.0) / 2.0;
+        chick.species = *a.3;
+        chick.color = a.3.color(settings.species_count, rng.random_range(0..SHADES));
+        chick.traits = Some(BoidTraits::inherit(&mut **rng, &settings, a.5, b.5));
+        chick.mass = Some(Mass::inherit(&mut **rng, &settings, a.6, b.6));
+
+        for hunger in [&mut a.4, &mut b.4] {
+            hunger.0 = (hunger.0 + BREED_COST).min(1.0);
+        }
+
+        let chick = spawn_boid(&mut commands, &settings, chick);
+        commands.entity(chick).insert(Age::newborn(&mut **rng));
+        count += 1;
+    }
+
+    sync_boid_count(&mut settings, count);
+}
@@ -0,0 +1,40 @@
+use bevy::prelude::*;
+
+use crate::{settings::BoidSettings, MainCamera};
+
+// World-space region flocked at full detail: the camera's view grown by `lod_margin`.
+// None, as when headless, keeps every boid at full detail
+#[derive(Resource, Default)]
+pub struct DetailRegion(pub Option<Rect>);
+
+impl DetailRegion {
+    // Whether a boid here steers this tick, or coasts on its last steering
+    pub fn steers(&self, settings: &BoidSettings, boid: Entity, position: Vec2, tick: u32) -> bool {
+        let interval = settings.lod_interval.max(1);
+        let detailed = self.0.is_none_or(|region| region.contains(position));
+
+        // Staggered so a tick's skipped boids aren't all the same ones
+        detailed || tick.wrapping_add(boid.index()).is_multiple_of(interval)
+    }
+}
+
+// Steering a low detail boid last computed, reapplied on the ticks it skips
+#[derive(Component, Default)]
+pub struct LodSteering(pub Vec2);
+
+pub fn track_detail_region(
+    camera: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    settings: Res<BoidSettings>,
+    mut region: ResMut<DetailRegion>,
+) {
+    // Everything in any view, when split
+    region.0 = camera.iter()
+        .filter_map(|(camera, t_camera)| {
+            let viewport = camera.logical_viewport_rect()?;
+            let a = camera.viewport_to_world_2d(t_camera, Vec2::ZERO)?;
+            let b = camera.viewport_to_world_2d(t_camera, viewport.size())?;
+            Some(Rect::from_corners(a, b))
+        })
+        .reduce(|a, b| a.union(b))
+        .map(|region| Rect::from_corners(region.min - settings.lod_margin, region.max + settings.lod_margin));
+}
@@ -0,0 +1,83 @@
+use std::f32::consts::TAU;
+use bevy::prelude::*;
+
+use crate::{camera::OverviewCamera, settings::BoidSettings, BoidSet, MainCamera};
+
+// Size of the compass in logical pixels, and its gap from the top right corner
+const COMPASS_RADIUS: f32 = 24.;
+const COMPASS_MARGIN: f32 = 16.;
+const COMPASS_COLOR: Color = Color::rgb(0.9, 0.8, 0.4);
+
+// Heading the whole flock is drawn along, swinging round once every `migration_period` seconds
+// so over the minutes it tours the world rather than milling in place
+#[derive(Resource, Default, Reflect)]
+#[reflect(Resource)]
+pub struct Migration {
+    // Radians anticlockwise from +x
+    pub angle: f32,
+}
+
+impl Migration {
+    pub fn heading(&self) -> Vec2 {
+        Vec2::from_angle(self.angle)
+    }
+}
+
+// Turns the migration heading while `migration_factor` is set
+pub struct MigrationPlugin;
+
+impl Plugin for MigrationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Migration>()
+            .register_type::<Migration>()
+            .add_systems(FixedUpdate, turn_migration.in_set(BoidSet::Index).run_if(migrating));
+    }
+}
+
+fn migrating(settings: Res<BoidSettings>) -> bool {
+    settings.migration_factor != 0.0
+}
+
+fn turn_migration(mut migration: ResMut<Migration>, settings: Res<BoidSettings>, time: Res<Time>) {
+    let step = TAU * time.delta_seconds() / settings.migration_period.max(1.0);
+    migration.angle = (migration.angle + step).rem_euclid(TAU);
+}
+
+// A compass in the top right corner pointing the way the flock is migrating, while it is
+pub struct MigrationViewPlugin;
+
+impl Plugin for MigrationViewPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, draw_compass.run_if(migrating));
+    }
+}
+
+fn draw_compass(
+    migration: Res<Migration>,
+    camera: Query<(&Camera, &GlobalTransform), (With<MainCamera>, Without<OverviewCamera>)>,
+    mut gizmos: Gizmos,
+) {
+    let Ok((camera, t_camera)) = camera.get_single() else {
+        return;
+    };
+    let Some(viewport) = camera.logical_viewport_size() else {
+        return;
+    };
+
+    // Fixed in the corner of the main view however the camera pans and zooms, or the view is split
+    let center = Vec2::new(viewport.x - COMPASS_MARGIN - COMPASS_RADIUS, COMPASS_MARGIN + COMPASS_RADIUS);
+    let at = |offset: Vec2| camera.viewport_to_world_2d(t_camera, center + offset);
+    let (Some(middle), Some(rim)) = (at(Vec2::ZERO), at(Vec2::X * COMPASS_RADIUS)) else {
+        return;
+    };
+    let radius = middle.distance(rim);
+
+    let heading = migration.heading();
+    let tip = middle + heading * radius * 0.8;
+    gizmos.circle_2d(middle, radius, Color::GRAY);
+    gizmos.line_2d(middle - heading * radius * 0.5, tip, COMPASS_COLOR);
+    for side in [-1.0, 1.0] {
+        let barb = Vec2::from_angle(side * 2.6).rotate(heading);
+        gizmos.line_2d(tip, tip + barb * radius * 0.35, COMPASS_COLOR);
+    }
+}
@@ -0,0 +1,154 @@
+use bevy::{
+    math::Vec3Swizzles,
+    prelude::*,
+    render::{camera::{ScalingMode, Viewport}, view::RenderLayers},
+    window::PrimaryWindow,
+};
+
+use crate::{input::{Action, Actions}, predators::Predator, AnyBoid, BoidColor, MainCamera, WorldBounds};
+
+// Drawn only by the minimap's camera, the main camera staying on the default layer
+const MINIMAP_LAYER: u8 = 1;
+// Width of the minimap in logical pixels, its height following the world's aspect
+const MINIMAP_WIDTH: f32 = 200.;
+const MINIMAP_MARGIN: f32 = 8.;
+// Each boid is a dash this many pixels long along its heading
+const DOT_LENGTH: f32 = 2.;
+const DOT_WIDTH: f32 = 3.;
+
+#[derive(Default, Reflect, GizmoConfigGroup)]
+struct MinimapGizmos;
+
+#[derive(Component)]
+struct MinimapCamera;
+
+// `U` toggles a minimap in the bottom right corner, with the whole world, every boid and
+// predator, and the main camera's view
+pub struct MinimapPlugin;
+
+impl Plugin for MinimapPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_gizmo_group(MinimapGizmos, GizmoConfig {
+                line_width: DOT_WIDTH,
+                render_layers: RenderLayers::layer(MINIMAP_LAYER),
+                ..default()
+            })
+            .add_systems(Startup, spawn_minimap)
+            .add_systems(Update, (toggle_minimap, place_minimap, draw_minimap).chain());
+    }
+}
+
+fn fit(bounds: Vec2) -> ScalingMode {
+    ScalingMode::Fixed { width: bounds.x, height: bounds.y }
+}
+
+fn spawn_minimap(mut commands: Commands, bounds: Res<WorldBounds>) {
+    let mut camera = Camera2dBundle {
+        camera: Camera {
+            // Over the main camera and any overview, and hidden until toggled
+            order: 2,
+            is_active: false,
+            clear_color: ClearColorConfig::Custom(Color::rgb(0.05, 0.05, 0.08)),
+            ..default()
+        },
+        ..default()
+    };
+    camera.projection.scaling_mode = fit(**bounds);
+
+    commands.spawn((
+        camera,
+        RenderLayers::layer(MINIMAP_LAYER),
+        MinimapCamera,
+    ));
+}
+
+fn toggle_minimap(actions: Actions, mut camera: Query<&mut Camera, With<MinimapCamera>>) {
+    if actions.just_pressed(Action::Minimap) {
+        for mut camera in camera.iter_mut() {
+            camera.is_active = !camera.is_active;
+        }
+    }
+}
+
+// Kept in the corner and fitted to the world as either is resized
+fn place_minimap(
+    mut camera: Query<(&mut Camera, &mut OrthographicProjection), With<MinimapCamera>>,
+    window: Query<&Window, With<PrimaryWindow>>,
+    bounds: Res<WorldBounds>,
+) {
+    let (Ok((mut camera, mut projection)), Ok(window)) = (camera.get_single_mut(), window.get_single()) else {
+        return;
+    };
+    if bounds.is_changed() {
+        projection.scaling_mode = fit(**bounds);
+    }
+    if !camera.is_active {
+        return;
+    }
+
+    let size = Vec2::new(MINIMAP_WIDTH, MINIMAP_WIDTH * bounds.y / bounds.x.max(1.0))
+        .min(Vec2::new(window.width(), window.height()) - 2.0 * MINIMAP_MARGIN);
+    let corner = Vec2::new(window.width(), window.height()) - size - MINIMAP_MARGIN;
+    if size.min_element() < 1.0 {
+        return;
+    }
+
+    let scale = window.scale_factor();
+    let viewport = Viewport {
+        physical_position: (corner * scale).as_uvec2(),
+        physical_size: (size * scale).as_uvec2(),
+        ..default()
+    };
+    if camera.viewport.as_ref().map(|v| (v.physical_position, v.physical_size))
+        != Some((viewport.physical_position, viewport.physical_size))
+    {
+        camera.viewport = Some(viewport);
+    }
+}
+
+fn draw_minimap(
+    minimap: Query<&Camera, With<MinimapCamera>>,
+    main: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    boids: Query<(&Transform, &BoidColor), AnyBoid>,
+    predators: Query<&Transform, With<Predator>>,
+    bounds: Res<WorldBounds>,
+    mut gizmos: Gizmos<MinimapGizmos>,
+) {
+    let Ok(minimap) = minimap.get_single() else {
+        return;
+    };
+    let Some(viewport) = minimap.logical_viewport_size().filter(|_| minimap.is_active) else {
+        return;
+    };
+
+    // World units a minimap pixel covers
+    let pixel = bounds.x / viewport.x.max(1.0);
+    let dash = |transform: &Transform| {
+        let position = transform.translation.xy();
+        (position, position + transform.local_x().xy() * DOT_LENGTH * pixel)
+    };
+
+    gizmos.rect_2d(Vec2::ZERO, 0.0, **bounds, Color::DARK_GRAY);
+
+    for (transform, color) in boids.iter() {
+        let (start, end) = dash(transform);
+        gizmos.line_2d(start, end, color.0);
+    }
+    for transform in predators.iter() {
+        let (start, end) = dash(transform);
+        gizmos.line_2d(start, end, Color::RED);
+    }
+
+    // What the main camera sees, wherever it's following or zoomed to
+    for (camera, t_camera) in main.iter() {
+        let Some(size) = camera.logical_viewport_size() else {
+            continue;
+        };
+        let corners = camera.viewport_to_world_2d(t_camera, Vec2::ZERO)
+            .zip(camera.viewport_to_world_2d(t_camera, size));
+        if let Some((a, b)) = corners {
+            let view = Rect::from_corners(a, b);
+            gizmos.rect_2d(view.center(), 0.0, view.size(), Color::WHITE);
+        }
+    }
+}
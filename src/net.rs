@@ -0,0 +1,343 @@
+use std::{
+    io::ErrorKind,
+    net::{SocketAddr, UdpSocket},
+};
+use bevy::{math::Vec3Swizzles, prelude::*, utils::HashMap};
+
+use crate::{
+    attractors::{Attractor, PLACED_RADIUS, PLACED_STRENGTH},
+    cli::Cli,
+    BoidAssets,
+    BoidColor,
+    CursorPosition,
+};
+
+// Clients silent for this long are dropped, along with their attractor
+const CLIENT_TIMEOUT: f32 = 2.;
+// Boids per snapshot packet, keeping each well under a UDP datagram
+const BOIDS_PER_PACKET: usize = 400;
+// Replicas missing from this many snapshots in a row were despawned on the server
+const STALE_SNAPSHOTS: u32 = 30;
+
+// Packet tags, the first byte of every datagram
+const CURSOR: u8 = 1;
+const BOIDS: u8 = 2;
+const CURSORS: u8 = 3;
+
+// Little-endian fields read off the front of a datagram
+struct Packet<'a>(&'a [u8]);
+
+impl Packet<'_> {
+    fn take<const N: usize>(&mut self) -> Option<[u8; N]> {
+        let (head, rest) = self.0.split_at_checked(N)?;
+        self.0 = rest;
+        head.try_into().ok()
+    }
+
+    fn u8(&mut self) -> Option<u8> {
+        self.take::<1>().map(|[byte]| byte)
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.take().map(u32::from_le_bytes)
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        self.take().map(u64::from_le_bytes)
+    }
+
+    fn f32(&mut self) -> Option<f32> {
+        self.take().map(f32::from_le_bytes)
+    }
+
+    fn vec2(&mut self) -> Option<Vec2> {
+        Some(Vec2::new(self.f32()?, self.f32()?))
+    }
+}
+
+fn put_vec2(bytes: &mut Vec<u8>, v: Vec2) {
+    bytes.extend(v.x.to_le_bytes());
+    bytes.extend(v.y.to_le_bytes());
+}
+
+// `--serve [port]` simulates and broadcasts the flock, `--connect <host:port>` watches one
+pub enum NetRole {
+    Server(u16),
+    Client(SocketAddr),
+}
+
+impl NetRole {
+    pub fn from_cli(cli: &Cli) -> Option<Self> {
+        cli.serve.map(NetRole::Server).or(cli.connect.map(NetRole::Client))
+    }
+}
+
+struct RemoteClient {
+    // At the client's cursor, while it's over their window
+    attractor: Option<Entity>,
+    last_seen: f32,
+}
+
+#[derive(Resource)]
+struct NetServer {
+    socket: UdpSocket,
+    clients: HashMap<SocketAddr, RemoteClient>,
+    tick: u32,
+}
+
+// Alongside the normal simulation: each connected client steers the flock with an attractor at
+// their cursor, and is sent every boid's transform and color each frame
+pub struct NetServerPlugin {
+    pub port: u16,
+}
+
+impl Plugin for NetServerPlugin {
+    fn build(&self, app: &mut App) {
+        let socket = UdpSocket::bind(("0.0.0.0", self.port))
+            .and_then(|socket| socket.set_nonblocking(true).map(|_| socket));
+
+        match socket {
+            Ok(socket) => {
+                info!("serving the flock on udp port {}", self.port);
+                app.insert_resource(NetServer { socket, clients: HashMap::new(), tick: 0 })
+                    .add_systems(Update, (receive_cursors, broadcast_flock).chain());
+            }
+            Err(err) => error!("couldn't serve on udp port {}: {err}", self.port),
+        }
+    }
+}
+
+fn receive_cursors(
+    mut commands: Commands,
+    mut server: ResMut<NetServer>,
+    mut attractors: Query<&mut Transform, With<Attractor>>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed_seconds();
+    let mut buffer = [0; 64];
+
+    loop {
+        let (len, from) = match server.socket.recv_from(&mut buffer) {
+            Ok(received) => received,
+            Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+            // e.g. the previous send to a client that has since closed
+            Err(_) => continue,
+        };
+
+        let mut packet = Packet(&buffer[..len]);
+        if packet.u8() != Some(CURSOR) {
+            continue;
+        }
+        let cursor = match packet.u8() {
+            Some(1) => packet.vec2(),
+            _ => None,
+        };
+
+        let client = server.clients.entry(from).or_insert_with(|| {
+            info!("{from} connected");
+            RemoteClient { attractor: None, last_seen: now }
+        });
+        client.last_seen = now;
+
+        // Also re-placed if the attractor was middle-clicked away on the server
+        let existing = client.attractor.and_then(|attractor| attractors.get_mut(attractor).ok());
+        match (cursor, existing) {
+            (Some(position), Some(mut transform)) => {
+                transform.translation.x = position.x;
+                transform.translation.y = position.y;
+            }
+            (Some(position), None) => {
+                client.attractor = Some(commands.spawn((
+                    Attractor { strength: PLACED_STRENGTH, radius: PLACED_RADIUS },
+                    TransformBundle::from_transform(Transform::from_translation(position.extend(0.0))),
+                )).id());
+            }
+            (None, _) => {
+                if let Some(attractor) = client.attractor.take() {
+                    commands.entity(attractor).despawn();
+                }
+            }
+        }
+    }
+
+    server.clients.retain(|from, client| {
+        let alive = now - client.last_seen < CLIENT_TIMEOUT;
+        if !alive {
+            info!("{from} timed out");
+            if let Some(attractor) = client.attractor {
+                commands.entity(attractor).despawn();
+            }
+        }
+        alive
+    });
+}
+
+fn broadcast_flock(
+    mut server: ResMut<NetServer>,
+    boids: Query<(Entity, &Transform, &BoidColor)>,
+    attractors: Query<&Transform, With<Attractor>>,
+) {
+    if server.clients.is_empty() {
+        return;
+    }
+    server.tick = server.tick.wrapping_add(1);
+
+    let mut packets = vec![];
+    let all = boids.iter().collect::<Vec<_>>();
+
+    for chunk in all.chunks(BOIDS_PER_PACKET) {
+        let mut bytes = vec![BOIDS];
+        bytes.extend(server.tick.to_le_bytes());
+
+        for (boid, transform, color) in chunk {
+            bytes.extend(boid.to_bits().to_le_bytes());
+            put_vec2(&mut bytes, transform.translation.xy());
+            bytes.extend(transform.rotation.to_euler(EulerRot::ZYX).0.to_le_bytes());
+            bytes.extend(transform.scale.x.to_le_bytes());
+            bytes.extend(color.0.as_rgba_u8());
+        }
+        packets.push(bytes);
+    }
+
+    // Every client's cursor, so each can see where the others are pulling
+    let mut cursors = vec![CURSORS];
+    for attractor in server.clients.values().filter_map(|client| client.attractor) {
+        if let Ok(transform) = attractors.get(attractor) {
+            put_vec2(&mut cursors, transform.translation.xy());
+        }
+    }
+    packets.push(cursors);
+
+    for client in server.clients.keys() {
+        for packet in &packets {
+            // Dropped datagrams are simply superseded by the next frame's
+            let _ = server.socket.send_to(packet, client);
+        }
+    }
+}
+
+#[derive(Resource)]
+struct NetClient {
+    socket: UdpSocket,
+    // Server entity bits to the local replica, and the last snapshot it appeared in
+    replicas: HashMap<u64, (Entity, u32)>,
+    latest: u32,
+    cursors: Vec<Vec2>,
+}
+
+// Replaces the simulation with replicas of a server's boids, sending it the cursor each frame
+pub struct NetClientPlugin {
+    pub server: SocketAddr,
+}
+
+impl Plugin for NetClientPlugin {
+    fn build(&self, app: &mut App) {
+        let socket = UdpSocket::bind(("0.0.0.0", 0))
+            .and_then(|socket| socket.connect(self.server).map(|_| socket))
+            .and_then(|socket| socket.set_nonblocking(true).map(|_| socket));
+
+        let socket = match socket {
+            Ok(socket) => socket,
+            Err(err) => {
+                error!("couldn't connect to {}: {err}", self.server);
+                return;
+            }
+        };
+
+        info!("watching the flock served at {}", self.server);
+        app.insert_resource(NetClient { socket, replicas: HashMap::new(), latest: 0, cursors: vec![] })
+            .init_resource::<BoidAssets>()
+            .init_resource::<CursorPosition>()
+            .add_systems(Startup, crate::spawn_camera)
+            .add_systems(PreUpdate, crate::track_cursor)
+            .add_systems(Update, (send_cursor, receive_flock, draw_cursors).chain())
+            .add_systems(PostUpdate, crate::attach_boid_meshes);
+    }
+}
+
+fn send_cursor(client: Res<NetClient>, cursor: Res<CursorPosition>) {
+    let mut bytes = vec![CURSOR, cursor.0.is_some() as u8];
+    if let Some(position) = cursor.0 {
+        put_vec2(&mut bytes, position);
+    }
+
+    // Also keeps the connection alive; a server that isn't up yet just misses these
+    let _ = client.socket.send(&bytes);
+}
+
+fn receive_flock(mut commands: Commands, mut client: ResMut<NetClient>, mut transforms: Query<&mut Transform>) {
+    let client = &mut *client;
+    let mut buffer = vec![0; 65_536];
+
+    loop {
+        let len = match client.socket.recv(&mut buffer) {
+            Ok(len) => len,
+            Err(err) if err.kind() == ErrorKind::WouldBlock => break,
+            Err(_) => continue,
+        };
+
+        let mut packet = Packet(&buffer[..len]);
+        match packet.u8() {
+            Some(BOIDS) => {
+                let Some(tick) = packet.u32() else {
+                    continue;
+                };
+                // Only ever move forwards, out-of-order snapshots are dropped
+                if tick.wrapping_sub(client.latest) > u32::MAX / 2 {
+                    continue;
+                }
+                client.latest = tick;
+
+                while let (Some(id), Some(position), Some(angle), Some(scale), Some(rgba)) =
+                    (packet.u64(), packet.vec2(), packet.f32(), packet.f32(), packet.take::<4>())
+                {
+                    let transform = Transform::from_translation(position.extend(0.0))
+                        .with_rotation(Quat::from_rotation_z(angle))
+                        .with_scale(Vec3::splat(scale));
+
+                    let replica = client.replicas.get_mut(&id)
+                        .filter(|(replica, _)| transforms.contains(*replica));
+
+                    match replica {
+                        Some((replica, seen)) => {
+                            *seen = tick;
+                            if let Ok(mut t) = transforms.get_mut(*replica) {
+                                *t = transform;
+                            }
+                        }
+                        None => {
+                            let [r, g, b, a] = rgba;
+                            let replica = commands.spawn((
+                                SpatialBundle::from_transform(transform),
+                                BoidColor(Color::rgba_u8(r, g, b, a)),
+                            )).id();
+                            client.replicas.insert(id, (replica, tick));
+                        }
+                    }
+                }
+            }
+            Some(CURSORS) => {
+                client.cursors.clear();
+                while let Some(position) = packet.vec2() {
+                    client.cursors.push(position);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let latest = client.latest;
+    client.replicas.retain(|_, (replica, seen)| {
+        let alive = latest.wrapping_sub(*seen) < STALE_SNAPSHOTS;
+        if !alive {
+            commands.entity(*replica).despawn();
+        }
+        alive
+    });
+}
+
+fn draw_cursors(client: Res<NetClient>, mut gizmos: Gizmos) {
+    for cursor in &client.cursors {
+        gizmos.circle_2d(*cursor, PLACED_RADIUS, Color::LIME_GREEN.with_a(0.2));
+    }
+}
@@ -0,0 +1,291 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+use bevy_spatial::{
+    kdtree::KDTree2,
+    SpatialAccess,
+};
+
+use crate::{
+    boundary::Boundary,
+    settings::{BoidSettings, BoundaryMode},
+    spatial::rebuild_tree2,
+    BoidSet,
+};
+
+// Obstacle centers are looked up by proximity, so only check this many
+const OBSTACLE_CAP: usize = 8;
+// Steps a whisker marches along before giving up, and how close to a surface counts as touching
+const WHISKER_STEPS: usize = 16;
+const WHISKER_HIT: f32 = 0.5;
+
+// Static collider boids steer around, centered on the entity's transform
+#[derive(Component, Clone, Copy, Debug, Reflect)]
+#[reflect(Component)]
+pub enum Obstacle {
+    Circle { radius: f32 },
+    // Axis-aligned, ignores the transform's rotation
+    Rect { half_size: Vec2 },
+}
+
+impl Obstacle {
+    // Distance from `point` to the surface (negative inside) and the outward direction
+    pub fn signed_distance(&self, center: Vec2, point: Vec2) -> (f32, Vec2) {
+        let rel = point - center;
+
+        match *self {
+            Obstacle::Circle { radius } => (rel.length() - radius, rel.normalize_or_zero()),
+            Obstacle::Rect { half_size } => {
+                let outside = rel - rel.clamp(-half_size, half_size);
+
+                if let Some(dir) = outside.try_normalize() {
+                    return (outside.length(), dir);
+                }
+
+                // Inside, push out through the nearest edge
+                let depth = half_size - rel.abs();
+                if depth.x < depth.y {
+                    (-depth.x, Vec2::new(rel.x.signum(), 0.0))
+                } else {
+                    (-depth.y, Vec2::new(0.0, rel.y.signum()))
+                }
+            }
+        }
+    }
+
+    // Whether the segment from `from` to `to` passes through the obstacle
+    pub fn blocks(&self, center: Vec2, from: Vec2, to: Vec2) -> bool {
+        let (from, to) = (from - center, to - center);
+        let along = to - from;
+
+        match *self {
+            Obstacle::Circle { radius } => {
+                let t = (-from.dot(along) / along.length_squared()).clamp(0.0, 1.0);
+                let closest = if t.is_finite() { from + along * t } else { from };
+                closest.length_squared() < radius * radius
+            }
+            Obstacle::Rect { half_size } => {
+                // Slab test, clipping the segment to each axis' extent in turn
+                let (mut enter, mut exit) = (0.0_f32, 1.0_f32);
+                for axis in 0..2 {
+                    let (start, delta) = (from[axis], along[axis]);
+                    if delta == 0.0 {
+                        if start.abs() >= half_size[axis] {
+                            return false;
+                        }
+                        continue;
+                    }
+                    let a = (-half_size[axis] - start) / delta;
+                    let b = (half_size[axis] - start) / delta;
+                    enter = enter.max(a.min(b));
+                    exit = exit.min(a.max(b));
+                }
+                enter < exit
+            }
+        }
+    }
+}
+
+pub struct ObstaclePlugin;
+
+impl Plugin for ObstaclePlugin {
+    fn build(&self, app: &mut App) {
+        // Obstacles get their own tree so boid neighbor queries stay boids-only
+        app.init_resource::<KDTree2<Obstacle>>()
+            .register_type::<Obstacle>()
+            .add_systems(FixedUpdate, rebuild_tree2::<Obstacle>.in_set(BoidSet::Index))
+            .add_systems(Startup, spawn_obstacles);
+    }
+}
+
+pub fn spawn_obstacles(mut commands: Commands) {
+    commands.spawn((
+        Obstacle::Circle { radius: 40. },
+        TransformBundle::from_transform(Transform::from_xyz(-150., 0., 0.)),
+    ));
+    commands.spawn((
+        Obstacle::Rect { half_size: Vec2::new(30., 60.) },
+        TransformBundle::from_transform(Transform::from_xyz(150., 0., 0.)),
+    ));
+}
+
+pub fn draw_obstacle_gizmos(
+    obstacles: Query<(&Obstacle, &Transform)>,
+    mut gizmos: Gizmos,
+) {
+    for (obstacle, transform) in obstacles.iter() {
+        let center = transform.translation.truncate();
+
+        match *obstacle {
+            Obstacle::Circle { radius } => {
+                gizmos.circle_2d(center, radius, Color::ORANGE_RED);
+            }
+            Obstacle::Rect { half_size } => {
+                gizmos.rect_2d(center, 0.0, half_size * 2.0, Color::ORANGE_RED);
+            }
+        }
+    }
+}
+
+// Read access to nearby obstacles for the flocking pass
+#[derive(SystemParam)]
+pub struct Obstacles<'w, 's> {
+    tree: Res<'w, KDTree2<Obstacle>>,
+    query: Query<'w, 's, (&'static Obstacle, &'static Transform)>,
+}
+
+impl Obstacles<'_, '_> {
+    // Steering away from obstacle surfaces within `obstacle_range`, strongest on contact
+    pub fn avoidance(&self, settings: &BoidSettings, position: Vec2) -> Vec2 {
+        let mut dv = Vec2::ZERO;
+
+        for (_, entity) in self.tree.k_nearest_neighbour(position, OBSTACLE_CAP) {
+            let Some(Ok((obstacle, transform))) = entity.map(|e| self.query.get(e)) else {
+                continue;
+            };
+
+            let (dist, dir) = obstacle.signed_distance(transform.translation.truncate(), position);
+
+            if dist < settings.obstacle_range {
+                let strength = 1.0 - dist.max(0.0) / settings.obstacle_range;
+                dv += dir * strength * settings.obstacle_avoid_factor;
+            }
+        }
+
+        dv
+    }
+
+    // Fills `occluders` with the obstacles close enough to `position` to hide any boid it could
+    // see, for `hidden`; none while line of sight is off
+    pub fn occluders(&self, settings: &BoidSettings, position: Vec2, occluders: &mut Vec<(Obstacle, Vec2)>) {
+        occluders.clear();
+        if !settings.line_of_sight {
+            return;
+        }
+
+        occluders.extend(self.tree.k_nearest_neighbour(position, OBSTACLE_CAP).into_iter()
+            .filter_map(|(_, entity)| self.query.get(entity?).ok())
+            .map(|(obstacle, transform)| (*obstacle, transform.translation.truncate()))
+            .filter(|(obstacle, center)| obstacle.signed_distance(*center, position).0 < settings.vis_range));
+    }
+
+    // Steering along whatever surface the boid's whiskers, rays `whisker_length` ahead and
+    // `whisker_angle` either side, are about to run into: obstacles, and the boundary unless it
+    // wraps. Nearer hits turn harder, slipping boids around walls instead of bouncing off late
+    pub fn whiskers(
+        &self,
+        settings: &BoidSettings,
+        boundary: &Boundary,
+        world: Vec2,
+        position: Vec2,
+        heading: Vec2,
+    ) -> Vec2 {
+        let length = settings.whisker_length;
+        if length <= 0.0 || settings.whisker_factor == 0.0 {
+            return Vec2::ZERO;
+        }
+
+        // Only what's already within reach could be hit
+        let nearby: Vec<_> = self.tree.k_nearest_neighbour(position, OBSTACLE_CAP).into_iter()
+            .filter_map(|(_, entity)| self.query.get(entity?).ok())
+            .map(|(obstacle, transform)| (*obstacle, transform.translation.truncate()))
+            .filter(|(obstacle, center)| obstacle.signed_distance(*center, position).0 < length)
+            .collect();
+        let walled = settings.boundary_mode != BoundaryMode::Wrap
+            && -boundary.distance(position, world, settings.boundary_size) < length;
+        if nearby.is_empty() && !walled {
+            return Vec2::ZERO;
+        }
+
+        let surface = |point: Vec2| {
+            let mut nearest = (f32::INFINITY, Vec2::ZERO);
+            for (obstacle, center) in &nearby {
+                let hit = obstacle.signed_distance(*center, point);
+                if hit.0 < nearest.0 {
+                    nearest = hit;
+                }
+            }
+            if walled {
+                // Inside is where the boundary's distance is negative, so facing in
+                let wall = -boundary.distance(point, world, settings.boundary_size);
+                if wall < nearest.0 {
+                    nearest = (wall, -boundary.normal(point, world, settings.boundary_size));
+                }
+            }
+            nearest
+        };
+
+        let mut dv = Vec2::ZERO;
+        for side in [0.0, -1.0, 1.0] {
+            let ray = Vec2::from_angle(side * settings.whisker_angle).rotate(heading);
+            let Some((distance, normal)) = cast(surface, position, ray, length) else {
+                continue;
+            };
+
+            // Along the surface the way the boid was already turning; head on, away from the
+            // whisker that saw it, or to the left
+            let along = heading - normal * heading.dot(normal);
+            let along = along.try_normalize().unwrap_or_else(|| {
+                if side > 0.0 { -normal.perp() } else { normal.perp() }
+            });
+            let urgency = 1.0 - distance / length;
+            dv += (along - heading + normal * urgency) * urgency * settings.whisker_factor;
+        }
+
+        dv
+    }
+}
+
+// How far along `ray` from `origin` the surface `sdf` measures is, and its outward normal there,
+// if within `length`. Sphere traced, each step as long as the nearest surface is far
+fn cast(sdf: impl Fn(Vec2) -> (f32, Vec2), origin: Vec2, ray: Vec2, length: f32) -> Option<(f32, Vec2)> {
+    let mut travelled = 0.0;
+
+    for _ in 0..WHISKER_STEPS {
+        let (distance, normal) = sdf(origin + ray * travelled);
+        if distance < WHISKER_HIT {
+            return Some((travelled, normal));
+        }
+        travelled += distance;
+        if travelled > length {
+            return None;
+        }
+    }
+
+    None
+}
+
+// Whether a boid at `position` can't see the one `offset` from it for any of `occluders`
+pub fn hidden(occluders: &[(Obstacle, Vec2)], position: Vec2, offset: Vec2) -> bool {
+    occluders.iter().any(|(obstacle, center)| obstacle.blocks(*center, position, position + offset))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn obstacles_block_sight_through_them() {
+        let circle = [(Obstacle::Circle { radius: 10. }, Vec2::new(20., 0.))];
+        assert!(hidden(&circle, Vec2::ZERO, Vec2::new(40., 0.)), "straight through");
+        assert!(!hidden(&circle, Vec2::ZERO, Vec2::new(40., 30.)), "grazing past it");
+        assert!(!hidden(&circle, Vec2::ZERO, Vec2::new(5., 0.)), "short of it");
+        assert!(!hidden(&circle, Vec2::new(0., 12.), Vec2::new(40., 0.)), "passing over it");
+
+        let rect = [(Obstacle::Rect { half_size: Vec2::new(5., 20.) }, Vec2::new(20., 0.))];
+        assert!(hidden(&rect, Vec2::ZERO, Vec2::new(40., 10.)), "across it");
+        assert!(!hidden(&rect, Vec2::new(0., 25.), Vec2::new(40., 0.)), "above it");
+        assert!(!hidden(&rect, Vec2::new(30., -30.), Vec2::new(0., 60.)), "alongside it");
+    }
+
+    #[test]
+    fn whiskers_find_surfaces_ahead() {
+        let circle = Obstacle::Circle { radius: 10. };
+        let sdf = |point| circle.signed_distance(Vec2::new(50., 0.), point);
+
+        let (distance, normal) = cast(sdf, Vec2::ZERO, Vec2::X, 60.).expect("straight at it");
+        assert!((distance - 40.).abs() <= WHISKER_HIT, "{distance}");
+        assert!(normal.distance(-Vec2::X) < 0.01, "{normal}");
+
+        assert!(cast(sdf, Vec2::ZERO, Vec2::X, 30.).is_none(), "out of reach");
+        assert!(cast(sdf, Vec2::ZERO, Vec2::Y, 60.).is_none(), "looking past it");
+    }
+}
@@ -0,0 +1,217 @@
+use bevy::{
+    math::Vec3Swizzles,
+    prelude::*,
+    render::{
+        render_asset::RenderAssetUsages,
+        render_resource::{Extent3d, TextureDimension, TextureFormat},
+        texture::ImageSampler,
+    },
+};
+
+use crate::{input::{Action, Actions}, settings::BoidSettings, SpatialEntity, WorldBounds};
+
+// Side of a pheromone cell, in world units
+const PHEROMONE_CELL: f32 = 10.;
+// Concentration drawn fully opaque
+const SATURATION: f32 = 2.;
+// Boids smell the field this far ahead of them
+const SENSE_AHEAD: f32 = 20.;
+
+// Trail left by every boid, spreading out and fading over time; row 0 at the bottom
+#[derive(Resource, Clone, Debug)]
+pub struct PheromoneField {
+    // World position of the bottom-left corner
+    origin: Vec2,
+    cols: usize,
+    rows: usize,
+    values: Vec<f32>,
+    scratch: Vec<f32>,
+}
+
+impl PheromoneField {
+    pub fn new(size: Vec2) -> Self {
+        let cols = (size.x / PHEROMONE_CELL).ceil().max(1.0) as usize;
+        let rows = (size.y / PHEROMONE_CELL).ceil().max(1.0) as usize;
+
+        Self {
+            origin: -Vec2::new(cols as f32, rows as f32) * PHEROMONE_CELL / 2.0,
+            cols,
+            rows,
+            values: vec![0.0; cols * rows],
+            scratch: vec![0.0; cols * rows],
+        }
+    }
+
+    fn cell(&self, position: Vec2) -> Option<(usize, usize)> {
+        let cell = ((position - self.origin) / PHEROMONE_CELL).floor();
+
+        if cell.x < 0.0 || cell.y < 0.0 || cell.x >= self.cols as f32 || cell.y >= self.rows as f32 {
+            return None;
+        }
+
+        Some((cell.x as usize, cell.y as usize))
+    }
+
+    // Clamped to the edge, so the field reads as flat outside it
+    fn value(&self, col: isize, row: isize) -> f32 {
+        let col = col.clamp(0, self.cols as isize - 1) as usize;
+        let row = row.clamp(0, self.rows as isize - 1) as usize;
+        self.values[row * self.cols + col]
+    }
+
+    // Difference across the cells either side of `position`, zero outside the field
+    fn gradient(&self, position: Vec2) -> Vec2 {
+        let Some((col, row)) = self.cell(position) else {
+            return Vec2::ZERO;
+        };
+        let (col, row) = (col as isize, row as isize);
+
+        Vec2::new(
+            self.value(col + 1, row) - self.value(col - 1, row),
+            self.value(col, row + 1) - self.value(col, row - 1),
+        ) / 2.0
+    }
+
+    // Uphill just ahead of a boid, at most unit length; never back along the trail it just laid
+    pub fn steering(&self, position: Vec2, heading: Vec2) -> Vec2 {
+        let gradient = self.gradient(position + heading * SENSE_AHEAD);
+        let backward = gradient.dot(heading).min(0.0);
+
+        (gradient - heading * backward).clamp_length_max(1.0)
+    }
+
+    fn deposit(&mut self, position: Vec2, amount: f32) {
+        if let Some((col, row)) = self.cell(position) {
+            self.values[row * self.cols + col] += amount;
+        }
+    }
+
+    // Blend each cell towards its neighbors' average by `diffusion`, then decay it
+    fn spread(&mut self, diffusion: f32, decay: f32) {
+        for row in 0..self.rows {
+            for col in 0..self.cols {
+                let (c, r) = (col as isize, row as isize);
+                let around = (self.value(c - 1, r) + self.value(c + 1, r)
+                    + self.value(c, r - 1) + self.value(c, r + 1)) / 4.0;
+                let here = self.values[row * self.cols + col];
+
+                self.scratch[row * self.cols + col] = (here + (around - here) * diffusion) * decay;
+            }
+        }
+
+        std::mem::swap(&mut self.values, &mut self.scratch);
+    }
+}
+
+impl FromWorld for PheromoneField {
+    fn from_world(world: &mut World) -> Self {
+        Self::new(**world.resource::<WorldBounds>())
+    }
+}
+
+pub fn deposit_pheromone(
+    boids: Query<&Transform, With<SpatialEntity>>,
+    mut field: ResMut<PheromoneField>,
+    time: Res<Time>,
+    settings: Res<BoidSettings>,
+) {
+    let amount = settings.pheromone_deposit * time.delta_seconds();
+
+    for transform in boids.iter() {
+        field.deposit(transform.translation.xy(), amount);
+    }
+}
+
+pub fn spread_pheromone(mut field: ResMut<PheromoneField>, time: Res<Time>, settings: Res<BoidSettings>) {
+    let dt = time.delta_seconds();
+    let diffusion = (settings.pheromone_diffusion * dt).clamp(0.0, 1.0);
+
+    field.spread(diffusion, (-settings.pheromone_decay * dt).exp());
+}
+
+// Start over on a grid covering the play area as the window is resized
+pub fn resize_pheromone_field(bounds: Res<WorldBounds>, mut field: ResMut<PheromoneField>) {
+    if bounds.is_changed() && !bounds.is_added() {
+        *field = PheromoneField::new(**bounds);
+    }
+}
+
+#[derive(Component)]
+struct PheromoneOverlay;
+
+// `O` toggles a green overlay of the pheromone trails
+pub struct PheromoneViewPlugin;
+
+impl Plugin for PheromoneViewPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, spawn_overlay)
+            .add_systems(Update, (toggle_overlay, paint_overlay).chain());
+    }
+}
+
+fn spawn_overlay(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    let mut image = Image::new_fill(
+        Extent3d { width: 1, height: 1, depth_or_array_layers: 1 },
+        TextureDimension::D2,
+        &[0, 0, 0, 0],
+        TextureFormat::Rgba8UnormSrgb,
+        RenderAssetUsages::default(),
+    );
+    image.sampler = ImageSampler::linear();
+
+    // Behind the boids, under the heatmap
+    commands.spawn((
+        SpriteBundle {
+            texture: images.add(image),
+            transform: Transform::from_xyz(0.0, 0.0, -1.5),
+            visibility: Visibility::Hidden,
+            ..default()
+        },
+        PheromoneOverlay,
+    ));
+}
+
+fn toggle_overlay(actions: Actions, mut overlay: Query<&mut Visibility, With<PheromoneOverlay>>) {
+    if !actions.just_pressed(Action::Pheromones) {
+        return;
+    }
+
+    for mut visibility in overlay.iter_mut() {
+        *visibility = match *visibility {
+            Visibility::Hidden => Visibility::Visible,
+            _ => Visibility::Hidden,
+        };
+    }
+}
+
+fn paint_overlay(
+    field: Res<PheromoneField>,
+    mut images: ResMut<Assets<Image>>,
+    mut overlay: Query<(&Handle<Image>, &mut Sprite, &Visibility), With<PheromoneOverlay>>,
+) {
+    for (handle, mut sprite, visibility) in overlay.iter_mut() {
+        if *visibility == Visibility::Hidden {
+            continue;
+        }
+        let Some(image) = images.get_mut(handle) else {
+            continue;
+        };
+
+        image.resize(Extent3d {
+            width: field.cols as u32,
+            height: field.rows as u32,
+            depth_or_array_layers: 1,
+        });
+
+        // Images store their top row first
+        for row in 0..field.rows {
+            for col in 0..field.cols {
+                let t = (field.values[row * field.cols + col] / SATURATION).clamp(0.0, 1.0);
+                let pixel = ((field.rows - 1 - row) * field.cols + col) * 4;
+                image.data[pixel..pixel + 4].copy_from_slice(&[40, 220, 90, (t * 200.0) as u8]);
+            }
+        }
+
+        sprite.custom_size = Some(Vec2::new(field.cols as f32, field.rows as f32) * PHEROMONE_CELL);
+    }
+}
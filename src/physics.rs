@@ -0,0 +1,223 @@
+use bevy::{math::Vec3Swizzles, prelude::*};
+use bevy_rapier2d::prelude::{
+    Collider,
+    ExternalForce,
+    LockedAxes,
+    NoUserData,
+    PhysicsSet,
+    RapierConfiguration,
+    RapierPhysicsPlugin,
+    ReadMassProperties,
+    RigidBody,
+    TimestepMode,
+    Velocity as BodyVelocity,
+};
+
+use crate::{
+    boundary::{wrap_position, Boundary},
+    energy::Energy,
+    flocking::limit_velocity,
+    grab::Held,
+    obstacles::Obstacle,
+    player::Player,
+    predators::Predator,
+    roost::Perched,
+    settings::{BoidSettings, BoundaryMode},
+    simulation::simulation_running,
+    variation::{with_traits, BoidTraits, Mass},
+    steered_flight,
+    top_speed,
+    Acceleration,
+    BoidSet,
+    Flown,
+    Velocity,
+    WorldBounds,
+};
+
+// Rapier tunes its tolerances for objects about a meter across, about a boid here
+const LENGTH_UNIT: f32 = 8.;
+
+// Boids and obstacles as rapier bodies, so they collide instead of passing through each other.
+// Steering forces are handed to rapier as they are and it integrates them, in place of
+// velocity_system and movement_system. The player and boids held or perched aren't steered, so
+// those are still pushed towards whatever Velocity they were given
+pub struct PhysicsPlugin;
+
+impl Plugin for PhysicsPlugin {
+    fn build(&self, app: &mut App) {
+        // Stepping along with the simulation's fixed timestep
+        let dt = app.world.resource::<Time<Fixed>>().timestep().as_secs_f32();
+        app.insert_resource(RapierConfiguration {
+                gravity: Vec2::ZERO,
+                timestep_mode: TimestepMode::Fixed { dt, substeps: 1 },
+                ..RapierConfiguration::new(LENGTH_UNIT)
+            })
+            .add_plugins(RapierPhysicsPlugin::<NoUserData>::pixels_per_meter(LENGTH_UNIT).in_fixed_schedule())
+            .configure_sets(FixedUpdate, (
+                PhysicsSet::SyncBackend.after(BoidSet::Movement),
+                PhysicsSet::StepSimulation.run_if(simulation_running),
+            ))
+            .add_systems(FixedUpdate, (
+                (add_boid_bodies, add_obstacle_bodies).before(BoidSet::Index),
+                (steer_bodies, drive_bodies).after(BoidSet::Movement).before(PhysicsSet::SyncBackend),
+                (limit_bodies, sync_from_bodies).chain().after(PhysicsSet::Writeback),
+            ));
+    }
+}
+
+// Every moving entity: boids, predators and the player
+fn add_boid_bodies(mut commands: Commands, added: Query<(Entity, &Velocity), Added<Velocity>>) {
+    for (entity, velocity) in added.iter() {
+        commands.entity(entity).insert((
+            RigidBody::Dynamic,
+            // Scaled by the transform to the boid's size
+            Collider::ball(0.5),
+            BodyVelocity::linear(velocity.0),
+            ExternalForce::default(),
+            ReadMassProperties::default(),
+            // Boids face along their velocity rather than spinning off contacts
+            LockedAxes::ROTATION_LOCKED,
+        ));
+    }
+}
+
+fn add_obstacle_bodies(mut commands: Commands, added: Query<(Entity, &Obstacle), Added<Obstacle>>) {
+    for (entity, obstacle) in added.iter() {
+        let collider = match *obstacle {
+            Obstacle::Circle { radius } => Collider::ball(radius),
+            Obstacle::Rect { half_size } => Collider::cuboid(half_size.x, half_size.y),
+        };
+        commands.entity(entity).insert((RigidBody::Fixed, collider));
+    }
+}
+
+// Each flown boid's steering, boundary turn, thrust and drag as velocity_system would total
+// them, scaled to the body's own mass so it answers them as the boid would
+fn steer_bodies(
+    mut bodies: Query<SteeredBody, (Flown, Without<Perched>)>,
+    boundary: Res<Boundary>,
+    bounds: Res<WorldBounds>,
+    settings: Res<BoidSettings>,
+) {
+    for (mut acceleration, body, body_mass, mut force, transform, traits, mass, energy, predator) in bodies.iter_mut() {
+        let settings = with_traits(&settings, traits, mass);
+        let position = transform.translation.xy();
+        let flight = steered_flight(&settings, &mut acceleration, position, mass, energy, predator, &boundary, **bounds);
+        force.force = flight.acceleration(body.linvel) * body_mass.get().mass;
+    }
+}
+
+type SteeredBody = (
+    &'static mut Acceleration,
+    &'static BodyVelocity,
+    &'static ReadMassProperties,
+    &'static mut ExternalForce,
+    &'static Transform,
+    Option<&'static BoidTraits>,
+    Option<&'static Mass>,
+    Option<&'static Energy>,
+    Has<Predator>,
+);
+
+type Unsteered = Or<(With<Player>, With<Held>, With<Perched>)>;
+
+// The force that takes each body that isn't steered from its current velocity to the one it was
+// given this tick
+fn drive_bodies(
+    mut bodies: Query<(&Velocity, &BodyVelocity, &ReadMassProperties, &mut ExternalForce), Unsteered>,
+    time: Res<Time>,
+) {
+    let dt = time.delta_seconds();
+    if dt <= 0.0 {
+        return;
+    }
+
+    for (velocity, body, mass, mut force) in bodies.iter_mut() {
+        force.force = (velocity.0 - body.linvel) * mass.get().mass / dt;
+    }
+}
+
+// Rapier answers steering and collisions alike, so each flown boid is held to the same turn rate
+// and speeds velocity_system keeps it to, the body carrying on from there next step
+fn limit_bodies(
+    mut bodies: Query<LimitedBody, (Flown, Without<Perched>)>,
+    settings: Res<BoidSettings>,
+    time: Res<Time>,
+) {
+    let dt = time.delta_seconds();
+
+    for (mut body, velocity, traits, mass, energy, predator) in bodies.iter_mut() {
+        let settings = with_traits(&settings, traits, mass);
+        let max_speed = top_speed(&settings, energy, predator);
+        body.linvel = limit_velocity(&settings, velocity.0, body.linvel, max_speed, dt);
+    }
+}
+
+type LimitedBody = (
+    &'static mut BodyVelocity,
+    &'static Velocity,
+    Option<&'static BoidTraits>,
+    Option<&'static Mass>,
+    Option<&'static Energy>,
+    Has<Predator>,
+);
+
+// What the step made of it, along with what movement_system would otherwise do
+fn sync_from_bodies(
+    mut bodies: Query<(&mut Velocity, &BodyVelocity, &mut Transform, Option<&mut Energy>)>,
+    bounds: Res<WorldBounds>,
+    settings: Res<BoidSettings>,
+    time: Res<Time>,
+) {
+    let wrap = settings.boundary_mode == BoundaryMode::Wrap;
+    let dt = time.delta_seconds();
+
+    for (mut velocity, body, mut transform, energy) in bodies.iter_mut() {
+        if let Some(mut energy) = energy {
+            energy.spend(&settings, velocity.0, body.linvel, dt);
+        }
+        velocity.0 = body.linvel;
+
+        if let Some(heading) = body.linvel.try_normalize() {
+            transform.rotation = Quat::from_rotation_arc_2d(Vec2::X, heading);
+        }
+
+        if wrap {
+            let wrapped = wrap_position(transform.translation.xy(), **bounds);
+            transform.translation.x = wrapped.x;
+            transform.translation.y = wrapped.y;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bench::headless_app, SpatialEntity};
+
+    #[test]
+    fn bodies_fly_within_the_limits() {
+        let settings = BoidSettings { count: 100, drag: 0.0, ..default() };
+        let mut app = headless_app(settings.clone(), Some(1), |app| {
+            // Rapier places bodies by their global transforms and looks for mesh colliders
+            app.add_plugins((TransformPlugin, HierarchyPlugin, AssetPlugin::default(), PhysicsPlugin))
+                .init_asset::<Mesh>();
+        });
+        let dt = app.world.resource::<Time<Fixed>>().timestep().as_secs_f32();
+        let max_turn = settings.max_turn_rate.to_radians() * dt;
+
+        let mut boids = app.world.query_filtered::<(Entity, &Velocity), With<SpatialEntity>>();
+        let mut before: Vec<_> = boids.iter(&app.world).map(|(boid, velocity)| (boid, velocity.0)).collect();
+        for _ in 0..60 {
+            app.update();
+
+            let after: Vec<_> = boids.iter(&app.world).map(|(boid, velocity)| (boid, velocity.0)).collect();
+            for ((_, before), (_, after)) in before.iter().zip(&after).filter(|(a, b)| a.0 == b.0) {
+                let speed = after.length();
+                assert!(speed >= settings.min_speed * 0.999 && speed <= settings.max_speed * 1.001, "{speed}");
+                assert!(before.angle_between(*after).abs() <= max_turn + 1e-4, "{before} to {after}");
+            }
+            before = after;
+        }
+    }
+}
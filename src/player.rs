@@ -0,0 +1,114 @@
+use bevy::{math::Vec3Swizzles, prelude::*};
+
+use crate::{
+    input::{Action, Actions},
+    rng::SimRng,
+    settings::BoidSettings,
+    species::Species,
+    spawn_boid,
+    BoidSet,
+    BoidSpawn,
+    CursorPosition,
+    Velocity,
+    WorldBounds,
+};
+
+// Steered by keys or a gamepad stick instead of flocking, its species still flocks with it
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
+pub struct Player;
+
+// `P` spawns or removes the player boid
+pub struct PlayerPlugin;
+
+impl Plugin for PlayerPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Player>()
+            .add_systems(Update, (toggle_player, draw_player))
+            .add_systems(FixedUpdate, steer_player.in_set(BoidSet::Velocity));
+    }
+}
+
+fn toggle_player(
+    mut commands: Commands,
+    mut rng: ResMut<SimRng>,
+    mut settings: ResMut<BoidSettings>,
+    actions: Actions,
+    cursor: Res<CursorPosition>,
+    player: Query<Entity, With<Player>>,
+) {
+    if !actions.just_pressed(Action::Player) {
+        return;
+    }
+
+    // Counted with the flock, without re-triggering boid_count_system
+    let settings = settings.bypass_change_detection();
+
+    if let Ok(player) = player.get_single() {
+        commands.entity(player).despawn();
+        settings.count = settings.count.saturating_sub(1);
+        return;
+    }
+
+    let mut spawn = BoidSpawn::random(&mut **rng, settings, cursor.0.unwrap_or(Vec2::ZERO));
+    spawn.species = Species(0);
+    spawn.color = Color::WHITE;
+    spawn.traits = None;
+    spawn.mass = None;
+
+    let player = spawn_boid(&mut commands, settings, spawn);
+    commands.entity(player).insert(Player);
+    settings.count += 1;
+}
+
+// Stick or keys set the heading at full speed, letting go coasts at minimum speed
+fn steer_player(
+    mut player: Query<(&mut Velocity, &mut Transform), With<Player>>,
+    actions: Actions,
+    gamepads: Res<Gamepads>,
+    axes: Res<Axis<GamepadAxis>>,
+    bounds: Res<WorldBounds>,
+    settings: Res<BoidSettings>,
+) {
+    let Ok((mut velocity, mut transform)) = player.get_single_mut() else {
+        return;
+    };
+
+    let mut input = Vec2::ZERO;
+    for (action, dir) in [
+        (Action::PlayerUp, Vec2::Y),
+        (Action::PlayerDown, Vec2::NEG_Y),
+        (Action::PlayerLeft, Vec2::NEG_X),
+        (Action::PlayerRight, Vec2::X),
+    ] {
+        if actions.pressed(action) {
+            input += dir;
+        }
+    }
+    for gamepad in gamepads.iter() {
+        let x = axes.get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickX)).unwrap_or(0.0);
+        let y = axes.get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickY)).unwrap_or(0.0);
+        input += Vec2::new(x, y);
+    }
+
+    velocity.0 = match input.try_normalize() {
+        Some(dir) => dir * settings.max_speed * input.length().min(1.0),
+        None => velocity.0.try_normalize().unwrap_or(Vec2::X) * settings.min_speed,
+    };
+
+    // Boundary steering doesn't apply, so keep it on screen
+    let half = **bounds / 2.0;
+    let position = transform.translation.xy().clamp(-half, half);
+    transform.translation.x = position.x;
+    transform.translation.y = position.y;
+}
+
+fn draw_player(
+    player: Query<&Transform, With<Player>>,
+    settings: Res<BoidSettings>,
+    mut gizmos: Gizmos,
+) {
+    for transform in player.iter() {
+        gizmos.circle_2d(transform.translation.xy(), settings.size * 1.5, Color::WHITE);
+    }
+}
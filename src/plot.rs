@@ -0,0 +1,205 @@
+use std::collections::VecDeque;
+use bevy::{
+    math::Vec3Swizzles,
+    prelude::*,
+    render::{camera::{ScalingMode, Viewport}, view::RenderLayers},
+    window::PrimaryWindow,
+};
+use bevy_spatial::SpatialAccess;
+
+use crate::{
+    index::BoidIndex,
+    input::{Action, Actions},
+    settings::BoidSettings,
+    simulation::simulation_running,
+    BoidSet,
+    SpatialEntity,
+    Velocity,
+};
+
+// Drawn only by the plot's own camera, as the minimap is on its layer
+const PLOT_LAYER: u8 = 2;
+// Size of the plot in logical pixels, in the bottom left corner
+const PLOT_SIZE: Vec2 = Vec2::new(320., 120.);
+const PLOT_MARGIN: f32 = 8.;
+// Seconds of simulated time the plot spans, sampled this often
+const PLOT_SECONDS: f32 = 30.;
+const SAMPLE_INTERVAL: f32 = 0.1;
+
+const POLARIZATION_COLOR: Color = Color::WHITE;
+const SPACING_COLOR: Color = Color::CYAN;
+
+#[derive(Default, Reflect, GizmoConfigGroup)]
+struct PlotGizmos;
+
+#[derive(Component)]
+struct PlotCamera;
+
+#[derive(Component)]
+struct PlotLegend;
+
+// Order parameters over the last `PLOT_SECONDS`: polarization, and the mean distance to each
+// boid's nearest neighbor as a fraction of `vis_range`
+#[derive(Resource, Default)]
+struct OrderHistory {
+    shown: bool,
+    // Simulated seconds since shown, and the time until the next sample
+    elapsed: f32,
+    due: f32,
+    samples: VecDeque<(f32, f32, f32)>,
+}
+
+// `Y` toggles a plot of how ordered the flock has been lately, to watch tuning change it
+pub struct OrderPlotPlugin;
+
+impl Plugin for OrderPlotPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<OrderHistory>()
+            .insert_gizmo_group(PlotGizmos, GizmoConfig {
+                render_layers: RenderLayers::layer(PLOT_LAYER),
+                ..default()
+            })
+            .add_systems(Startup, spawn_plot)
+            .add_systems(FixedUpdate, sample_order.after(BoidSet::Movement).run_if(simulation_running))
+            .add_systems(Update, (toggle_plot, place_plot, draw_plot).chain());
+    }
+}
+
+fn spawn_plot(mut commands: Commands) {
+    // Looking at the unit square, time along x and both parameters 0 to 1 up y
+    let mut camera = Camera2dBundle {
+        camera: Camera {
+            order: 3,
+            is_active: false,
+            clear_color: ClearColorConfig::Custom(Color::rgb(0.05, 0.05, 0.08)),
+            ..default()
+        },
+        transform: Transform::from_xyz(0.5, 0.5, 0.0),
+        ..default()
+    };
+    camera.projection.scaling_mode = ScalingMode::Fixed { width: 1.1, height: 1.2 };
+
+    commands.spawn((camera, RenderLayers::layer(PLOT_LAYER), PlotCamera));
+
+    let style = |color| TextStyle { font_size: 14.0, color, ..default() };
+    commands.spawn((
+        TextBundle::from_sections([
+            TextSection::new("polarization", style(POLARIZATION_COLOR)),
+            TextSection::new("  nearest neighbor / vis range", style(SPACING_COLOR)),
+            TextSection::new(format!("  last {PLOT_SECONDS:.0} s"), style(Color::GRAY)),
+        ])
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            bottom: Val::Px(PLOT_MARGIN * 2.0 + PLOT_SIZE.y),
+            left: Val::Px(PLOT_MARGIN),
+            ..default()
+        }),
+        Visibility::Hidden,
+        PlotLegend,
+    ));
+}
+
+fn toggle_plot(
+    actions: Actions,
+    mut history: ResMut<OrderHistory>,
+    mut camera: Query<&mut Camera, With<PlotCamera>>,
+    mut legend: Query<&mut Visibility, With<PlotLegend>>,
+) {
+    if !actions.just_pressed(Action::Plot) {
+        return;
+    }
+
+    // Starts over each time, so gaps while hidden don't show as flat lines
+    let shown = !history.shown;
+    *history = OrderHistory { shown, ..default() };
+
+    for mut camera in camera.iter_mut() {
+        camera.is_active = shown;
+    }
+    for mut visibility in legend.iter_mut() {
+        *visibility = if shown { Visibility::Visible } else { Visibility::Hidden };
+    }
+}
+
+fn sample_order(
+    mut history: ResMut<OrderHistory>,
+    boids: Query<(Entity, &Transform, &Velocity), With<SpatialEntity>>,
+    index: Res<BoidIndex>,
+    settings: Res<BoidSettings>,
+    time: Res<Time>,
+) {
+    if !history.shown {
+        return;
+    }
+
+    // Counted in ticks run, so a paused flock holds still on the plot too
+    history.elapsed += time.delta_seconds();
+    history.due -= time.delta_seconds();
+    if history.due > 0.0 {
+        return;
+    }
+    history.due = SAMPLE_INTERVAL;
+
+    let mut heading = Vec2::ZERO;
+    let mut spacing = 0.0;
+    for (boid, transform, velocity) in boids.iter() {
+        heading += velocity.0.normalize_or_zero();
+
+        // The closest hit is usually the boid itself
+        let position = transform.translation.xy();
+        let nearest = index.k_nearest_neighbour(position, 2).into_iter()
+            .find(|(_, neighbor)| *neighbor != Some(boid))
+            .map_or(settings.vis_range, |(point, _)| point.distance(position));
+        spacing += nearest.min(settings.vis_range);
+    }
+
+    let n = boids.iter().len().max(1) as f32;
+    let sample = (history.elapsed, heading.length() / n, spacing / n / settings.vis_range.max(f32::EPSILON));
+    history.samples.push_back(sample);
+
+    while history.samples.front().is_some_and(|(at, ..)| *at < history.elapsed - PLOT_SECONDS) {
+        history.samples.pop_front();
+    }
+}
+
+fn place_plot(mut camera: Query<&mut Camera, With<PlotCamera>>, window: Query<&Window, With<PrimaryWindow>>) {
+    let (Ok(mut camera), Ok(window)) = (camera.get_single_mut(), window.get_single()) else {
+        return;
+    };
+    if !camera.is_active {
+        return;
+    }
+
+    let size = PLOT_SIZE.min(Vec2::new(window.width(), window.height()) - 2.0 * PLOT_MARGIN);
+    if size.min_element() < 1.0 {
+        return;
+    }
+
+    let scale = window.scale_factor();
+    let corner = Vec2::new(PLOT_MARGIN, window.height() - size.y - PLOT_MARGIN);
+    let viewport = ((corner * scale).as_uvec2(), (size * scale).as_uvec2());
+    if camera.viewport.as_ref().map(|v| (v.physical_position, v.physical_size)) != Some(viewport) {
+        camera.viewport = Some(Viewport {
+            physical_position: viewport.0,
+            physical_size: viewport.1,
+            ..default()
+        });
+    }
+}
+
+fn draw_plot(history: Res<OrderHistory>, mut gizmos: Gizmos<PlotGizmos>) {
+    if !history.shown {
+        return;
+    }
+
+    gizmos.rect_2d(Vec2::splat(0.5), 0.0, Vec2::ONE, Color::DARK_GRAY);
+    gizmos.line_2d(Vec2::new(0.0, 0.5), Vec2::new(1.0, 0.5), Color::rgb(0.2, 0.2, 0.2));
+
+    // Newest on the right edge, scrolling left
+    let x = |at: f32| 1.0 - (history.elapsed - at) / PLOT_SECONDS;
+    let points = |value: fn(&(f32, f32, f32)) -> f32| history.samples.iter()
+        .map(move |sample| Vec2::new(x(sample.0), value(sample).clamp(0.0, 1.0)));
+
+    gizmos.linestrip_2d(points(|sample| sample.1), POLARIZATION_COLOR);
+    gizmos.linestrip_2d(points(|sample| sample.2), SPACING_COLOR);
+}
@@ -0,0 +1,262 @@
+use rand::prelude::*;
+use bevy::{
+    ecs::system::SystemParam,
+    math::Vec3Swizzles,
+    prelude::*,
+    utils::HashSet,
+};
+use bevy_spatial::{
+    kdtree::KDTree2,
+    SpatialAccess,
+};
+
+use crate::{
+    index::BoidIndex,
+    obstacles::Obstacles,
+    rng::SimRng,
+    settings::BoidSettings,
+    spatial::rebuild_tree2,
+    boundary_system,
+    setup,
+    spawn_boid,
+    Acceleration,
+    AnyBoid,
+    BoidColor,
+    BoidSet,
+    BoidSpawn,
+    SpatialEntity,
+    Travel,
+    Velocity,
+    WorldBounds,
+};
+
+// A burst of particles fades out over this many seconds
+const BURST_LIFETIME: f32 = 0.5;
+const BURST_PARTICLES: usize = 8;
+const BURST_SPEED: f32 = 60.;
+
+// Sent when a predator catches a boid, before it is despawned
+#[derive(Event, Clone, Copy)]
+pub struct PreyCaught {
+    pub position: Vec2,
+    pub color: Color,
+}
+
+// Seconds left until each caught boid is replaced
+#[derive(Resource, Default)]
+struct PendingRespawns(Vec<f32>);
+
+// Hunts the nearest prey boid; tracked in its own tree, never flocks
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
+pub struct Predator;
+
+pub struct PredatorPlugin;
+
+impl Plugin for PredatorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<KDTree2<Predator>>()
+            .register_type::<Predator>()
+            .add_systems(FixedUpdate, rebuild_tree2::<Predator>.in_set(BoidSet::Index))
+            // Prey draw from the shared rng first, keeping seeded runs reproducible
+            .add_systems(Startup, spawn_predators.after(setup))
+            .add_systems(FixedUpdate, predator_chase_system.in_set(BoidSet::Steer))
+            .init_resource::<PendingRespawns>()
+            .add_event::<PreyCaught>()
+            .add_systems(FixedUpdate, (catch_system, respawn_system)
+                .chain()
+                .in_set(BoidSet::Movement)
+                .after(boundary_system));
+    }
+}
+
+fn catch_system(
+    mut commands: Commands,
+    predators: Query<&Transform, With<Predator>>,
+    prey: Query<&BoidColor, With<SpatialEntity>>,
+    tree: Res<BoidIndex>,
+    mut pending: ResMut<PendingRespawns>,
+    mut caught: EventWriter<PreyCaught>,
+    settings: Res<BoidSettings>,
+) {
+    let mut eaten = HashSet::new();
+
+    for transform in predators.iter() {
+        // The index holds positions from the start of the tick, and may still list
+        // boids despawned since, including ones caught earlier this tick
+        let target = tree.within_distance(transform.translation.xy(), settings.catch_range)
+            .into_iter()
+            .filter_map(|(position, entity)| Some((position, entity?)))
+            .find(|(_, entity)| prey.contains(*entity) && !eaten.contains(entity));
+
+        let Some((position, boid)) = target else {
+            continue;
+        };
+
+        caught.send(PreyCaught {
+            position,
+            color: prey.get(boid).map_or(Color::WHITE, |color| color.0),
+        });
+
+        commands.entity(boid).despawn();
+        eaten.insert(boid);
+        pending.0.push(settings.respawn_delay);
+    }
+}
+
+// Caught boids come back at a random point on the edge of the world
+fn respawn_system(
+    mut commands: Commands,
+    mut pending: ResMut<PendingRespawns>,
+    mut rng: ResMut<SimRng>,
+    boids: Query<(), AnyBoid>,
+    bounds: Res<WorldBounds>,
+    time: Res<Time>,
+    settings: Res<BoidSettings>,
+) {
+    let dt = time.delta_seconds();
+    let due = pending.0.iter_mut().fold(0, |due, delay| {
+        *delay -= dt;
+        due + usize::from(*delay <= 0.0)
+    });
+    pending.0.retain(|delay| *delay > 0.0);
+
+    // The count may have been lowered meanwhile, don't overshoot it
+    let room = settings.count.saturating_sub(boids.iter().len());
+    let half = **bounds / 2.0;
+
+    for _ in 0..due.min(room) {
+        let along = rng.random_range(-1.0..1.0);
+        let position = match rng.random_range(0..4) {
+            0 => Vec2::new(-half.x, along * half.y),
+            1 => Vec2::new(half.x, along * half.y),
+            2 => Vec2::new(along * half.x, -half.y),
+            _ => Vec2::new(along * half.x, half.y),
+        };
+
+        let spawn = BoidSpawn::random(&mut **rng, &settings, position);
+        spawn_boid(&mut commands, &settings, spawn);
+    }
+}
+
+fn spawn_predators(
+    mut commands: Commands,
+    mut rng: ResMut<SimRng>,
+    bounds: Res<WorldBounds>,
+    settings: Res<BoidSettings>,
+) {
+    for _ in 0..settings.predator_count {
+        spawn_predator(&mut commands, &mut rng, &settings, **bounds);
+    }
+}
+
+// At a random point in the world, heading a random way
+pub fn spawn_predator(commands: &mut Commands, rng: &mut SimRng, settings: &BoidSettings, bounds: Vec2) {
+    let half = bounds / 2.0;
+    let position = Vec2::new(rng.random_range(-half.x..half.x),
+                             rng.random_range(-half.y..half.y));
+
+    commands.spawn((
+        // Drawn above and twice the size of prey
+        SpatialBundle::from_transform(Transform::from_translation(position.extend(1.0))
+            .with_scale(Vec3::splat(settings.size * 2.0))),
+        Velocity(Vec2::new(rng.random_range(-1.0..1.0),
+                           rng.random_range(-1.0..1.0)) * settings.min_speed),
+        Acceleration::default(),
+        Travel::default(),
+        BoidColor(Color::RED),
+        Predator,
+    ));
+}
+
+fn predator_chase_system(
+    mut predators: Query<(&Transform, &mut Acceleration), With<Predator>>,
+    prey: Res<BoidIndex>,
+    obstacles: Obstacles,
+    settings: Res<BoidSettings>,
+) {
+    for (transform, mut acceleration) in predators.iter_mut() {
+        let position = transform.translation.xy();
+        let mut dv = obstacles.avoidance(&settings, position);
+
+        if let Some((target, _)) = prey.nearest_neighbour(position) {
+            dv += (target - position) * settings.predator_chase_factor;
+        }
+
+        acceleration.0 = dv;
+    }
+}
+
+// Read access to predators for prey steering during the flocking pass
+#[derive(SystemParam)]
+pub struct Predators<'w> {
+    tree: Res<'w, KDTree2<Predator>>,
+}
+
+impl Predators<'_> {
+    pub fn near(&self, settings: &BoidSettings, position: Vec2) -> bool {
+        !self.tree.within_distance(position, settings.vis_range).is_empty()
+    }
+
+    // Steering away from every predator within vision range, stronger the closer it is
+    pub fn flee(&self, settings: &BoidSettings, position: Vec2) -> Vec2 {
+        let mut dv = Vec2::ZERO;
+
+        for (predator, _) in self.tree.within_distance(position, settings.vis_range) {
+            let away = position - predator;
+            let strength = 1.0 - away.length() / settings.vis_range;
+            dv += away.normalize_or_zero() * strength * settings.flee_factor;
+        }
+
+        dv
+    }
+}
+
+// Draws a fading ring of particles wherever a boid was caught
+pub struct CatchBurstPlugin;
+
+impl Plugin for CatchBurstPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (spawn_bursts, draw_bursts).chain());
+    }
+}
+
+#[derive(Component)]
+struct Burst {
+    color: Color,
+    age: f32,
+}
+
+fn spawn_bursts(mut commands: Commands, mut caught: EventReader<PreyCaught>) {
+    for PreyCaught { position, color } in caught.read() {
+        commands.spawn((
+            Burst { color: *color, age: 0.0 },
+            TransformBundle::from_transform(Transform::from_translation(position.extend(0.0))),
+        ));
+    }
+}
+
+fn draw_bursts(
+    mut commands: Commands,
+    mut bursts: Query<(Entity, &mut Burst, &Transform)>,
+    time: Res<Time>,
+    mut gizmos: Gizmos,
+) {
+    for (entity, mut burst, transform) in bursts.iter_mut() {
+        burst.age += time.delta_seconds();
+
+        if burst.age >= BURST_LIFETIME {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        let fade = 1.0 - burst.age / BURST_LIFETIME;
+        let center = transform.translation.xy();
+
+        for i in 0..BURST_PARTICLES {
+            let angle = std::f32::consts::TAU * i as f32 / BURST_PARTICLES as f32;
+            let position = center + Vec2::from_angle(angle) * burst.age * BURST_SPEED;
+            gizmos.circle_2d(position, 1.5, burst.color.with_a(fade));
+        }
+    }
+}
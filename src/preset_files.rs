@@ -0,0 +1,85 @@
+use std::{error::Error, fs, path::PathBuf};
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::{settings::BoidSettings, species::SpeciesInteractions};
+
+// Where preset files are saved to and listed from, relative to the working directory
+pub const PRESET_DIR: &str = "presets";
+
+// A tuned behavior to share: every setting and how the species treat each other, but no boids,
+// unlike a snapshot. Fields left out of a file take their defaults
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct PresetFile {
+    pub settings: BoidSettings,
+    pub species: SpeciesInteractions,
+}
+
+// `starling murmuration` is kept in `presets/starling_murmuration.ron`, and listed back as such
+fn file_name(name: &str) -> String {
+    let stem: String = name.trim()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' { c.to_ascii_lowercase() } else { '_' })
+        .collect();
+    format!("{stem}.ron")
+}
+
+fn path(name: &str) -> PathBuf {
+    [PRESET_DIR, &file_name(name)].iter().collect()
+}
+
+impl PresetFile {
+    pub fn current(settings: &BoidSettings, species: &SpeciesInteractions) -> Self {
+        Self { settings: settings.clone(), species: species.clone() }
+    }
+
+    pub fn save(&self, name: &str) -> Result<PathBuf, Box<dyn Error>> {
+        let ron = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        let path = path(name);
+        fs::create_dir_all(PRESET_DIR)?;
+        fs::write(&path, ron)?;
+        Ok(path)
+    }
+
+    pub fn load(name: &str) -> Result<Self, Box<dyn Error>> {
+        Ok(ron::from_str(&fs::read_to_string(path(name))?)?)
+    }
+
+    // Every preset in `PRESET_DIR` by name, sorted; none if it doesn't exist yet
+    pub fn list() -> Vec<String> {
+        let Ok(entries) = fs::read_dir(PRESET_DIR) else {
+            return vec![];
+        };
+
+        let mut names: Vec<_> = entries
+            .filter_map(|entry| entry.ok()?.path().file_name()?.to_str()?.strip_suffix(".ron").map(str::to_owned))
+            .map(|stem| stem.replace('_', " "))
+            .collect();
+        names.sort();
+        names
+    }
+
+    // Takes over the behavior, leaving the flock as big as it is
+    pub fn apply(self, settings: &mut ResMut<BoidSettings>, species: &mut ResMut<SpeciesInteractions>) {
+        let count = settings.count;
+        settings.set_if_neq(BoidSettings { count, ..self.settings });
+        species.set_if_neq(self.species);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_map_to_files_and_back() {
+        assert_eq!(file_name("Lazy fish school"), "lazy_fish_school.ron");
+        assert_eq!(file_name(" ../starling-murmuration "), "___starling-murmuration.ron");
+
+        let preset: PresetFile = ron::from_str("(settings: (max_speed: 50.0), species: [[Flock, Avoid]])").unwrap();
+        assert_eq!(preset.settings.max_speed, 50.0);
+        assert_eq!(preset.settings.min_speed, BoidSettings::default().min_speed);
+        assert_eq!(preset.species.0.len(), 1);
+    }
+}
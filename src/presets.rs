@@ -0,0 +1,252 @@
+use rand::prelude::*;
+use bevy::prelude::*;
+use clap::ValueEnum;
+
+use crate::{
+    attractors::{Attractor, Repeller},
+    cli::Cli,
+    food::Food,
+    input::{Action, Actions},
+    obstacles::{spawn_obstacles, Obstacle},
+    predators::{spawn_predator, Predator},
+    rng::SimRng,
+    roost::Perched,
+    settings::BoidSettings,
+    waypoints::Waypoints,
+    spawn_boid,
+    BoidSpawn,
+    SpatialEntity,
+    WorldBounds,
+};
+
+// Everything a preset clears away before laying out its own
+type PresetEntities = Or<(
+    With<SpatialEntity>,
+    With<Perched>,
+    With<Predator>,
+    With<Obstacle>,
+    With<Attractor>,
+    With<Repeller>,
+    With<Food>,
+)>;
+
+// Built-in scenarios, bundling settings with the obstacles and route they fly around
+#[derive(Clone, Copy, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Preset {
+    // A dense, fast-aligning flock wheeling away from a lone falcon
+    Murmuration,
+    // Loose, slow boids barely holding together
+    Cruising,
+    // A pack of quick predators harrying the flock
+    Chase,
+    // The flock weaving through a line of posts along a waypoint route
+    Slalom,
+}
+
+// Selected with the number keys in this order
+const PRESETS: [(Action, Preset); 4] = [
+    (Action::Preset1, Preset::Murmuration),
+    (Action::Preset2, Preset::Cruising),
+    (Action::Preset3, Preset::Chase),
+    (Action::Preset4, Preset::Slalom),
+];
+
+// The settings every preset sets, so switching between them never leaves one's tuning behind
+struct Tuning {
+    count: usize,
+    predator_count: usize,
+    predator_max_speed: f32,
+    vis_range: f32,
+    prot_range: f32,
+    center_factor: f32,
+    matching_factor: f32,
+    avoid_factor: f32,
+    wander_factor: f32,
+    wind_factor: f32,
+    min_speed: f32,
+    max_speed: f32,
+}
+
+impl Default for Tuning {
+    fn default() -> Self {
+        let defaults = BoidSettings::default();
+        Self {
+            count: defaults.count,
+            predator_count: defaults.predator_count,
+            predator_max_speed: defaults.predator_max_speed,
+            vis_range: defaults.vis_range,
+            prot_range: defaults.prot_range,
+            center_factor: defaults.center_factor,
+            matching_factor: defaults.matching_factor,
+            avoid_factor: defaults.avoid_factor,
+            wander_factor: defaults.wander_factor,
+            wind_factor: defaults.wind_factor,
+            min_speed: defaults.min_speed,
+            max_speed: defaults.max_speed,
+        }
+    }
+}
+
+// Posts spread across the middle of the world for the slalom, as a fraction of its half size
+const SLALOM_POSTS: usize = 5;
+const SLALOM_SPAN: f32 = 0.7;
+const SLALOM_OFFSET: f32 = 0.2;
+const SLALOM_RADIUS: f32 = 25.;
+
+impl Preset {
+    fn tuning(&self) -> Tuning {
+        match self {
+            Preset::Murmuration => Tuning {
+                count: 800,
+                predator_count: 1,
+                vis_range: 50.,
+                prot_range: 6.,
+                center_factor: 2.4,
+                matching_factor: 6.0,
+                // Gusts sway the whole murmuration
+                wind_factor: 150.,
+                ..default()
+            },
+            Preset::Cruising => Tuning {
+                count: 120,
+                predator_count: 0,
+                vis_range: 30.,
+                center_factor: 0.3,
+                matching_factor: 1.0,
+                wander_factor: 120.,
+                min_speed: 60.,
+                max_speed: 140.,
+                ..default()
+            },
+            Preset::Chase => Tuning {
+                count: 300,
+                predator_count: 5,
+                predator_max_speed: 300.,
+                ..default()
+            },
+            Preset::Slalom => Tuning {
+                count: 200,
+                predator_count: 0,
+                avoid_factor: 240.,
+                ..default()
+            },
+        }
+    }
+
+    pub fn apply(&self, settings: &mut BoidSettings) {
+        let tuning = self.tuning();
+        settings.count = tuning.count;
+        settings.predator_count = tuning.predator_count;
+        settings.predator_max_speed = tuning.predator_max_speed;
+        settings.vis_range = tuning.vis_range;
+        settings.prot_range = tuning.prot_range;
+        settings.center_factor = tuning.center_factor;
+        settings.matching_factor = tuning.matching_factor;
+        settings.avoid_factor = tuning.avoid_factor;
+        settings.wander_factor = tuning.wander_factor;
+        settings.wind_factor = tuning.wind_factor;
+        settings.min_speed = tuning.min_speed;
+        settings.max_speed = tuning.max_speed;
+    }
+
+    // Obstacles and waypoint route for a world of the given size
+    fn layout(&self, bounds: Vec2) -> (Vec<(Obstacle, Vec2)>, Vec<Vec2>) {
+        let half = bounds / 2.0;
+
+        match self {
+            Preset::Slalom => {
+                let step = 2.0 * SLALOM_SPAN / (SLALOM_POSTS - 1) as f32;
+                let posts = (0..SLALOM_POSTS)
+                    .map(|i| {
+                        let side = if i % 2 == 0 { 1.0 } else { -1.0 };
+                        Vec2::new(-SLALOM_SPAN + i as f32 * step, side * SLALOM_OFFSET) * half
+                    })
+                    .collect::<Vec<_>>();
+
+                // Past each post on the side away from it, out and back along the line
+                let out = posts.iter().map(|post| Vec2::new(post.x, -post.y * 2.0));
+                let back = posts.iter().rev().map(|post| Vec2::new(post.x, post.y * 3.0));
+
+                let obstacles = posts.iter()
+                    .map(|&post| (Obstacle::Circle { radius: SLALOM_RADIUS }, post))
+                    .collect();
+                (obstacles, out.chain(back).collect())
+            }
+            _ => (vec![], vec![]),
+        }
+    }
+}
+
+// Number keys reset the world to one of the presets, `--preset <name>` starts in one
+pub struct PresetPlugin;
+
+impl Plugin for PresetPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, lay_out_cli_preset.after(spawn_obstacles))
+            .add_systems(Update, select_preset);
+    }
+}
+
+// The command line preset's settings are applied with the rest of `Cli`, leaving only its layout
+fn lay_out_cli_preset(
+    mut commands: Commands,
+    cli: Option<Res<Cli>>,
+    obstacles: Query<Entity, With<Obstacle>>,
+    mut waypoints: ResMut<Waypoints>,
+    bounds: Res<WorldBounds>,
+) {
+    let Some(preset) = cli.and_then(|cli| cli.preset) else {
+        return;
+    };
+
+    for obstacle in obstacles.iter() {
+        commands.entity(obstacle).despawn();
+    }
+    lay_out(&mut commands, &mut waypoints, preset, **bounds);
+}
+
+fn lay_out(commands: &mut Commands, waypoints: &mut Waypoints, preset: Preset, bounds: Vec2) {
+    let (obstacles, route) = preset.layout(bounds);
+
+    for (obstacle, position) in obstacles {
+        commands.spawn((obstacle, TransformBundle::from_transform(Transform::from_translation(position.extend(0.0)))));
+    }
+    *waypoints = Waypoints { points: route, current: 0 };
+}
+
+fn select_preset(
+    mut commands: Commands,
+    actions: Actions,
+    mut settings: ResMut<BoidSettings>,
+    mut rng: ResMut<SimRng>,
+    mut waypoints: ResMut<Waypoints>,
+    entities: Query<Entity, PresetEntities>,
+    bounds: Res<WorldBounds>,
+) {
+    let Some(&(_, preset)) = PRESETS.iter().find(|(action, _)| actions.just_pressed(*action)) else {
+        return;
+    };
+    info!("switching to the {preset:?} preset");
+
+    for entity in entities.iter() {
+        commands.entity(entity).despawn();
+    }
+
+    // As when restoring a snapshot, boid_count_system mustn't see this while the old boids
+    // are still around, so the new ones are spawned here
+    let settings = settings.bypass_change_detection();
+    preset.apply(settings);
+
+    let half = **bounds / 2.0;
+    for _ in 0..settings.count {
+        let position = Vec2::new(rng.random_range(-half.x..half.x),
+                                 rng.random_range(-half.y..half.y));
+        let spawn = BoidSpawn::random(&mut **rng, settings, position);
+        spawn_boid(&mut commands, settings, spawn);
+    }
+    for _ in 0..settings.predator_count {
+        spawn_predator(&mut commands, &mut rng, settings, **bounds);
+    }
+
+    lay_out(&mut commands, &mut waypoints, preset, **bounds);
+}
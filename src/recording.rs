@@ -0,0 +1,298 @@
+use std::{
+    error::Error,
+    f32::consts::PI,
+    fs,
+    path::{Path, PathBuf},
+};
+use bevy::{math::Vec3Swizzles, prelude::*, utils::{HashMap, HashSet}, window::PrimaryWindow};
+
+use crate::{input::{Action, Actions}, simulation::simulation_running, AnyBoid, BoidAssets, BoidColor, BoidSet, MainCamera};
+
+const RECORDING_PATH: &str = "boids_recording.bin";
+const MAGIC: &[u8; 8] = b"BOIDREC1";
+// Positions are stored in quarter units, so recordings cover ±8192 units around the origin
+const POSITION_STEPS: f32 = 4.;
+const SCALE_STEPS: f32 = 16.;
+// Scrubbing with the arrow keys covers this many seconds of recording per second held
+const SCRUB_RATE: f32 = 4.;
+// Height of the timeline along the bottom of the window, in pixels
+const TIMELINE_HEIGHT: f32 = 24.;
+
+// One boid in a recorded tick
+#[derive(Clone, Copy, Debug)]
+pub struct RecordedBoid {
+    pub id: u64,
+    pub position: Vec2,
+    pub angle: f32,
+    pub scale: f32,
+    pub color: [u8; 4],
+}
+
+// 19 bytes a boid: id, quantized position, angle and scale, and sRGB color
+impl RecordedBoid {
+    fn write(&self, bytes: &mut Vec<u8>) {
+        let position = (self.position * POSITION_STEPS).round();
+        bytes.extend(self.id.to_le_bytes());
+        bytes.extend((position.x as i16).to_le_bytes());
+        bytes.extend((position.y as i16).to_le_bytes());
+        bytes.extend(((self.angle / PI * i16::MAX as f32) as i16).to_le_bytes());
+        bytes.push((self.scale * SCALE_STEPS).round().clamp(0.0, 255.0) as u8);
+        bytes.extend(self.color);
+    }
+
+    fn read(bytes: &mut &[u8]) -> Option<Self> {
+        let id = u64::from_le_bytes(take(bytes)?);
+        let x = i16::from_le_bytes(take(bytes)?);
+        let y = i16::from_le_bytes(take(bytes)?);
+        let angle = i16::from_le_bytes(take(bytes)?);
+        let [scale] = take(bytes)?;
+        let color = take(bytes)?;
+
+        Some(Self {
+            id,
+            position: Vec2::new(x as f32, y as f32) / POSITION_STEPS,
+            angle: angle as f32 / i16::MAX as f32 * PI,
+            scale: scale as f32 / SCALE_STEPS,
+            color,
+        })
+    }
+}
+
+fn take<const N: usize>(bytes: &mut &[u8]) -> Option<[u8; N]> {
+    let (head, rest) = bytes.split_at_checked(N)?;
+    *bytes = rest;
+    head.try_into().ok()
+}
+
+// Every boid on every tick of a run, at the fixed timestep it was simulated with
+#[derive(Clone, Debug, Default)]
+pub struct Recording {
+    pub timestep: f32,
+    pub ticks: Vec<Vec<RecordedBoid>>,
+}
+
+impl Recording {
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        let mut bytes = MAGIC.to_vec();
+        bytes.extend(self.timestep.to_le_bytes());
+
+        for tick in &self.ticks {
+            bytes.extend((tick.len() as u32).to_le_bytes());
+            for boid in tick {
+                boid.write(&mut bytes);
+            }
+        }
+
+        fs::write(path, bytes)?;
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        let file = fs::read(path)?;
+        let mut bytes = file.strip_prefix(MAGIC.as_slice()).ok_or("not a boid recording")?;
+        let timestep = f32::from_le_bytes(take(&mut bytes).ok_or("missing timestep")?);
+
+        let mut ticks = vec![];
+        while !bytes.is_empty() {
+            let count = u32::from_le_bytes(take(&mut bytes).ok_or("truncated tick")?);
+            let tick = (0..count)
+                .map(|_| RecordedBoid::read(&mut bytes))
+                .collect::<Option<Vec<_>>>()
+                .ok_or("truncated tick")?;
+            ticks.push(tick);
+        }
+
+        Ok(Self { timestep, ticks })
+    }
+}
+
+#[derive(Resource, Default)]
+struct Recorder(Option<Recording>);
+
+// `R` starts recording every simulated tick, and pressing it again saves them to `boids_recording.bin`
+pub struct RecorderPlugin;
+
+impl Plugin for RecorderPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Recorder>()
+            .add_systems(Update, toggle_recording)
+            .add_systems(FixedUpdate, record_tick.after(BoidSet::Movement).run_if(simulation_running));
+    }
+}
+
+fn toggle_recording(actions: Actions, mut recorder: ResMut<Recorder>, time: Res<Time<Fixed>>) {
+    if !actions.just_pressed(Action::Record) {
+        return;
+    }
+
+    match recorder.0.take() {
+        None => {
+            recorder.0 = Some(Recording { timestep: time.timestep().as_secs_f32(), ticks: vec![] });
+            info!("recording to {RECORDING_PATH}, press R again to stop");
+        }
+        Some(recording) => match recording.save(RECORDING_PATH) {
+            Ok(()) => info!("saved {} ticks to {RECORDING_PATH}", recording.ticks.len()),
+            Err(err) => error!("failed to save recording to {RECORDING_PATH}: {err}"),
+        },
+    }
+}
+
+fn record_tick(mut recorder: ResMut<Recorder>, boids: Query<(Entity, &Transform, &BoidColor), AnyBoid>) {
+    let Some(recording) = recorder.0.as_mut() else {
+        return;
+    };
+
+    let tick = boids.iter()
+        .map(|(boid, transform, color)| RecordedBoid {
+            id: boid.to_bits(),
+            position: transform.translation.xy(),
+            angle: transform.rotation.to_euler(EulerRot::ZYX).0,
+            scale: transform.scale.x,
+            color: color.0.as_rgba_u8(),
+        })
+        .collect();
+    recording.ticks.push(tick);
+}
+
+#[derive(Resource)]
+struct Playback {
+    recording: Recording,
+    // Position in the recording, in ticks
+    tick: f32,
+    paused: bool,
+    // Recorded ids to the entity showing that boid
+    shown: HashMap<u64, Entity>,
+}
+
+impl Playback {
+    fn last_tick(&self) -> f32 {
+        self.recording.ticks.len().saturating_sub(1) as f32
+    }
+}
+
+// Plays a recording back in place of the simulation: `Space` pauses, the arrow keys scrub
+// (`Shift` for a single tick), `Home` / `End` jump to either end and clicking or dragging
+// along the timeline seeks
+pub struct ReplayPlugin {
+    pub path: PathBuf,
+}
+
+impl Plugin for ReplayPlugin {
+    fn build(&self, app: &mut App) {
+        let recording = match Recording::load(&self.path) {
+            Ok(recording) => recording,
+            Err(err) => {
+                error!("failed to load recording from {}: {err}", self.path.display());
+                return;
+            }
+        };
+
+        info!("replaying {} ticks from {}", recording.ticks.len(), self.path.display());
+        app.insert_resource(Playback { recording, tick: 0.0, paused: false, shown: HashMap::new() })
+            .init_resource::<BoidAssets>()
+            .add_systems(Startup, crate::spawn_camera)
+            .add_systems(Update, (playback_input, show_tick, draw_timeline).chain())
+            .add_systems(PostUpdate, crate::attach_boid_meshes);
+    }
+}
+
+fn playback_input(
+    mut playback: ResMut<Playback>,
+    actions: Actions,
+    window: Query<&Window, With<PrimaryWindow>>,
+    time: Res<Time>,
+) {
+    let last = playback.last_tick();
+    let ticks_per_second = 1.0 / playback.recording.timestep.max(f32::EPSILON);
+
+    if actions.just_pressed(Action::Pause) {
+        playback.paused = !playback.paused;
+    }
+
+    let shift = actions.pressed(Action::Alternate);
+    let direction = actions.pressed(Action::ReplayForward) as i32 - actions.pressed(Action::ReplayBack) as i32;
+    if shift && direction != 0 {
+        let step = actions.just_pressed(Action::ReplayForward) as i32 - actions.just_pressed(Action::ReplayBack) as i32;
+        playback.tick = playback.tick.floor() + step as f32;
+    } else if direction != 0 {
+        playback.tick += direction as f32 * SCRUB_RATE * ticks_per_second * time.delta_seconds();
+    } else if !playback.paused {
+        playback.tick += ticks_per_second * time.delta_seconds();
+    }
+
+    if actions.just_pressed(Action::ReplayStart) {
+        playback.tick = 0.0;
+    }
+    if actions.just_pressed(Action::ReplayEnd) {
+        playback.tick = last;
+    }
+
+    if let Ok(window) = window.get_single() {
+        let on_timeline = window.cursor_position().filter(|cursor| cursor.y >= window.height() - TIMELINE_HEIGHT);
+        if let Some(cursor) = on_timeline.filter(|_| actions.pressed(Action::Select)) {
+            playback.tick = cursor.x / window.width() * last;
+        }
+    }
+
+    // Holds on the final tick rather than looping, so the end result can be studied
+    playback.tick = playback.tick.clamp(0.0, last);
+}
+
+fn show_tick(
+    mut commands: Commands,
+    mut playback: ResMut<Playback>,
+    mut transforms: Query<(&mut Transform, &mut Visibility)>,
+) {
+    let playback = &mut *playback;
+    let Some(tick) = playback.recording.ticks.get(playback.tick as usize) else {
+        return;
+    };
+
+    let mut visible = HashSet::with_capacity(tick.len());
+    for boid in tick {
+        let transform = Transform::from_translation(boid.position.extend(0.0))
+            .with_rotation(Quat::from_rotation_z(boid.angle))
+            .with_scale(Vec3::splat(boid.scale));
+
+        match playback.shown.get(&boid.id).and_then(|&shown| transforms.get_mut(shown).ok()) {
+            Some((mut t, _)) => *t = transform,
+            None => {
+                let [r, g, b, a] = boid.color;
+                let shown = commands.spawn((
+                    SpatialBundle::from_transform(transform),
+                    BoidColor(Color::rgba_u8(r, g, b, a)),
+                )).id();
+                playback.shown.insert(boid.id, shown);
+            }
+        }
+        visible.insert(boid.id);
+    }
+
+    // Boids that hadn't spawned yet or were already gone at this tick are hidden, kept for scrubbing back
+    for (id, shown) in &playback.shown {
+        if let Ok((_, mut visibility)) = transforms.get_mut(*shown) {
+            *visibility = if visible.contains(id) { Visibility::Inherited } else { Visibility::Hidden };
+        }
+    }
+}
+
+fn draw_timeline(
+    playback: Res<Playback>,
+    camera: Query<(&Camera, &GlobalTransform), With<MainCamera>>,
+    window: Query<&Window, With<PrimaryWindow>>,
+    mut gizmos: Gizmos,
+) {
+    let (Ok((camera, t_camera)), Ok(window)) = (camera.get_single(), window.get_single()) else {
+        return;
+    };
+
+    let y = window.height() - TIMELINE_HEIGHT / 2.0;
+    let at = |x: f32| camera.viewport_to_world_2d(t_camera, Vec2::new(x, y));
+    let progress = playback.tick / playback.last_tick().max(1.0);
+
+    if let (Some(start), Some(end), Some(head)) = (at(0.0), at(window.width()), at(progress * window.width())) {
+        gizmos.line_2d(start, end, Color::GRAY);
+        gizmos.line_2d(start, head, Color::WHITE);
+        gizmos.circle_2d(head, 6.0, if playback.paused { Color::YELLOW } else { Color::WHITE });
+    }
+}
@@ -0,0 +1,236 @@
+use std::collections::VecDeque;
+use bevy::{math::Vec3Swizzles, prelude::*, utils::{HashMap, HashSet}};
+
+use crate::{
+    input::{Action, Actions},
+    player::Player,
+    settings::BoidSettings,
+    simulation::{simulation_running, SimulationState},
+    snapshot::BoidState,
+    species::Species,
+    variation::{BoidTraits, Mass},
+    spawn_boid,
+    sync_boid_count,
+    AnyBoid,
+    BoidColor,
+    BoidSet,
+    BoidSpawn,
+    Velocity,
+    Wander,
+};
+
+// Where every boid was and how it flew on one tick
+struct RewindTick {
+    tick: u64,
+    boids: Vec<(Entity, Transform, Vec2)>,
+}
+
+// What it takes to bring back a boid that died within the window, as of the last tick it was
+// recorded alive
+struct Identity {
+    boid: BoidState,
+    last_seen: u64,
+}
+
+// The flock as it was on each of the last `rewind_seconds` worth of ticks, newest at the back
+#[derive(Resource, Default)]
+pub struct RewindBuffer {
+    ticks: VecDeque<RewindTick>,
+    identities: HashMap<Entity, Identity>,
+    // Boids brought back while rewinding, from the entity they were recorded as to the new one
+    revived: HashMap<Entity, Entity>,
+    next_tick: u64,
+}
+
+impl RewindBuffer {
+    fn push(&mut self, boids: Vec<(Entity, Transform, Vec2)>, capacity: usize) {
+        while self.ticks.len() >= capacity.max(1) {
+            self.ticks.pop_front();
+        }
+        self.ticks.push_back(RewindTick { tick: self.next_tick, boids });
+        self.next_tick += 1;
+
+        // Boids last seen before the oldest tick kept can't be rewound to any more
+        let oldest = self.ticks.front().map_or(0, |tick| tick.tick);
+        self.identities.retain(|_, identity| identity.last_seen >= oldest);
+        let identities = &self.identities;
+        self.revived.retain(|recorded, _| identities.contains_key(recorded));
+    }
+
+    // Drops the newest tick, leaving the one before it to go back to
+    fn step_back(&mut self) -> Option<&RewindTick> {
+        if self.ticks.len() < 2 {
+            return None;
+        }
+        self.ticks.pop_back();
+        self.ticks.back()
+    }
+
+    // The entity a recorded boid lives on now, following it through however many revivals
+    fn resolve(&self, mut boid: Entity) -> Entity {
+        while let Some(revived) = self.revived.get(&boid) {
+            boid = *revived;
+        }
+        boid
+    }
+}
+
+// Holding `,` pauses and rewinds the flock a tick per frame, through the last `rewind_seconds`.
+// Boids are moved back in place, keeping everything else about them; only those born or dead
+// since are despawned or brought back. Resuming or stepping from there carries on from that
+// point, the rewound ticks forgotten
+pub struct RewindPlugin;
+
+impl Plugin for RewindPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RewindBuffer>()
+            .add_systems(FixedUpdate, record_tick.after(BoidSet::Movement).run_if(simulation_running))
+            .add_systems(Update, rewind.run_if(|actions: Actions| actions.pressed(Action::Rewind)));
+    }
+}
+
+type RecordedQuery = (
+    Entity,
+    &'static Transform,
+    &'static Velocity,
+    &'static BoidColor,
+    &'static Species,
+    &'static Wander,
+    Option<&'static BoidTraits>,
+    Option<&'static Mass>,
+);
+
+fn record_tick(
+    mut buffer: ResMut<RewindBuffer>,
+    boids: Query<RecordedQuery, AnyBoid>,
+    settings: Res<BoidSettings>,
+    time: Res<Time>,
+) {
+    if settings.rewind_seconds <= 0.0 {
+        *buffer = RewindBuffer::default();
+        return;
+    }
+
+    let tick = buffer.next_tick;
+    for (boid, transform, velocity, color, species, wander, traits, mass) in boids.iter() {
+        buffer.identities.entry(boid)
+            .or_insert_with(|| Identity {
+                boid: BoidState {
+                    position: transform.translation.xy().to_array(),
+                    velocity: velocity.0.to_array(),
+                    color: color.0.as_linear_rgba_f32(),
+                    species: *species,
+                    wander_phase: wander.0,
+                    traits: traits.copied(),
+                    mass: mass.copied(),
+                },
+                last_seen: tick,
+            })
+            .last_seen = tick;
+    }
+
+    let capacity = (settings.rewind_seconds / time.delta_seconds().max(f32::EPSILON)).ceil() as usize;
+    let recorded = boids.iter().map(|(boid, transform, velocity, ..)| (boid, *transform, velocity.0)).collect();
+    buffer.push(recorded, capacity);
+}
+
+fn rewind(
+    mut commands: Commands,
+    mut buffer: ResMut<RewindBuffer>,
+    mut state: ResMut<SimulationState>,
+    mut settings: ResMut<BoidSettings>,
+    mut boids: Query<(Entity, &mut Transform, &mut Velocity), AnyBoid>,
+    players: Query<(), With<Player>>,
+) {
+    if !state.paused {
+        state.paused = true;
+    }
+    if buffer.step_back().is_none() {
+        return;
+    }
+
+    let buffer = &mut *buffer;
+    let Some(earlier) = buffer.ticks.back() else {
+        return;
+    };
+    let mut count = boids.iter().len();
+    let mut present = HashSet::new();
+    let mut revived = vec![];
+
+    for (recorded, transform, velocity) in &earlier.boids {
+        let boid = buffer.resolve(*recorded);
+        if let Ok((_, mut current, mut current_velocity)) = boids.get_mut(boid) {
+            *current = *transform;
+            current_velocity.0 = *velocity;
+            present.insert(boid);
+            continue;
+        }
+
+        // Died since, so back it comes as it was
+        let Some(identity) = buffer.identities.get(recorded) else {
+            continue;
+        };
+        let spawn = BoidSpawn {
+            position: transform.translation.xy(),
+            velocity: *velocity,
+            species: identity.boid.species,
+            color: Color::rgba_linear_from_array(identity.boid.color),
+            wander_phase: identity.boid.wander_phase,
+            traits: identity.boid.traits,
+            mass: identity.boid.mass,
+        };
+        let boid = spawn_boid(&mut commands, &settings, spawn);
+        revived.push((*recorded, boid));
+        present.insert(boid);
+        count += 1;
+    }
+    buffer.revived.extend(revived);
+
+    // Born since, bar the player, which stays whenever it joined
+    for (boid, ..) in boids.iter() {
+        if !present.contains(&boid) && !players.contains(boid) {
+            commands.entity(boid).despawn();
+            count -= 1;
+        }
+    }
+
+    sync_boid_count(&mut settings, count);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tick(boids: &[(u32, f32)]) -> Vec<(Entity, Transform, Vec2)> {
+        boids.iter()
+            .map(|&(boid, x)| (Entity::from_raw(boid), Transform::from_xyz(x, 0.0, 0.0), Vec2::X))
+            .collect()
+    }
+
+    #[test]
+    fn rewinds_through_the_last_ticks_only() {
+        let mut buffer = RewindBuffer::default();
+        for x in 0..5 {
+            buffer.push(tick(&[(1, x as f32)]), 3);
+        }
+
+        let x = |buffer: &mut RewindBuffer| buffer.step_back().map(|tick| tick.boids[0].1.translation.x);
+        assert_eq!(x(&mut buffer), Some(3.0));
+        assert_eq!(x(&mut buffer), Some(2.0));
+        assert!(buffer.step_back().is_none(), "the oldest kept tick stays");
+
+        // Carrying on from there
+        buffer.push(tick(&[(1, 9.0)]), 3);
+        assert_eq!(x(&mut buffer), Some(2.0));
+    }
+
+    #[test]
+    fn revived_boids_are_followed_to_their_new_entity() {
+        let mut buffer = RewindBuffer::default();
+        let (a, b, c) = (Entity::from_raw(1), Entity::from_raw(2), Entity::from_raw(3));
+        buffer.revived.extend([(a, b), (b, c)]);
+
+        assert_eq!(buffer.resolve(a), c);
+        assert_eq!(buffer.resolve(c), c);
+    }
+}
@@ -0,0 +1,42 @@
+use bevy::prelude::*;
+use rand::{rngs::StdRng, SeedableRng};
+
+use crate::cli::Cli;
+
+// The single source of randomness for the simulation, so a seed reproduces a run
+#[derive(Resource, Deref, DerefMut)]
+pub struct SimRng {
+    pub seed: u64,
+    #[deref]
+    rng: StdRng,
+}
+
+impl SimRng {
+    pub fn from_seed(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    // `--seed <n>` from the command line, then `BOIDS_SEED`, otherwise a random seed
+    pub fn from_env(seed: Option<u64>) -> Self {
+        let seed = seed
+            .or_else(|| std::env::var("BOIDS_SEED").ok().and_then(|seed| seed.parse().ok()))
+            .unwrap_or_else(rand::random);
+
+        Self::from_seed(seed)
+    }
+}
+
+pub struct RngPlugin;
+
+impl Plugin for RngPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.world.contains_resource::<SimRng>() {
+            let seed = app.world.get_resource::<Cli>().and_then(|cli| cli.seed);
+            app.insert_resource(SimRng::from_env(seed));
+        }
+        app.add_systems(Startup, |rng: Res<SimRng>| info!("simulation seed: {}", rng.seed));
+    }
+}
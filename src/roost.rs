@@ -0,0 +1,183 @@
+use rand::prelude::*;
+use bevy::{math::Vec3Swizzles, prelude::*};
+
+use crate::{
+    daynight::DayNight,
+    energy::Energy,
+    rng::SimRng,
+    settings::BoidSettings,
+    Acceleration,
+    BoidSet,
+    NeighborCache,
+    Player,
+    SpatialEntity,
+    Velocity,
+    WorldBounds,
+};
+
+// Boids this close above a roost land on it
+const LANDING_RANGE: f32 = 6.;
+
+// A horizontal perch `half_length` either side of its transform
+#[derive(Component, Clone, Copy, Debug, Reflect)]
+#[reflect(Component)]
+pub struct Roost {
+    pub half_length: f32,
+}
+
+impl Roost {
+    fn nearest(&self, center: Vec2, position: Vec2) -> Vec2 {
+        Vec2::new(position.x.clamp(center.x - self.half_length, center.x + self.half_length), center.y)
+    }
+}
+
+// Headed for the nearest roost
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
+pub struct Landing;
+
+// Seconds left sitting on a roost, out of the index and the flock
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
+pub struct Perched(pub f32);
+
+// Every so often a boid descends to a roost, sits out a while, then takes off again
+pub struct RoostPlugin;
+
+impl Plugin for RoostPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<Roost>()
+            .register_type::<Landing>()
+            .register_type::<Perched>()
+            .add_systems(Startup, spawn_roosts)
+            .add_systems(FixedUpdate, (
+                // After flocking, so the descent wins over the flock's pull
+                (pick_landers, steer_landers).chain().in_set(BoidSet::Steer).after(crate::flocking_system),
+                (land_system, take_off_system).chain().in_set(BoidSet::Movement).after(crate::boundary_system),
+            ));
+    }
+}
+
+// Along the bottom of the steering boundary
+fn spawn_roosts(mut commands: Commands, bounds: Res<WorldBounds>, settings: Res<BoidSettings>) {
+    let bottom = -(bounds.y - settings.boundary_size) / 2.0;
+
+    commands.spawn((
+        Roost { half_length: 150. },
+        TransformBundle::from_transform(Transform::from_xyz(0., bottom, 0.)),
+    ));
+}
+
+type Flying = (With<SpatialEntity>, Without<Landing>, Without<Player>);
+
+fn pick_landers(
+    mut commands: Commands,
+    boids: Query<Entity, Flying>,
+    roosts: Query<(), With<Roost>>,
+    mut rng: ResMut<SimRng>,
+    time: Res<Time>,
+    settings: Res<BoidSettings>,
+    day_night: Res<DayNight>,
+) {
+    if roosts.is_empty() {
+        return;
+    }
+    let settings = day_night.settings(&settings);
+
+    let chance = (settings.perch_chance * time.delta_seconds()) as f64;
+    for boid in boids.iter() {
+        if rng.random_bool(chance.clamp(0.0, 1.0)) {
+            commands.entity(boid).insert(Landing);
+        }
+    }
+}
+
+fn steer_landers(
+    mut landers: Query<(&Transform, &mut Acceleration), With<Landing>>,
+    roosts: Query<(&Roost, &Transform)>,
+    settings: Res<BoidSettings>,
+) {
+    for (transform, mut acceleration) in landers.iter_mut() {
+        let position = transform.translation.xy();
+        let target = roosts.iter()
+            .map(|(roost, t_roost)| roost.nearest(t_roost.translation.xy(), position))
+            .min_by(|a, b| a.distance_squared(position).total_cmp(&b.distance_squared(position)));
+
+        if let Some(target) = target {
+            acceleration.0 += (target - position).normalize_or_zero() * settings.perch_seek_factor;
+        }
+    }
+}
+
+fn land_system(
+    mut commands: Commands,
+    mut landers: Query<(Entity, &mut Transform, &mut Velocity), With<Landing>>,
+    roosts: Query<(&Roost, &Transform), Without<Landing>>,
+    mut rng: ResMut<SimRng>,
+    settings: Res<BoidSettings>,
+    day_night: Res<DayNight>,
+) {
+    let settings = day_night.settings(&settings);
+    for (boid, mut transform, mut velocity) in landers.iter_mut() {
+        let position = transform.translation.xy();
+        let perch = roosts.iter()
+            .map(|(roost, t_roost)| roost.nearest(t_roost.translation.xy(), position))
+            .find(|perch| perch.distance(position) <= LANDING_RANGE);
+
+        let Some(perch) = perch else {
+            continue;
+        };
+
+        transform.translation.x = perch.x;
+        transform.translation.y = perch.y;
+        transform.rotation = Quat::IDENTITY;
+        velocity.0 = Vec2::ZERO;
+
+        let stay = rng.random_range(settings.perch_min..=settings.perch_max.max(settings.perch_min));
+        commands.entity(boid)
+            .remove::<(Landing, SpatialEntity)>()
+            .insert(Perched(stay));
+    }
+}
+
+type PerchedQuery = (
+    Entity,
+    &'static mut Perched,
+    &'static mut Velocity,
+    &'static mut NeighborCache,
+    Option<&'static mut Energy>,
+);
+
+fn take_off_system(
+    mut commands: Commands,
+    mut perched: Query<PerchedQuery>,
+    mut rng: ResMut<SimRng>,
+    time: Res<Time>,
+    settings: Res<BoidSettings>,
+) {
+    let dt = time.delta_seconds();
+
+    for (boid, mut perch, mut velocity, mut cache, energy) in perched.iter_mut() {
+        if let Some(mut energy) = energy {
+            energy.0 = (energy.0 + settings.energy_recovery * dt).min(1.0);
+        }
+
+        perch.0 -= dt;
+        if perch.0 > 0.0 {
+            continue;
+        }
+
+        // Up and away at an angle, back into the index on its next rebuild
+        velocity.0 = Vec2::new(rng.random_range(-1.0..1.0), 1.0).normalize() * settings.min_speed;
+        *cache = NeighborCache::default();
+        commands.entity(boid).remove::<Perched>().insert(SpatialEntity);
+    }
+}
+
+pub fn draw_roosts(roosts: Query<(&Roost, &Transform)>, mut gizmos: Gizmos) {
+    for (roost, transform) in roosts.iter() {
+        let center = transform.translation.xy();
+        let along = Vec2::X * roost.half_length;
+        gizmos.line_2d(center - along, center + along, Color::rgb(0.55, 0.4, 0.25));
+    }
+}
@@ -0,0 +1,203 @@
+use std::{
+    error::Error,
+    sync::atomic::{AtomicBool, Ordering},
+};
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+    utils::BoxedFuture,
+};
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+
+use crate::steering::{SteeringBehavior, SteeringContext};
+
+const SCRIPT_PATH: &str = "steering.rhai";
+// Bounds the work a runaway script can do per boid
+const MAX_OPERATIONS: u64 = 10_000;
+
+#[derive(Asset, TypePath, Debug)]
+pub struct SteeringScript(String);
+
+#[derive(Default)]
+struct SteeringScriptLoader;
+
+impl AssetLoader for SteeringScriptLoader {
+    type Asset = SteeringScript;
+    type Settings = ();
+    type Error = Box<dyn Error + Send + Sync>;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<SteeringScript, Self::Error>> {
+        Box::pin(async move {
+            let mut source = String::new();
+            reader.read_to_string(&mut source).await?;
+            Ok(SteeringScript(source))
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["rhai"]
+    }
+}
+
+// What a script sees of one boid and the neighbors it flocks with
+struct ScriptInput {
+    position: Vec2,
+    velocity: Vec2,
+    neighbors: usize,
+    // Average offset to and velocity of those neighbors
+    center: Vec2,
+    average_velocity: Vec2,
+    time: f32,
+}
+
+// The compiled `steer(boid)` function, if the script loaded and compiled
+#[derive(Resource)]
+pub struct ScriptedSteering {
+    engine: Engine,
+    ast: Option<AST>,
+    // Only the first failure after each reload is logged, not one per boid per tick
+    failed: AtomicBool,
+}
+
+impl ScriptedSteering {
+    // Extra acceleration for the boid, zero without a script or when it fails
+    fn steer(&self, input: &ScriptInput) -> Vec2 {
+        let Some(ast) = &self.ast else {
+            return Vec2::ZERO;
+        };
+
+        let mut boid = Map::new();
+        for (key, value) in [
+            ("x", input.position.x),
+            ("y", input.position.y),
+            ("vx", input.velocity.x),
+            ("vy", input.velocity.y),
+            ("cx", input.center.x),
+            ("cy", input.center.y),
+            ("avx", input.average_velocity.x),
+            ("avy", input.average_velocity.y),
+            ("time", input.time),
+        ] {
+            boid.insert(key.into(), value.into());
+        }
+        boid.insert("neighbors".into(), (input.neighbors as rhai::INT).into());
+
+        let result = self.engine
+            .call_fn::<rhai::Array>(&mut Scope::new(), ast, "steer", (boid,))
+            .map_err(|err| err.to_string())
+            .and_then(|steering| match steering.as_slice() {
+                [x, y] => Ok(Vec2::new(number(x)?, number(y)?)),
+                _ => Err(format!("steer returned {} values, expected [x, y]", steering.len())),
+            });
+
+        result.unwrap_or_else(|err| {
+            if !self.failed.swap(true, Ordering::Relaxed) {
+                error!("{SCRIPT_PATH}: {err}");
+            }
+            Vec2::ZERO
+        })
+    }
+}
+
+// Scripts may return integers as well as floats
+fn number(value: &Dynamic) -> Result<f32, String> {
+    value.as_float()
+        .or_else(|_| value.as_int().map(|int| int as f32))
+        .map_err(|kind| format!("steer returned a {kind}, expected a number"))
+}
+
+impl Default for ScriptedSteering {
+    fn default() -> Self {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+
+        Self {
+            engine,
+            ast: None,
+            failed: AtomicBool::new(false),
+        }
+    }
+}
+
+// The script's `steer` as one of the flock's steering behaviors
+pub struct Script;
+
+impl SteeringBehavior for Script {
+    fn name(&self) -> &'static str {
+        "script"
+    }
+
+    fn steer(&self, ctx: &SteeringContext) -> Vec2 {
+        let Some(script) = ctx.params.script.as_deref().filter(|script| script.ast.is_some()) else {
+            return Vec2::ZERO;
+        };
+
+        let count = ctx.neighbors.len();
+        let average = |total: Vec2| if count == 0 { Vec2::ZERO } else { total / count as f32 };
+
+        script.steer(&ScriptInput {
+            position: ctx.position,
+            velocity: ctx.velocity,
+            neighbors: count,
+            center: average(ctx.neighbors.iter().map(|neighbor| neighbor.offset).sum()),
+            average_velocity: average(ctx.neighbors.iter().map(|neighbor| neighbor.velocity).sum()),
+            time: ctx.params.time.elapsed_seconds(),
+        })
+    }
+}
+
+#[derive(Resource)]
+struct SteeringScriptHandle(Handle<SteeringScript>);
+
+// Adds the steering from `assets/steering.rhai` to every boid, recompiling it
+// whenever it changes on disk when built with the `hot_reload` feature
+pub struct ScriptingPlugin;
+
+impl Plugin for ScriptingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<SteeringScript>()
+            .init_asset_loader::<SteeringScriptLoader>()
+            .init_resource::<ScriptedSteering>()
+            .add_systems(PreStartup, load_script)
+            .add_systems(Update, compile_script);
+    }
+}
+
+fn load_script(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(SteeringScriptHandle(asset_server.load(SCRIPT_PATH)));
+}
+
+fn compile_script(
+    mut events: EventReader<AssetEvent<SteeringScript>>,
+    handle: Res<SteeringScriptHandle>,
+    scripts: Res<Assets<SteeringScript>>,
+    mut steering: ResMut<ScriptedSteering>,
+) {
+    for event in events.read() {
+        if !event.is_loaded_with_dependencies(&handle.0) && !event.is_modified(&handle.0) {
+            continue;
+        }
+        let Some(script) = scripts.get(&handle.0) else {
+            continue;
+        };
+
+        // A script that doesn't compile leaves boids unscripted until it's fixed
+        let steering = &mut *steering;
+        steering.failed.store(false, Ordering::Relaxed);
+        steering.ast = match steering.engine.compile(&script.0) {
+            Ok(ast) => {
+                info!("compiled {SCRIPT_PATH}");
+                Some(ast)
+            }
+            Err(err) => {
+                error!("{SCRIPT_PATH}: {err}");
+                None
+            }
+        };
+    }
+}
@@ -0,0 +1,74 @@
+use bevy::{ecs::system::SystemParam, math::Vec3Swizzles, prelude::*};
+
+use crate::{BoidSet, CursorPosition, CursorRepel, Species};
+
+// The entity a boid steers after when there's no waypoint route: anything with a transform,
+// like a scripted marker, the player boid or a lure. Boids start out after the cursor
+#[derive(Component, Clone, Copy, Debug, Reflect)]
+#[reflect(Component)]
+pub struct SeekTarget(pub Entity);
+
+// Stands in for the cursor as a target, only there to seek while the cursor is over the world
+// and drawing boids in
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
+pub struct CursorTarget {
+    pub present: bool,
+}
+
+// Read access to where each boid's target is, for the Seek behavior
+#[derive(SystemParam)]
+pub struct SeekTargets<'w, 's> {
+    seekers: Query<'w, 's, &'static SeekTarget>,
+    targets: Query<'w, 's, (&'static Transform, Option<&'static CursorTarget>)>,
+}
+
+impl SeekTargets<'_, '_> {
+    pub fn position(&self, boid: Entity) -> Option<Vec2> {
+        let target = self.seekers.get(boid).ok()?;
+        let (transform, cursor) = self.targets.get(target.0).ok()?;
+        cursor.is_none_or(|cursor| cursor.present).then(|| transform.translation.xy())
+    }
+}
+
+pub struct SeekPlugin;
+
+impl Plugin for SeekPlugin {
+    fn build(&self, app: &mut App) {
+        app.register_type::<SeekTarget>()
+            .register_type::<CursorTarget>()
+            .add_systems(Startup, spawn_cursor_target)
+            .add_systems(FixedUpdate, (track_cursor_target, target_cursor).in_set(BoidSet::Index));
+    }
+}
+
+fn spawn_cursor_target(mut commands: Commands) {
+    commands.spawn((CursorTarget::default(), TransformBundle::default()));
+}
+
+fn track_cursor_target(
+    cursor: Res<CursorPosition>,
+    repel: Res<CursorRepel>,
+    mut target: Query<(&mut CursorTarget, &mut Transform)>,
+) {
+    for (mut target, mut transform) in target.iter_mut() {
+        target.present = cursor.0.is_some() && !repel.0;
+        if let Some(position) = cursor.0 {
+            transform.translation = position.extend(0.0);
+        }
+    }
+}
+
+// New boids go after the cursor, until something sends them elsewhere
+fn target_cursor(
+    mut commands: Commands,
+    boids: Query<Entity, (Added<Species>, Without<SeekTarget>)>,
+    cursor: Query<Entity, With<CursorTarget>>,
+) {
+    let Ok(cursor) = cursor.get_single() else {
+        return;
+    };
+    for boid in boids.iter() {
+        commands.entity(boid).insert(SeekTarget(cursor));
+    }
+}
@@ -0,0 +1,169 @@
+use bevy::{math::Vec3Swizzles, prelude::*};
+use bevy_spatial::SpatialAccess;
+
+use crate::{
+    behavior::BoidState,
+    grab::{Grab, Held},
+    index::BoidIndex,
+    flow::PaintingWind,
+    input::{Action, Actions},
+    settings::BoidSettings,
+    CursorPosition,
+    SpatialEntity,
+    SteeringTerms,
+    Velocity,
+};
+
+// Clicks further than this from every boid clear the selection
+const PICK_RADIUS: f32 = 20.;
+
+// The boid under inspection, with what flocking_system last computed for it
+#[derive(Resource, Default)]
+pub struct Selection {
+    pub boid: Option<Entity>,
+    pub terms: SteeringTerms,
+    // Neighbors it perceived
+    pub neighbors: usize,
+}
+
+#[derive(Component)]
+struct InspectorPanel;
+
+// Left click selects the nearest boid and shows its steering in a panel; holding the button
+// drags it about, and letting go flings it
+pub struct SelectionPlugin;
+
+impl Plugin for SelectionPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Selection>()
+            .add_systems(Startup, spawn_panel)
+            .add_systems(Update, (select_boid, drag_held, update_panel, draw_selection).chain());
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+fn select_boid(
+    mut commands: Commands,
+    actions: Actions,
+    cursor: Res<CursorPosition>,
+    painting: Option<Res<PaintingWind>>,
+    index: Res<BoidIndex>,
+    boids: Query<(), With<SpatialEntity>>,
+    mut selection: ResMut<Selection>,
+    mut grab: ResMut<Grab>,
+) {
+    // Shift + click spawns, ctrl + click places waypoints, and painting wind owns the left button
+    let modified = actions.pressed(Action::Alternate) || actions.pressed(Action::Route);
+    let painting = painting.is_some_and(|painting| painting.0);
+
+    if !actions.just_pressed(Action::Select) || modified || painting {
+        return;
+    }
+    let Some(position) = cursor.0 else {
+        return;
+    };
+
+    // Skip boids despawned since the index's last rebuild
+    selection.boid = index.within_distance(position, PICK_RADIUS)
+        .into_iter()
+        .filter_map(|(point, entity)| Some((point, entity.filter(|e| boids.contains(*e))?)))
+        .min_by(|(a, _), (b, _)| a.distance_squared(position).total_cmp(&b.distance_squared(position)))
+        .map(|(_, entity)| entity);
+
+    if let Some(boid) = selection.boid {
+        commands.entity(boid).insert(Held);
+        grab.target = position;
+    }
+}
+
+fn drag_held(
+    mut commands: Commands,
+    actions: Actions,
+    cursor: Res<CursorPosition>,
+    held: Query<Entity, With<Held>>,
+    mut grab: ResMut<Grab>,
+) {
+    if actions.pressed(Action::Select) {
+        if let Some(position) = cursor.0 {
+            grab.target = position;
+        }
+        return;
+    }
+
+    // Caught or despawned while held doesn't matter, they're gone either way
+    for boid in held.iter() {
+        commands.entity(boid).remove::<Held>();
+    }
+}
+
+fn spawn_panel(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section("", TextStyle {
+            font_size: 14.0,
+            color: Color::WHITE,
+            ..default()
+        })
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(8.0),
+            right: Val::Px(8.0),
+            ..default()
+        }),
+        Visibility::Hidden,
+        InspectorPanel,
+    ));
+}
+
+fn update_panel(
+    mut selection: ResMut<Selection>,
+    boids: Query<(&Velocity, &BoidState), With<SpatialEntity>>,
+    mut panel: Query<(&mut Text, &mut Visibility), With<InspectorPanel>>,
+) {
+    let velocity = selection.boid.map(|boid| boids.get(boid));
+
+    // Caught or removed boids drop out of the selection
+    if let Some(Err(_)) = velocity {
+        selection.boid = None;
+    }
+
+    for (mut text, mut visibility) in panel.iter_mut() {
+        let Some(Ok((velocity, state))) = velocity else {
+            *visibility = Visibility::Hidden;
+            continue;
+        };
+
+        let mut readout = format!(
+            "{state:?}\nvelocity ({:.0}, {:.0})\nspeed {:.1}\nneighbors {}",
+            velocity.0.x, velocity.0.y, velocity.0.length(), selection.neighbors,
+        );
+        for (name, term) in selection.terms.named() {
+            if term != Vec2::ZERO {
+                readout += &format!("\n{name} {:.1}", term.length());
+            }
+        }
+
+        text.sections[0].value = readout;
+        *visibility = Visibility::Visible;
+    }
+}
+
+fn draw_selection(
+    selection: Res<Selection>,
+    boids: Query<&Transform, With<SpatialEntity>>,
+    settings: Res<BoidSettings>,
+    mut gizmos: Gizmos,
+) {
+    let Some(transform) = selection.boid.and_then(|boid| boids.get(boid).ok()) else {
+        return;
+    };
+
+    let position = transform.translation.xy();
+    gizmos.circle_2d(position, settings.size * 2.0, Color::ORANGE);
+
+    // Each active term as an arrow from the boid, scaled down to stay readable
+    for (_, term) in selection.terms.named() {
+        if term != Vec2::ZERO {
+            gizmos.arrow_2d(position, position + term * 0.1, Color::ORANGE.with_a(0.6));
+        }
+    }
+}
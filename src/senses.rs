@@ -0,0 +1,82 @@
+use std::f32::consts::TAU;
+use bevy::prelude::*;
+
+use crate::settings::BoidSettings;
+
+// Imperfect senses: what a boid makes of each neighbor, jittered by Gaussian noise with
+// `perception_position_noise` and `perception_velocity_noise` standard deviations, or nothing
+// at all with `perception_miss_chance`. Drawn from a hash of the seed, tick and pair of boids
+// rather than a shared generator, so steering in parallel stays reproducible
+pub fn sense(
+    settings: &BoidSettings,
+    seed: u64,
+    tick: u32,
+    boid: Entity,
+    other: Entity,
+    offset: Vec2,
+    velocity: Vec2,
+) -> Option<(Vec2, Vec2)> {
+    let (position_noise, velocity_noise) = (settings.perception_position_noise, settings.perception_velocity_noise);
+    let miss_chance = settings.perception_miss_chance;
+    if position_noise <= 0.0 && velocity_noise <= 0.0 && miss_chance <= 0.0 {
+        return Some((offset, velocity));
+    }
+
+    let mut draws = Draws(mix(seed ^ mix(boid.to_bits() ^ mix(other.to_bits() ^ mix(tick as u64)))));
+    if draws.uniform() < miss_chance {
+        return None;
+    }
+
+    Some((
+        offset + draws.gaussian() * position_noise.max(0.0),
+        velocity + draws.gaussian() * velocity_noise.max(0.0),
+    ))
+}
+
+// SplitMix64's finalizer
+fn mix(mut x: u64) -> u64 {
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58476d1ce4e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d049bb133111eb);
+    x ^ (x >> 31)
+}
+
+struct Draws(u64);
+
+impl Draws {
+    // In [0, 1)
+    fn uniform(&mut self) -> f32 {
+        self.0 = self.0.wrapping_add(0x9e3779b97f4a7c15);
+        (mix(self.0) >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    // A pair of standard normals, by Box-Muller
+    fn gaussian(&mut self) -> Vec2 {
+        let radius = (-2.0 * (1.0 - self.uniform()).ln()).sqrt();
+        Vec2::from_angle(TAU * self.uniform()) * radius
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn senses_are_noisy_but_reproducible() {
+        let (boid, other) = (Entity::from_raw(1), Entity::from_raw(2));
+        let (offset, velocity) = (Vec2::new(10., 0.), Vec2::new(0., 100.));
+
+        let sharp = BoidSettings::default();
+        assert_eq!(sense(&sharp, 7, 3, boid, other, offset, velocity), Some((offset, velocity)));
+
+        let noisy = BoidSettings { perception_position_noise: 5., perception_velocity_noise: 20., ..default() };
+        let seen = sense(&noisy, 7, 3, boid, other, offset, velocity);
+        assert_eq!(seen, sense(&noisy, 7, 3, boid, other, offset, velocity));
+        assert_ne!(seen, sense(&noisy, 7, 4, boid, other, offset, velocity));
+        assert_ne!(seen, Some((offset, velocity)));
+
+        // Roughly the chance set, over many ticks
+        let forgetful = BoidSettings { perception_miss_chance: 0.25, ..default() };
+        let missed = (0..4000).filter(|tick| sense(&forgetful, 7, *tick, boid, other, offset, velocity).is_none()).count();
+        assert!((800..1200).contains(&missed), "{missed}");
+    }
+}
@@ -0,0 +1,439 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// How boids treat the edges of the play area
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, Reflect)]
+pub enum BoundaryMode {
+    // Gradually turn back once inside the boundary margin
+    #[default]
+    Steer,
+    // Reflect off the boundary like a wall
+    Bounce,
+    // Leave through one edge and reappear at the opposite one
+    Wrap,
+    // Boids crossing the boundary are despawned and replaced at a random point inside
+    Kill,
+}
+
+// How boids are drawn
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, Reflect)]
+pub enum BoidRender {
+    // A triangle mesh per boid, with a material per color
+    #[default]
+    Mesh,
+    // The `assets/bird.png` sprite tinted by each boid's color, swap the file to reskin
+    Sprite,
+}
+
+// What a boid's color shows
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, Reflect)]
+pub enum ColorMode {
+    // Its species' hue, in one of a few shades
+    #[default]
+    Species,
+    // Its heading around the hue wheel
+    Heading,
+    // Its speed from cold blue at `min_speed` to hot red at `max_speed`
+    Speed,
+    // The sub-flock it's in, strays grey
+    Cluster,
+}
+
+impl ColorMode {
+    pub fn next(self) -> Self {
+        match self {
+            ColorMode::Species => ColorMode::Heading,
+            ColorMode::Heading => ColorMode::Speed,
+            ColorMode::Speed => ColorMode::Cluster,
+            ColorMode::Cluster => ColorMode::Species,
+        }
+    }
+}
+
+// How a flocking rule weighs a neighbor by its distance, as a fraction of the rule's range
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, Reflect)]
+pub enum Falloff {
+    // Full weight anywhere in range
+    #[default]
+    Constant,
+    // Fading evenly to nothing at the edge of range
+    Linear,
+    // Inverse square of distance, softened to 1 up close and 1/16 at the edge
+    InverseSquare,
+    // Like linear, but easing in and out
+    Smoothstep,
+}
+
+impl Falloff {
+    pub fn weight(self, distance: f32, range: f32) -> f32 {
+        let t = if range > 0.0 { (distance / range).clamp(0.0, 1.0) } else { 0.0 };
+
+        match self {
+            Falloff::Constant => 1.0,
+            Falloff::Linear => 1.0 - t,
+            Falloff::InverseSquare => (1.0 + 3.0 * t).powi(-2),
+            Falloff::Smoothstep => 1.0 - t * t * (3.0 - 2.0 * t),
+        }
+    }
+}
+
+// Structure answering the boids' neighbor queries
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, Reflect)]
+pub enum SpatialIndex {
+    // bevy_spatial's kd-tree
+    KdTree,
+    // A plain list checked in full by every query
+    Flat,
+    // Spatial hash of vision-range cells, only the cells around a boid are searched
+    #[default]
+    Grid,
+}
+
+// How a boid asks the index for its candidate neighbors
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, Reflect)]
+pub enum NeighborQuery {
+    // The `neighbor_cap` nearest, however far; cheapest on the kd-tree, lopsided in sparse flocks
+    Nearest,
+    // Every boid within range, uncapped, so dense flocks cost the most
+    Radius,
+    // Every boid within range, the nearest `neighbor_cap` of them when there are more
+    #[default]
+    Hybrid,
+}
+
+// How velocity_system steps boids' flight through each tick, steering held fixed over it
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, Reflect)]
+pub enum Integrator {
+    // Moved at the velocity it started the tick with, drag and all taken at the start; cheapest,
+    // and overshoots as ticks lengthen
+    Euler,
+    // Moved at the velocity it ends the tick with, drag solved for at the end so it never
+    // overshoots
+    #[default]
+    SemiImplicit,
+    // Thrust and drag sampled four times through the tick, for the smoothest arcs at low tick
+    // rates and large time scales
+    Rk4,
+}
+
+// Multipliers on a boid's steering terms while it's in one `BoidState`
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, Reflect)]
+#[serde(default)]
+pub struct StateWeights {
+    pub cohesion: f32,
+    pub alignment: f32,
+    pub separation: f32,
+    pub flee: f32,
+    pub food: f32,
+    pub wander: f32,
+    // Chasing the cursor or following waypoints
+    pub seek: f32,
+}
+
+impl Default for StateWeights {
+    fn default() -> Self {
+        Self {
+            cohesion: 1.0,
+            alignment: 1.0,
+            separation: 1.0,
+            flee: 1.0,
+            food: 1.0,
+            wander: 1.0,
+            seek: 1.0,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize, Reflect)]
+#[serde(default)]
+pub struct BehaviorWeights {
+    pub flocking: StateWeights,
+    pub fleeing: StateWeights,
+    pub feeding: StateWeights,
+    pub resting: StateWeights,
+}
+
+impl Default for BehaviorWeights {
+    fn default() -> Self {
+        let weights = StateWeights::default();
+
+        Self {
+            flocking: weights,
+            // Away from the predator first, ignoring food, wander and the cursor
+            fleeing: StateWeights { cohesion: 0.5, flee: 2.0, food: 0.0, wander: 0.0, seek: 0.0, ..weights },
+            // Break from the flock towards the pellet
+            feeding: StateWeights { cohesion: 0.5, alignment: 0.5, food: 1.5, seek: 0.0, ..weights },
+            // Drift along with the flock
+            resting: StateWeights { wander: 0.2, seek: 0.0, ..weights },
+        }
+    }
+}
+
+// Tunable flocking parameters, read by every boid system each tick
+#[derive(Resource, Clone, Debug, PartialEq, Serialize, Deserialize, Reflect)]
+#[reflect(Resource)]
+#[serde(default)]
+pub struct BoidSettings {
+    pub count: usize,
+    pub species_count: u8,
+    pub size: f32,
+    pub render: BoidRender,
+    pub color_mode: ColorMode,
+    pub neighbor_query: NeighborQuery,
+    pub neighbor_cap: usize,
+    // Boids re-query the index every this many ticks, or sooner once they've moved
+    // `neighbor_refresh_distance`; 1 queries every tick
+    pub neighbor_refresh_ticks: u32,
+    pub neighbor_refresh_distance: f32,
+    // Boids further than `lod_margin` outside the camera's view steer every `lod_interval`
+    // ticks, keeping their last steering in between; 1 steers them every tick
+    pub lod_interval: u32,
+    pub lod_margin: f32,
+    pub spatial_index: SpatialIndex,
+    // The index is rebuilt once boids may have drifted `index_drift` from where it has them,
+    // and at least every `index_max_ticks`; frames slower than `frame_budget_ms` let it drift
+    // up to four times as far. 0 drift or 1 tick rebuilds every tick
+    pub index_drift: f32,
+    pub index_max_ticks: u32,
+    // Times every neighbor query, to log alongside build times when the index is switched
+    pub log_index_timings: bool,
+    pub frame_budget_ms: f32,
+    pub boundary_mode: BoundaryMode,
+    pub boundary_size: f32,
+    pub vis_range: f32,
+    pub prot_range: f32,
+    // Widest angle off heading at which a neighbor is still seen, in radians
+    pub fov: f32,
+    // Imperfect senses: standard deviations of the noise on each neighbor's perceived offset
+    // and velocity, and the chance per tick of missing it altogether; 0 for perfect senses
+    pub perception_position_noise: f32,
+    pub perception_velocity_noise: f32,
+    pub perception_miss_chance: f32,
+    // Steering factors yield accelerations for a boid of mass 1, in units per second squared
+    pub center_factor: f32,
+    pub matching_factor: f32,
+    pub avoid_factor: f32,
+    // Weighting of neighbors by distance for each rule, over vision range for cohesion and
+    // alignment and protected range for separation
+    pub cohesion_falloff: Falloff,
+    pub alignment_falloff: Falloff,
+    pub separation_falloff: Falloff,
+    pub turn_factor: f32,
+    // Pull towards the boid's seek target, the cursor unless something retargets it
+    pub mouse_chase_factor: f32,
+    // Push away from the cursor while right mouse is held, fading out at `mouse_repel_range`
+    pub mouse_repel_factor: f32,
+    pub mouse_repel_range: f32,
+    // Pull towards the current waypoint, which is reached once the flock's centroid is within `waypoint_radius`
+    pub waypoint_seek_factor: f32,
+    pub waypoint_radius: f32,
+    pub leader_follow_factor: f32,
+    // Sideways drift from wander noise, and how quickly it changes
+    pub wander_factor: f32,
+    pub wander_rate: f32,
+    // How far behind a leader followers aim for
+    pub leader_distance: f32,
+    // Distance from an obstacle's surface at which boids start steering away
+    pub obstacle_range: f32,
+    pub obstacle_avoid_factor: f32,
+    // Rays cast ahead, and this many radians either side, to turn along obstacles and the
+    // boundary before reaching them; 0 length or factor to go without
+    pub whisker_length: f32,
+    pub whisker_angle: f32,
+    pub whisker_factor: f32,
+    // Boids don't react to neighbors hidden behind obstacles, so flocks split around large
+    // ones; off to skip the checks
+    pub line_of_sight: bool,
+    // Acceleration from a full-strength flow field cell
+    pub flow_factor: f32,
+    // Turbulent wind over the whole world: its acceleration at the strongest, how many units
+    // across its gusts are and how quickly they shift; 0 factor for still air
+    pub wind_factor: f32,
+    pub wind_scale: f32,
+    pub wind_rate: f32,
+    // Pull along a heading shared by the whole flock, turning full circle every `migration_period`
+    // seconds so the flock migrates around the world; 0 factor to stay put
+    pub migration_factor: f32,
+    pub migration_period: f32,
+    // Trail laid per boid per second, the fraction of it lost per second and how fast it spreads
+    pub pheromone_deposit: f32,
+    pub pheromone_decay: f32,
+    pub pheromone_diffusion: f32,
+    // Pull up the trail gradient ahead of a boid
+    pub pheromone_follow_factor: f32,
+    // Pull of the nearest food pellet on a hungry boid
+    pub forage_factor: f32,
+    // Hunger gained per second, a boid is hungry from 0.5
+    pub hunger_rate: f32,
+    // Seconds between food pellets appearing, while fewer than `food_max` lie around
+    pub food_interval: f32,
+    pub food_max: usize,
+    pub predator_count: usize,
+    pub predator_chase_factor: f32,
+    // Speeds are in units per second
+    pub predator_max_speed: f32,
+    // Predators catch prey this close, which reappear at the edge `respawn_delay` seconds later
+    pub catch_range: f32,
+    pub respawn_delay: f32,
+    // Applied to prey for each predator within vision range
+    pub flee_factor: f32,
+    // Push from each boid of a species to avoid in view, and pull towards the nearest one to chase
+    pub species_avoid_factor: f32,
+    pub species_chase_factor: f32,
+    pub min_speed: f32,
+    pub max_speed: f32,
+    // Quadratic air drag on a boid of mass 1, balanced by its thrust at `max_speed` (or the
+    // predators' top speed) so that becomes its cruising speed rather than a hard cap; 0 to
+    // clamp speed at the maximum instead
+    pub drag: f32,
+    pub integrator: Integrator,
+    // Fastest a boid's heading can swing, in degrees per second, so it flies round in arcs
+    // rather than flipping about; 0 for no limit
+    pub max_turn_rate: f32,
+    // Energy spent per second at full speed and per radian turned, regained per second coasting
+    pub energy_drain: f32,
+    pub turn_cost: f32,
+    pub energy_recovery: f32,
+    // Spread of per-boid vision, FOV, protected range and max speed, as a fraction; 0 for a uniform flock
+    pub trait_variation: f32,
+    // How far boids' masses stray from 1 either way, as a fraction; 0 for all alike
+    pub mass_variation: f32,
+    // Seconds a boid lives, give or take a fifth; 0 lets boids live forever, and not breed.
+    // Well-fed pairs of a species within `breed_range` hatch a chick at `breed_chance` per
+    // second, its traits and mass halfway between theirs and nudged by up to `mutation`
+    pub lifespan: f32,
+    pub breed_chance: f32,
+    pub breed_range: f32,
+    pub mutation: f32,
+    // Seconds of past ticks kept to rewind through, 0 to keep none
+    pub rewind_seconds: f32,
+    // Positions kept per boid trail, one per tick
+    pub trail_length: usize,
+    // Chance per second a boid heads down to a roost, staying between `perch_min` and `perch_max` seconds
+    pub perch_chance: f32,
+    pub perch_min: f32,
+    pub perch_max: f32,
+    pub perch_seek_factor: f32,
+    // Seconds of simulated time a whole day takes, once the day/night cycle is running
+    pub day_length: f32,
+    // Seconds a round of capture-the-zone lasts, and how far each team's zone reaches
+    pub round_length: f32,
+    pub zone_radius: f32,
+    // Boids a second from each nest placed with `J`, and the population beyond which the oldest
+    // boids are despawned to make room, if any
+    pub emitter_rate: f32,
+    pub emitter_cap: Option<usize>,
+    pub behavior: BehaviorWeights,
+}
+
+impl BoidSettings {
+    pub fn vis_range_sq(&self) -> f32 {
+        self.vis_range * self.vis_range
+    }
+
+    pub fn prot_range_sq(&self) -> f32 {
+        self.prot_range * self.prot_range
+    }
+}
+
+impl Default for BoidSettings {
+    fn default() -> Self {
+        Self {
+            count: 256,
+            species_count: 1,
+            size: 7.5,
+            render: BoidRender::Mesh,
+            color_mode: ColorMode::Species,
+            neighbor_query: NeighborQuery::Hybrid,
+            neighbor_cap: 100,
+            neighbor_refresh_ticks: 4,
+            neighbor_refresh_distance: 10.,
+            lod_interval: 4,
+            lod_margin: 50.,
+            spatial_index: SpatialIndex::Grid,
+            index_drift: 4.,
+            index_max_ticks: 8,
+            log_index_timings: false,
+            frame_budget_ms: 1000. / 60.,
+            boundary_mode: BoundaryMode::Steer,
+            boundary_size: 150.,
+            vis_range: 40.,
+            prot_range: 8.,
+            // https://en.wikipedia.org/wiki/Bird_vision#Extraocular_anatomy
+            fov: 120_f32.to_radians(),
+            perception_position_noise: 0.,
+            perception_velocity_noise: 0.,
+            perception_miss_chance: 0.,
+            center_factor: 1.8,
+            matching_factor: 3.0,
+            avoid_factor: 180.,
+            cohesion_falloff: Falloff::Constant,
+            alignment_falloff: Falloff::Constant,
+            separation_falloff: Falloff::Constant,
+            turn_factor: 720.,
+            mouse_chase_factor: 1.8,
+            mouse_repel_factor: 1500.,
+            mouse_repel_range: 150.,
+            waypoint_seek_factor: 1.8,
+            waypoint_radius: 60.,
+            leader_follow_factor: 7.2,
+            wander_factor: 60.,
+            wander_rate: 1.5,
+            leader_distance: 20.,
+            obstacle_range: 30.,
+            obstacle_avoid_factor: 1800.,
+            whisker_length: 60.,
+            whisker_angle: 30_f32.to_radians(),
+            whisker_factor: 600.,
+            line_of_sight: true,
+            flow_factor: 300.,
+            wind_factor: 0.,
+            wind_scale: 400.,
+            wind_rate: 0.15,
+            migration_factor: 0.,
+            migration_period: 600.,
+            pheromone_deposit: 5.,
+            pheromone_decay: 0.5,
+            pheromone_diffusion: 2.,
+            pheromone_follow_factor: 120.,
+            forage_factor: 600.,
+            hunger_rate: 0.05,
+            food_interval: 0.5,
+            food_max: 40,
+            predator_count: 0,
+            predator_chase_factor: 7.2,
+            predator_max_speed: 270.,
+            catch_range: 6.,
+            respawn_delay: 2.,
+            flee_factor: 1080.,
+            species_avoid_factor: 360.,
+            species_chase_factor: 7.2,
+            min_speed: 120.,
+            max_speed: 240.,
+            drag: 0.01,
+            integrator: Integrator::SemiImplicit,
+            max_turn_rate: 540.,
+            trait_variation: 0.0,
+            mass_variation: 0.0,
+            lifespan: 0.,
+            breed_chance: 0.1,
+            breed_range: 20.,
+            mutation: 0.05,
+            energy_drain: 0.1,
+            turn_cost: 0.02,
+            energy_recovery: 0.2,
+            rewind_seconds: 10.,
+            trail_length: 30,
+            perch_chance: 0.0,
+            perch_min: 2.,
+            perch_max: 6.,
+            perch_seek_factor: 900.,
+            day_length: 240.,
+            round_length: 60.,
+            zone_radius: 80.,
+            emitter_rate: 20.,
+            emitter_cap: None,
+            behavior: BehaviorWeights::default(),
+        }
+    }
+}
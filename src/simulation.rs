@@ -0,0 +1,105 @@
+use bevy::prelude::*;
+
+use crate::{
+    input::{Action, Actions},
+    rng::RngPlugin,
+    settings::{BoidRender, BoidSettings, BoundaryMode, ColorMode, Falloff, Integrator, NeighborQuery},
+    species::{Interaction, SpeciesInteractions},
+};
+
+pub const MIN_TIME_SCALE: f32 = 0.1;
+pub const MAX_TIME_SCALE: f32 = 4.0;
+
+// Pause, single-step and speed controls for the fixed-rate simulation
+#[derive(Resource, Clone, Debug, Reflect)]
+#[reflect(Resource)]
+pub struct SimulationState {
+    pub paused: bool,
+    // Run exactly one more tick while paused
+    pub step: bool,
+    // Multiplier on how fast simulated time passes
+    pub time_scale: f32,
+}
+
+impl Default for SimulationState {
+    fn default() -> Self {
+        Self {
+            paused: false,
+            step: false,
+            time_scale: 1.0,
+        }
+    }
+}
+
+// Run condition for the boid systems
+pub fn simulation_running(state: Res<SimulationState>) -> bool {
+    !state.paused || state.step
+}
+
+// Tick rate, settings, rng and pause state shared by the 2D and 3D simulations
+pub struct SimulationPlugin;
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(Time::<Fixed>::from_hz(60.0))
+            .init_resource::<BoidSettings>()
+            .init_resource::<SpeciesInteractions>()
+            .init_resource::<SimulationState>()
+            .register_type::<BoidSettings>()
+            .register_type::<BoidRender>()
+            .register_type::<BoundaryMode>()
+            .register_type::<ColorMode>()
+            .register_type::<Falloff>()
+            .register_type::<Interaction>()
+            .register_type::<Integrator>()
+            .register_type::<NeighborQuery>()
+            .register_type::<SpeciesInteractions>()
+            .register_type::<SimulationState>()
+            .add_plugins(RngPlugin)
+            .add_systems(Update, time_scale_system)
+            .add_systems(FixedLast, finish_step_system);
+    }
+}
+
+// `Space` pauses, `.` steps one tick while paused, `[` / `]` halve / double the time scale
+pub struct SimulationControlsPlugin;
+
+impl Plugin for SimulationControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, simulation_input_system.before(time_scale_system));
+    }
+}
+
+fn simulation_input_system(
+    actions: Actions,
+    mut state: ResMut<SimulationState>,
+) {
+    if actions.just_pressed(Action::Pause) {
+        state.paused = !state.paused;
+    }
+    if actions.just_pressed(Action::Step) && state.paused {
+        state.step = true;
+    }
+    if actions.just_pressed(Action::SlowDown) {
+        state.time_scale = (state.time_scale / 2.0).max(MIN_TIME_SCALE);
+    }
+    if actions.just_pressed(Action::SpeedUp) {
+        state.time_scale = (state.time_scale * 2.0).min(MAX_TIME_SCALE);
+    }
+}
+
+// Scaling virtual time runs FixedUpdate more or less often without changing its timestep
+fn time_scale_system(
+    state: Res<SimulationState>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    if state.is_changed() {
+        time.set_relative_speed(state.time_scale.clamp(MIN_TIME_SCALE, MAX_TIME_SCALE));
+    }
+}
+
+fn finish_step_system(mut state: ResMut<SimulationState>) {
+    if state.step {
+        state.step = false;
+    }
+}
@@ -0,0 +1,152 @@
+use std::{error::Error, fs, path::Path};
+use bevy::{math::Vec3Swizzles, prelude::*};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+    input::{Action, Actions},
+    settings::BoidSettings,
+    species::Species,
+    variation::{BoidTraits, Mass},
+    spawn_boid,
+    AnyBoid,
+    BoidColor,
+    BoidSpawn,
+    Velocity,
+    Wander,
+};
+
+const SNAPSHOT_PATH: &str = "boids_state.ron";
+
+// Everything needed to recreate the flock exactly as it was
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct SimulationSnapshot {
+    pub settings: BoidSettings,
+    pub boids: Vec<BoidState>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct BoidState {
+    pub position: [f32; 2],
+    pub velocity: [f32; 2],
+    // Linear RGBA
+    pub color: [f32; 4],
+    pub species: Species,
+    #[serde(default)]
+    pub wander_phase: f32,
+    #[serde(default)]
+    pub traits: Option<BoidTraits>,
+    #[serde(default)]
+    pub mass: Option<Mass>,
+}
+
+impl SimulationSnapshot {
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), Box<dyn Error>> {
+        let ron = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        fs::write(path, ron)?;
+        Ok(())
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Box<dyn Error>> {
+        Ok(ron::from_str(&fs::read_to_string(path)?)?)
+    }
+}
+
+// F5 saves the simulation to `boids_state.ron`, F9 restores it
+pub struct SnapshotPlugin;
+
+impl Plugin for SnapshotPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (
+            save_snapshot_system.run_if(|actions: Actions| actions.just_pressed(Action::SaveSnapshot)),
+            load_snapshot_system.run_if(|actions: Actions| actions.just_pressed(Action::LoadSnapshot)),
+        ));
+    }
+}
+
+pub type SnapshotQuery = (
+    &'static Transform,
+    &'static Velocity,
+    &'static BoidColor,
+    &'static Species,
+    &'static Wander,
+    Option<&'static BoidTraits>,
+    Option<&'static Mass>,
+);
+
+// Capture every boid plus the active settings; perched boids come back flying
+pub fn take_snapshot(
+    boids: &Query<SnapshotQuery, AnyBoid>,
+    settings: &BoidSettings,
+) -> SimulationSnapshot {
+    let boids = boids.iter()
+        .map(|(transform, velocity, color, species, wander, traits, mass)| BoidState {
+            position: transform.translation.xy().to_array(),
+            velocity: velocity.0.to_array(),
+            color: color.0.as_linear_rgba_f32(),
+            species: *species,
+            wander_phase: wander.0,
+            traits: traits.copied(),
+            mass: mass.copied(),
+        })
+        .collect();
+
+    SimulationSnapshot {
+        settings: settings.clone(),
+        boids,
+    }
+}
+
+// Replace the current flock and settings with the snapshot's
+pub fn restore_snapshot(
+    commands: &mut Commands,
+    settings: &mut ResMut<BoidSettings>,
+    boids: impl Iterator<Item = Entity>,
+    snapshot: SimulationSnapshot,
+) {
+    for boid in boids {
+        commands.entity(boid).despawn();
+    }
+
+    // The old boids are only gone once commands apply, so boid_count_system
+    // must not see this change and try to make up the difference
+    let settings = settings.bypass_change_detection();
+    *settings = snapshot.settings;
+    settings.count = snapshot.boids.len();
+
+    for boid in snapshot.boids {
+        let spawn = BoidSpawn {
+            position: Vec2::from_array(boid.position),
+            velocity: Vec2::from_array(boid.velocity),
+            species: boid.species,
+            color: Color::rgba_linear_from_array(boid.color),
+            wander_phase: boid.wander_phase,
+            traits: boid.traits,
+            mass: boid.mass,
+        };
+        spawn_boid(commands, settings, spawn);
+    }
+}
+
+fn save_snapshot_system(
+    boids: Query<SnapshotQuery, AnyBoid>,
+    settings: Res<BoidSettings>,
+) {
+    match take_snapshot(&boids, &settings).save(SNAPSHOT_PATH) {
+        Ok(()) => info!("saved simulation to {SNAPSHOT_PATH}"),
+        Err(err) => error!("failed to save simulation to {SNAPSHOT_PATH}: {err}"),
+    }
+}
+
+fn load_snapshot_system(
+    mut commands: Commands,
+    mut settings: ResMut<BoidSettings>,
+    boids: Query<Entity, AnyBoid>,
+) {
+    match SimulationSnapshot::load(SNAPSHOT_PATH) {
+        Ok(snapshot) => {
+            restore_snapshot(&mut commands, &mut settings, boids.iter(), snapshot);
+            info!("loaded simulation from {SNAPSHOT_PATH}");
+        }
+        Err(err) => error!("failed to load simulation from {SNAPSHOT_PATH}: {err}"),
+    }
+}
@@ -0,0 +1,77 @@
+use bevy::{math::Vec3Swizzles, prelude::*};
+use bevy_spatial::{
+    kdtree::{KDTree2, KDTree3},
+    point::{Point2, SpatialPoint},
+};
+use kd_tree::KdTree;
+
+// The trees are rebuilt from FixedUpdate rather than bevy_spatial's timer in Update,
+// so which positions a tick sees only depends on the simulation and not on frame timing
+
+pub fn rebuild_tree2<Comp: Component>(
+    mut tree: ResMut<KDTree2<Comp>>,
+    query: Query<(Entity, &Transform), With<Comp>>,
+) {
+    let points = query.iter()
+        .map(|(entity, transform)| (entity, transform.translation.xy()).into())
+        .collect();
+
+    tree.tree = build_kdtree2(points);
+}
+
+pub fn build_kdtree2(points: Vec<Point2>) -> KdTree<Point2> {
+    #[cfg(not(target_arch = "wasm32"))]
+    let built = KdTree::par_build_by_ordered_float(points);
+    #[cfg(target_arch = "wasm32")]
+    let built = KdTree::build_by_ordered_float(points);
+
+    built
+}
+
+// Splitting lines of a 2D tree clipped to `area`, with the depth of the node that made each.
+// The tree keeps its items in build order: each node's median along its axis sits in the middle
+// of its slice, the points on either side of it in the halves before and after
+pub fn partitions2<Comp>(tree: &KDTree2<Comp>, area: Rect) -> Vec<(Vec2, Vec2, usize)> {
+    let points = tree.tree.items().iter().map(|item| item.vec()).collect::<Vec<_>>();
+    let mut splits = vec![];
+    split_cells(&points, 0, 0, area, &mut splits);
+    splits
+}
+
+fn split_cells(points: &[Vec2], axis: usize, depth: usize, cell: Rect, splits: &mut Vec<(Vec2, Vec2, usize)>) {
+    if points.is_empty() {
+        return;
+    }
+
+    let mid = points.len() / 2;
+    let at = points[mid][axis].clamp(cell.min[axis], cell.max[axis]);
+
+    let (mut low, mut high) = (cell, cell);
+    low.max[axis] = at;
+    high.min[axis] = at;
+
+    splits.push(if axis == 0 {
+        (Vec2::new(at, cell.min.y), Vec2::new(at, cell.max.y), depth)
+    } else {
+        (Vec2::new(cell.min.x, at), Vec2::new(cell.max.x, at), depth)
+    });
+
+    split_cells(&points[..mid], 1 - axis, depth + 1, low, splits);
+    split_cells(&points[mid + 1..], 1 - axis, depth + 1, high, splits);
+}
+
+pub fn rebuild_tree3<Comp: Component>(
+    mut tree: ResMut<KDTree3<Comp>>,
+    query: Query<(Entity, &Transform), With<Comp>>,
+) {
+    let points = query.iter()
+        .map(|(entity, transform)| (entity, transform.translation).into())
+        .collect();
+
+    #[cfg(not(target_arch = "wasm32"))]
+    let built = KdTree::par_build_by_ordered_float(points);
+    #[cfg(target_arch = "wasm32")]
+    let built = KdTree::build_by_ordered_float(points);
+
+    tree.tree = built;
+}
@@ -0,0 +1,82 @@
+use bevy::prelude::*;
+use bevy_spatial::SpatialAccess;
+
+use crate::{
+    index::BoidIndex,
+    input::{Action, Actions},
+    rng::SimRng,
+    settings::BoidSettings,
+    spawn_boid,
+    sync_boid_count,
+    AnyBoid,
+    BoidSpawn,
+    CursorPosition,
+    Flown,
+};
+
+// Boids added or removed per `+`/`-` press
+const SPAWN_BATCH: usize = 10;
+
+// `+`/`-` add or remove a batch of boids at the cursor,
+// shift + left/right click adds or removes a single boid
+pub struct SpawningPlugin;
+
+impl Plugin for SpawningPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, spawn_input_system);
+    }
+}
+
+fn spawn_input_system(
+    mut commands: Commands,
+    mut settings: ResMut<BoidSettings>,
+    mut rng: ResMut<SimRng>,
+    actions: Actions,
+    index: Res<BoidIndex>,
+    // Neither the player nor a boid in the mouse's grip is removed
+    boids: Query<(), (AnyBoid, Flown)>,
+    cursor: Res<CursorPosition>,
+) {
+    let shift = actions.pressed(Action::Alternate);
+
+    let spawn = if actions.just_pressed(Action::SpawnBatch) {
+        SPAWN_BATCH
+    } else if shift && actions.just_pressed(Action::Select) {
+        1
+    } else {
+        0
+    };
+
+    let despawn = if actions.just_pressed(Action::DespawnBatch) {
+        SPAWN_BATCH
+    } else if shift && actions.just_pressed(Action::Repel) {
+        1
+    } else {
+        0
+    };
+
+    if spawn == 0 && despawn == 0 {
+        return;
+    }
+
+    let cursor = cursor.0.unwrap_or(Vec2::ZERO);
+    // Adjusting the configured count, which every other path adding or removing boids keeps in step
+    let mut count = settings.count;
+
+    for _ in 0..spawn {
+        let spawn = BoidSpawn::random(&mut **rng, &settings, cursor);
+        spawn_boid(&mut commands, &settings, spawn);
+        count += 1;
+    }
+
+    // Remove the boids closest to the cursor; the index may still hold
+    // boids despawned since its last rebuild, so check they still exist
+    for (_, entity) in index.k_nearest_neighbour(cursor, despawn) {
+        if let Some(entity) = entity.filter(|e| boids.contains(*e)) {
+            commands.entity(entity).despawn();
+            count = count.saturating_sub(1);
+        }
+    }
+
+    sync_boid_count(&mut settings, count);
+}
@@ -0,0 +1,51 @@
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::settings::BoidSettings;
+
+// Saturation levels per species, keeps the number of distinct materials small
+pub const SHADES: u8 = 4;
+
+// Boids flock with others of the same species, and treat the rest as `SpeciesInteractions` says
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize, Reflect)]
+#[reflect(Component)]
+pub struct Species(pub u8);
+
+impl Species {
+    // Evenly spaced hues, with one of `SHADES` saturations per boid
+    pub fn color(&self, species_count: u8, shade: u8) -> Color {
+        let hue = 360. * self.0 as f32 / species_count.max(1) as f32;
+        let saturation = (shade.min(SHADES - 1) + 1) as f32 / SHADES as f32;
+        Color::hsl(hue, saturation, 0.7)
+    }
+}
+
+// How boids of one species react to those of another
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize, Reflect)]
+pub enum Interaction {
+    // Cohere, align and separate as with their own kind
+    Flock,
+    #[default]
+    Ignore,
+    // Steer away from them
+    Avoid,
+    // Steer after the nearest one
+    Chase,
+}
+
+// Row `a`, column `b` is how species `a` reacts to species `b`. Pairs outside
+// the matrix use the default: boids flock with their own kind and ignore the rest
+#[derive(Resource, Clone, Debug, Default, PartialEq, Serialize, Deserialize, Reflect)]
+#[reflect(Resource)]
+#[serde(transparent)]
+pub struct SpeciesInteractions(pub Vec<Vec<Interaction>>);
+
+impl SpeciesInteractions {
+    pub fn get(&self, species: Species, other: Species) -> Interaction {
+        match self.0.get(species.0 as usize).and_then(|row| row.get(other.0 as usize)) {
+            Some(interaction) => *interaction,
+            None if species == other => Interaction::Flock,
+            None => Interaction::Ignore,
+        }
+    }
+}
@@ -0,0 +1,133 @@
+use bevy::{
+    diagnostic::{DiagnosticsStore, FrameTimeDiagnosticsPlugin},
+    prelude::*,
+};
+
+use crate::{flocking_system, input::{Action, Actions}, settings::BoidSettings, BoidSet, SpatialEntity, Velocity};
+
+// Flock-wide measurements, refreshed every tick while the HUD is shown or telemetry is exported
+#[derive(Resource, Default)]
+pub struct FlockStats {
+    pub enabled: bool,
+    pub exporting: bool,
+    pub boids: usize,
+    pub mean_speed: f32,
+    // Neighbors each boid perceived, and was handed by the index to check, on average
+    pub mean_neighbors: f32,
+    pub mean_candidates: f32,
+    // Length of the mean heading, 1 when every boid flies the same way
+    pub polarization: f32,
+    // Perceived neighbors summed over all boids, filled by flocking_system
+    pub neighbors: usize,
+    pub candidates: usize,
+    // Sub-flocks of two or more boids, the largest's size and boids in none, filled by find_clusters
+    pub clusters: usize,
+    pub largest_cluster: usize,
+    pub strays: usize,
+}
+
+impl FlockStats {
+    pub fn collecting(&self) -> bool {
+        self.enabled || self.exporting
+    }
+}
+
+#[derive(Component)]
+struct StatsHud;
+
+// `H` toggles a HUD with FPS and flock statistics
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        if !app.is_plugin_added::<FrameTimeDiagnosticsPlugin>() {
+            app.add_plugins(FrameTimeDiagnosticsPlugin);
+        }
+
+        app.init_resource::<FlockStats>()
+            .add_systems(Startup, spawn_hud)
+            .add_systems(FixedUpdate, collect_stats
+                .in_set(BoidSet::Steer)
+                .after(flocking_system)
+                .run_if(|stats: Res<FlockStats>| stats.collecting()))
+            .add_systems(Update, (toggle_hud, update_hud).chain());
+    }
+}
+
+pub fn collect_stats(
+    boids: Query<&Velocity, With<SpatialEntity>>,
+    mut stats: ResMut<FlockStats>,
+) {
+    let mut speed = 0.0;
+    let mut heading = Vec2::ZERO;
+
+    for velocity in boids.iter() {
+        speed += velocity.0.length();
+        heading += velocity.0.normalize_or_zero();
+    }
+
+    let count = boids.iter().len();
+    let n = count.max(1) as f32;
+
+    stats.boids = count;
+    stats.mean_speed = speed / n;
+    stats.mean_neighbors = stats.neighbors as f32 / n;
+    stats.mean_candidates = stats.candidates as f32 / n;
+    stats.polarization = heading.length() / n;
+}
+
+fn spawn_hud(mut commands: Commands) {
+    commands.spawn((
+        TextBundle::from_section("", TextStyle {
+            font_size: 16.0,
+            color: Color::WHITE,
+            ..default()
+        })
+        .with_style(Style {
+            position_type: PositionType::Absolute,
+            top: Val::Px(8.0),
+            left: Val::Px(8.0),
+            ..default()
+        }),
+        Visibility::Hidden,
+        StatsHud,
+    ));
+}
+
+fn toggle_hud(
+    actions: Actions,
+    mut stats: ResMut<FlockStats>,
+    mut hud: Query<&mut Visibility, With<StatsHud>>,
+) {
+    if actions.just_pressed(Action::Stats) {
+        stats.enabled = !stats.enabled;
+
+        for mut visibility in hud.iter_mut() {
+            *visibility = if stats.enabled { Visibility::Visible } else { Visibility::Hidden };
+        }
+    }
+}
+
+fn update_hud(
+    stats: Res<FlockStats>,
+    diagnostics: Res<DiagnosticsStore>,
+    settings: Res<BoidSettings>,
+    mut hud: Query<&mut Text, With<StatsHud>>,
+) {
+    if !stats.enabled {
+        return;
+    }
+
+    let fps = diagnostics.get(&FrameTimeDiagnosticsPlugin::FPS)
+        .and_then(|fps| fps.smoothed())
+        .unwrap_or_default();
+
+    for mut text in hud.iter_mut() {
+        text.sections[0].value = format!(
+            "FPS {fps:.0}\nboids {}\nspeed {:.2}\nneighbors {:.1} of {:.1} ({:?})\npolarization {:.2}\nclusters {} (largest {}, {} strays)",
+            stats.boids, stats.mean_speed, stats.mean_neighbors, stats.mean_candidates, settings.neighbor_query,
+            stats.polarization,
+            stats.clusters, stats.largest_cluster, stats.strays,
+        );
+    }
+}
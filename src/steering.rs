@@ -0,0 +1,427 @@
+use bevy::prelude::*;
+
+use crate::{flocking::wander_noise, flow::turbulence, leaders::follow, settings::BoidSettings, FlockingParams};
+
+// Everything a behavior outside the crate needs to name
+pub use crate::{
+    flocking::{Neighbor, RuleTerms},
+    settings::StateWeights,
+    species::Interaction,
+};
+
+// One boid being steered, with everything a behavior can read about its surroundings
+pub struct SteeringContext<'a, 'w, 's> {
+    // The world as the built-in behaviors read it
+    pub(crate) params: &'a FlockingParams<'w, 's>,
+    // The boid's species settings with its traits applied
+    pub settings: &'a BoidSettings,
+    pub boid: Entity,
+    pub position: Vec2,
+    pub velocity: Vec2,
+    pub heading: Vec2,
+    // Boids it perceives and flocks with, offsets relative to it; the built-in rules use
+    // `rules` instead
+    pub neighbors: &'a [Neighbor<Vec2>],
+    // The three classic rules over them, worked out together
+    pub rules: RuleTerms<Vec2>,
+    // Boids it perceives and avoids or chases, by their species
+    pub others: &'a [(Interaction, Neighbor<Vec2>)],
+    // Offset and velocity of the closest leader among them
+    pub nearest_leader: Option<(Vec2, Vec2)>,
+    pub leader: bool,
+    // Phase of its wander noise
+    pub wander: f32,
+}
+
+// A single steering rule, returning an acceleration in units/s²
+pub trait SteeringBehavior: Send + Sync + 'static {
+    // Shown in the selection panel
+    fn name(&self) -> &'static str;
+
+    fn steer(&self, ctx: &SteeringContext) -> Vec2;
+
+    // How much the boid's behavior state scales this rule
+    fn state_weight(&self, _weights: &StateWeights) -> f32 {
+        1.0
+    }
+}
+
+// What each behavior contributed to one boid's acceleration this tick, after weighting
+#[derive(Clone, Default, Debug)]
+pub struct SteeringTerms(Vec<(&'static str, Vec2)>);
+
+impl SteeringTerms {
+    pub fn named(&self) -> impl Iterator<Item = (&'static str, Vec2)> + '_ {
+        self.0.iter().copied()
+    }
+}
+
+struct Registered {
+    behavior: Box<dyn SteeringBehavior>,
+    weight: f32,
+}
+
+// Every behavior boids steer by, summed by weight in registration order. Other crates add their
+// own to the resource, through `BoidsSimulation::app` headless. The turn back from the boundary
+// isn't one: boids coasting out of view skip steering for ticks at a time, so velocity_system
+// adds it every tick instead, keeping any from overshooting
+#[derive(Resource)]
+pub struct SteeringBehaviors(Vec<Registered>);
+
+impl SteeringBehaviors {
+    pub fn empty() -> Self {
+        Self(vec![])
+    }
+
+    // Another behavior, summed after those registered before it
+    pub fn register(&mut self, behavior: impl SteeringBehavior, weight: f32) -> &mut Self {
+        self.0.push(Registered { behavior: Box::new(behavior), weight });
+        self
+    }
+
+    // Weighted sum of every behavior, recording each one's share into `terms` if given
+    pub fn steer(&self, ctx: &SteeringContext, weights: &StateWeights, mut terms: Option<&mut SteeringTerms>) -> Vec2 {
+        let mut total = Vec2::ZERO;
+
+        for Registered { behavior, weight } in &self.0 {
+            let term = behavior.steer(ctx) * *weight * behavior.state_weight(weights);
+            if let Some(terms) = terms.as_deref_mut() {
+                terms.0.push((behavior.name(), term));
+            }
+            total += term;
+        }
+
+        total
+    }
+}
+
+impl Default for SteeringBehaviors {
+    fn default() -> Self {
+        let mut behaviors = Self::empty();
+        behaviors.register(Cohesion, 1.0)
+            .register(Alignment, 1.0)
+            .register(Separation, 1.0)
+            .register(FollowLeader, 1.0)
+            .register(AvoidObstacles, 1.0)
+            .register(Whiskers, 1.0)
+            .register(Flee, 1.0)
+            .register(RepelCursor, 1.0)
+            .register(AvoidSpecies, 1.0)
+            .register(ChaseSpecies, 1.0)
+            .register(Flow, 1.0)
+            .register(Wind, 1.0)
+            .register(Migrate, 1.0)
+            .register(Pheromone, 1.0)
+            .register(Attract, 1.0)
+            .register(Forage, 1.0)
+            .register(Wander, 1.0)
+            .register(Seek, 1.0);
+
+        #[cfg(feature = "scripting")]
+        behaviors.register(crate::scripting::Script, 1.0);
+
+        behaviors
+    }
+}
+
+pub struct Cohesion;
+
+impl SteeringBehavior for Cohesion {
+    fn name(&self) -> &'static str {
+        "cohesion"
+    }
+
+    // Leaders set the course rather than drifting to the middle of the flock
+    fn steer(&self, ctx: &SteeringContext) -> Vec2 {
+        if ctx.leader {
+            return Vec2::ZERO;
+        }
+        ctx.rules.cohesion
+    }
+
+    fn state_weight(&self, weights: &StateWeights) -> f32 {
+        weights.cohesion
+    }
+}
+
+pub struct Alignment;
+
+impl SteeringBehavior for Alignment {
+    fn name(&self) -> &'static str {
+        "alignment"
+    }
+
+    fn steer(&self, ctx: &SteeringContext) -> Vec2 {
+        ctx.rules.alignment
+    }
+
+    fn state_weight(&self, weights: &StateWeights) -> f32 {
+        weights.alignment
+    }
+}
+
+pub struct Separation;
+
+impl SteeringBehavior for Separation {
+    fn name(&self) -> &'static str {
+        "separation"
+    }
+
+    fn steer(&self, ctx: &SteeringContext) -> Vec2 {
+        ctx.rules.separation
+    }
+
+    fn state_weight(&self, weights: &StateWeights) -> f32 {
+        weights.separation
+    }
+}
+
+pub struct FollowLeader;
+
+impl SteeringBehavior for FollowLeader {
+    fn name(&self) -> &'static str {
+        "leader"
+    }
+
+    fn steer(&self, ctx: &SteeringContext) -> Vec2 {
+        ctx.nearest_leader.map_or(Vec2::ZERO, |(offset, velocity)| follow(ctx.settings, offset, velocity))
+    }
+}
+
+pub struct AvoidObstacles;
+
+impl SteeringBehavior for AvoidObstacles {
+    fn name(&self) -> &'static str {
+        "obstacles"
+    }
+
+    fn steer(&self, ctx: &SteeringContext) -> Vec2 {
+        ctx.params.obstacles.avoidance(ctx.settings, ctx.position)
+    }
+}
+
+// Along obstacles and the boundary the boid is about to meet
+pub struct Whiskers;
+
+impl SteeringBehavior for Whiskers {
+    fn name(&self) -> &'static str {
+        "whiskers"
+    }
+
+    fn steer(&self, ctx: &SteeringContext) -> Vec2 {
+        let params = ctx.params;
+        params.obstacles.whiskers(ctx.settings, &params.boundary, **params.bounds, ctx.position, ctx.heading)
+    }
+}
+
+// Away from predators
+pub struct Flee;
+
+impl SteeringBehavior for Flee {
+    fn name(&self) -> &'static str {
+        "flee"
+    }
+
+    fn steer(&self, ctx: &SteeringContext) -> Vec2 {
+        ctx.params.predators.flee(ctx.settings, ctx.position)
+    }
+
+    fn state_weight(&self, weights: &StateWeights) -> f32 {
+        weights.flee
+    }
+}
+
+// Away from the cursor within range while repelling, weighted like chasing it
+pub struct RepelCursor;
+
+impl SteeringBehavior for RepelCursor {
+    fn name(&self) -> &'static str {
+        "cursor"
+    }
+
+    fn steer(&self, ctx: &SteeringContext) -> Vec2 {
+        let Some(c_world) = ctx.params.cursor.0.filter(|_| ctx.params.repel.0) else {
+            return Vec2::ZERO;
+        };
+
+        let settings = ctx.settings;
+        let to_cursor = c_world - ctx.position;
+        let strength = 1.0 - to_cursor.length() / settings.mouse_repel_range;
+        -to_cursor.normalize_or_zero() * strength.max(0.0) * settings.mouse_repel_factor
+    }
+
+    fn state_weight(&self, weights: &StateWeights) -> f32 {
+        weights.seek
+    }
+}
+
+// Away from boids of species set to Avoid, stronger the closer they are
+pub struct AvoidSpecies;
+
+impl SteeringBehavior for AvoidSpecies {
+    fn name(&self) -> &'static str {
+        "avoid species"
+    }
+
+    fn steer(&self, ctx: &SteeringContext) -> Vec2 {
+        let settings = ctx.settings;
+
+        ctx.others.iter()
+            .filter(|(interaction, _)| *interaction == Interaction::Avoid)
+            .map(|(_, neighbor)| {
+                let strength = 1.0 - neighbor.offset.length() / settings.vis_range;
+                -neighbor.offset.normalize_or_zero() * strength.max(0.0) * settings.species_avoid_factor
+            })
+            .sum()
+    }
+
+    fn state_weight(&self, weights: &StateWeights) -> f32 {
+        weights.flee
+    }
+}
+
+// After the nearest boid of a species set to Chase
+pub struct ChaseSpecies;
+
+impl SteeringBehavior for ChaseSpecies {
+    fn name(&self) -> &'static str {
+        "chase species"
+    }
+
+    fn steer(&self, ctx: &SteeringContext) -> Vec2 {
+        ctx.others.iter()
+            .filter(|(interaction, _)| *interaction == Interaction::Chase)
+            .map(|(_, neighbor)| neighbor.offset)
+            .min_by(|a, b| a.length_squared().total_cmp(&b.length_squared()))
+            .map_or(Vec2::ZERO, |offset| offset * ctx.settings.species_chase_factor)
+    }
+
+    fn state_weight(&self, weights: &StateWeights) -> f32 {
+        weights.seek
+    }
+}
+
+pub struct Flow;
+
+impl SteeringBehavior for Flow {
+    fn name(&self) -> &'static str {
+        "flow"
+    }
+
+    fn steer(&self, ctx: &SteeringContext) -> Vec2 {
+        ctx.params.flow.sample(ctx.position) * ctx.settings.flow_factor
+    }
+}
+
+pub struct Wind;
+
+impl SteeringBehavior for Wind {
+    fn name(&self) -> &'static str {
+        "wind"
+    }
+
+    fn steer(&self, ctx: &SteeringContext) -> Vec2 {
+        let settings = ctx.settings;
+        if settings.wind_factor == 0.0 {
+            return Vec2::ZERO;
+        }
+
+        let time = ctx.params.time.elapsed_seconds() * settings.wind_rate;
+        turbulence(ctx.position, time, settings.wind_scale) * settings.wind_factor
+    }
+}
+
+// Along the slowly turning migration heading, the same for every boid
+pub struct Migrate;
+
+impl SteeringBehavior for Migrate {
+    fn name(&self) -> &'static str {
+        "migration"
+    }
+
+    fn steer(&self, ctx: &SteeringContext) -> Vec2 {
+        ctx.params.migration.heading() * ctx.settings.migration_factor
+    }
+}
+
+pub struct Pheromone;
+
+impl SteeringBehavior for Pheromone {
+    fn name(&self) -> &'static str {
+        "pheromone"
+    }
+
+    fn steer(&self, ctx: &SteeringContext) -> Vec2 {
+        ctx.params.pheromone.steering(ctx.position, ctx.heading) * ctx.settings.pheromone_follow_factor
+    }
+}
+
+pub struct Attract;
+
+impl SteeringBehavior for Attract {
+    fn name(&self) -> &'static str {
+        "attractors"
+    }
+
+    fn steer(&self, ctx: &SteeringContext) -> Vec2 {
+        ctx.params.attractors.steering(ctx.position)
+    }
+}
+
+pub struct Forage;
+
+impl SteeringBehavior for Forage {
+    fn name(&self) -> &'static str {
+        "food"
+    }
+
+    fn steer(&self, ctx: &SteeringContext) -> Vec2 {
+        ctx.params.foraging.steering(ctx.settings, ctx.boid, ctx.position)
+    }
+
+    fn state_weight(&self, weights: &StateWeights) -> f32 {
+        weights.food
+    }
+}
+
+// Drift sideways so lone boids don't fly dead straight
+pub struct Wander;
+
+impl SteeringBehavior for Wander {
+    fn name(&self) -> &'static str {
+        "wander"
+    }
+
+    fn steer(&self, ctx: &SteeringContext) -> Vec2 {
+        let settings = ctx.settings;
+        let noise = wander_noise(ctx.params.time.elapsed_seconds() * settings.wander_rate + ctx.wander);
+        ctx.heading.perp() * noise * settings.wander_factor
+    }
+
+    fn state_weight(&self, weights: &StateWeights) -> f32 {
+        weights.wander
+    }
+}
+
+// Along the waypoint route, or after the boid's `SeekTarget` when there isn't one
+pub struct Seek;
+
+impl SteeringBehavior for Seek {
+    fn name(&self) -> &'static str {
+        "seek"
+    }
+
+    fn steer(&self, ctx: &SteeringContext) -> Vec2 {
+        let params = ctx.params;
+        if params.waypoints.target().is_some() {
+            return params.waypoints.seek(ctx.settings, ctx.position);
+        }
+
+        params.seek.position(ctx.boid)
+            .map_or(Vec2::ZERO, |target| (target - ctx.position) * ctx.settings.mouse_chase_factor)
+    }
+
+    fn state_weight(&self, weights: &StateWeights) -> f32 {
+        weights.seek
+    }
+}
@@ -0,0 +1,140 @@
+use std::{
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+use bevy::{app::AppExit, math::Vec3Swizzles, prelude::*};
+
+use crate::{
+    cli::Cli,
+    simulation::simulation_running,
+    species::Species,
+    stats::{collect_stats, FlockStats},
+    BoidSet,
+    SpatialEntity,
+    Velocity,
+};
+
+const FLOCK_HEADER: &str = "tick,time,boids,mean_speed,mean_neighbors,polarization,milling,center_x,center_y,spread";
+const BOID_HEADER: &str = "tick,boid,species,x,y,vx,vy";
+
+// `--telemetry <path.csv> [--telemetry-boids <ticks>]`: flock metrics every tick, and every
+// boid's state every `ticks` ticks into `<path>_boids.csv`
+pub struct TelemetryConfig {
+    pub path: PathBuf,
+    pub boid_interval: Option<u32>,
+}
+
+impl TelemetryConfig {
+    pub fn from_cli(cli: &Cli) -> Option<Self> {
+        Some(Self { path: cli.telemetry.clone()?, boid_interval: cli.telemetry_boids })
+    }
+}
+
+#[derive(Resource)]
+struct Telemetry {
+    flock: BufWriter<File>,
+    boids: Option<(BufWriter<File>, u32)>,
+    tick: u64,
+}
+
+fn create(path: &Path, header: &str) -> io::Result<BufWriter<File>> {
+    let mut file = BufWriter::new(File::create(path)?);
+    writeln!(file, "{header}")?;
+    Ok(file)
+}
+
+pub struct TelemetryPlugin(pub TelemetryConfig);
+
+impl Plugin for TelemetryPlugin {
+    fn build(&self, app: &mut App) {
+        let TelemetryConfig { path, boid_interval } = &self.0;
+        let boids_path = path.with_file_name(format!(
+            "{}_boids.csv",
+            path.file_stem().unwrap_or_default().to_string_lossy(),
+        ));
+
+        let telemetry = create(path, FLOCK_HEADER).and_then(|flock| Ok(Telemetry {
+            flock,
+            boids: boid_interval
+                .map(|interval| create(&boids_path, BOID_HEADER).map(|boids| (boids, interval)))
+                .transpose()?,
+            tick: 0,
+        }));
+
+        match telemetry {
+            Ok(telemetry) => {
+                info!("writing flock telemetry to {}", path.display());
+                if telemetry.boids.is_some() {
+                    info!("writing boid telemetry to {}", boids_path.display());
+                }
+
+                app.insert_resource(telemetry)
+                    .add_systems(Startup, |mut stats: ResMut<FlockStats>| stats.exporting = true)
+                    .add_systems(FixedUpdate, write_telemetry
+                        .in_set(BoidSet::Steer)
+                        .after(collect_stats)
+                        .run_if(simulation_running))
+                    .add_systems(Last, flush_telemetry);
+            }
+            Err(err) => error!("failed to start telemetry at {}: {err}", path.display()),
+        }
+    }
+}
+
+fn write_telemetry(
+    mut telemetry: ResMut<Telemetry>,
+    stats: Res<FlockStats>,
+    boids: Query<(Entity, &Transform, &Velocity, &Species), With<SpatialEntity>>,
+    time: Res<Time>,
+) {
+    let telemetry = &mut *telemetry;
+    telemetry.tick += 1;
+    let tick = telemetry.tick;
+
+    let n = boids.iter().len().max(1) as f32;
+    let center = boids.iter().map(|(_, t, ..)| t.translation.xy()).sum::<Vec2>() / n;
+
+    // Milling is the mean heading around the center, 1 when the flock circles it as a torus
+    let mut spread = 0.0;
+    let mut milling = 0.0;
+    for (_, transform, velocity, _) in boids.iter() {
+        let offset = transform.translation.xy() - center;
+        spread += offset.length_squared();
+        milling += offset.normalize_or_zero().perp_dot(velocity.0.normalize_or_zero());
+    }
+
+    let row = writeln!(
+        telemetry.flock,
+        "{tick},{:.4},{},{:.4},{:.4},{:.4},{:.4},{:.2},{:.2},{:.2}",
+        time.elapsed_seconds(), stats.boids, stats.mean_speed, stats.mean_neighbors,
+        stats.polarization, (milling / n).abs(), center.x, center.y, (spread / n).sqrt(),
+    );
+
+    let rows = match &mut telemetry.boids {
+        Some((file, interval)) if tick.is_multiple_of(*interval as u64) => boids.iter()
+            .try_for_each(|(boid, transform, velocity, species)| writeln!(
+                file,
+                "{tick},{},{},{:.2},{:.2},{:.3},{:.3}",
+                boid.to_bits(), species.0, transform.translation.x, transform.translation.y,
+                velocity.0.x, velocity.0.y,
+            )),
+        _ => Ok(()),
+    };
+
+    if let Err(err) = row.and(rows) {
+        error!("failed to write telemetry: {err}");
+    }
+}
+
+// Writes are buffered, so flush them out once the app is closing
+fn flush_telemetry(mut telemetry: ResMut<Telemetry>, mut exit: EventReader<AppExit>) {
+    if exit.read().next().is_none() {
+        return;
+    }
+
+    let boids = telemetry.boids.as_mut().map_or(Ok(()), |(file, _)| file.flush());
+    if let Err(err) = telemetry.flock.flush().and(boids) {
+        error!("failed to flush telemetry: {err}");
+    }
+}
@@ -0,0 +1,283 @@
+use rand::prelude::*;
+use halton::Sequence;
+use bevy::{
+    input::mouse::{MouseMotion, MouseScrollUnit, MouseWheel},
+    prelude::*,
+    render::{mesh::*, render_asset::RenderAssetUsages},
+};
+use bevy_spatial::{
+    kdtree::KDTree3,
+    SpatialAccess,
+};
+
+use crate::{
+    boundary::box_turn,
+    flocking::{flocking_rules, limit_travel, limit_velocity, Flight, Neighbor},
+    input::{Action, Actions},
+    interpolation::track_transforms,
+    rng::SimRng,
+    settings::BoidSettings,
+    simulation::simulation_running,
+    spatial::rebuild_tree3,
+};
+
+// Distinct boid colors, evenly spaced hues
+const PALETTE_SIZE: usize = 8;
+
+// Size of the boxed volume the 3D flock flies in
+const WORLD_BOUNDS: Vec3 = Vec3::new(800., 400., 400.);
+
+// Radians the camera swings per unit of mouse motion, and how close and far it can get
+const ORBIT_SPEED: f32 = 0.005;
+const MIN_DISTANCE: f32 = 100.;
+const MAX_DISTANCE: f32 = 3000.;
+
+// Boids flying in a 3D volume, tracked in a KDTree3. Dragging with the left button orbits the
+// camera round the volume, with the middle button pans it, and the mouse wheel zooms
+pub struct Boids3dPlugin;
+
+impl Plugin for Boids3dPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<KDTree3<SpatialEntity3d>>()
+        .register_type::<SpatialEntity3d>()
+        .register_type::<Velocity3d>()
+        .register_type::<Acceleration3d>()
+        .register_type::<Travel3d>()
+        .add_systems(Startup, setup)
+        .add_systems(FixedUpdate, (
+            rebuild_tree3::<SpatialEntity3d>,
+            flocking_system,
+            velocity_system,
+            movement_system,
+        ).chain().run_if(simulation_running))
+        .add_systems(PostUpdate, track_transforms::<Velocity3d>)
+        .add_systems(Update, (orbit_camera, draw_boundary_gizmos));
+    }
+}
+
+// Marker for entities tracked by KDTree3
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
+struct SpatialEntity3d;
+
+#[derive(Component, Reflect)]
+#[reflect(Component)]
+struct Velocity3d(Vec3);
+
+// Change of velocity for the coming tick, consumed by velocity_system
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
+struct Acceleration3d(Vec3);
+
+// How far velocity_system's integrator moved the boid over the tick, for movement_system to apply
+#[derive(Component, Default, Reflect)]
+#[reflect(Component)]
+struct Travel3d(Option<Vec3>);
+
+// A camera circling `focus` at `distance`, `yaw` round the vertical and `pitch` above the level
+#[derive(Component)]
+struct OrbitCamera {
+    focus: Vec3,
+    yaw: f32,
+    pitch: f32,
+    distance: f32,
+}
+
+impl OrbitCamera {
+    fn transform(&self) -> Transform {
+        let rotation = Quat::from_euler(EulerRot::YXZ, self.yaw, -self.pitch, 0.0);
+        Transform::from_translation(self.focus + rotation * Vec3::Z * self.distance)
+            .looking_at(self.focus, Vec3::Y)
+    }
+}
+
+fn boid_mesh() -> Mesh {
+    // Dart pointing down +X, matching the 2D boid's heading
+    let mut mesh = Mesh::new(PrimitiveTopology::TriangleList, RenderAssetUsages::default())
+        .with_inserted_attribute(Mesh::ATTRIBUTE_POSITION, vec![
+            [1.0, 0.0, 0.0],
+            [-0.5, 0.5, 0.0],
+            [-0.5, -0.25, 0.4],
+            [-0.5, -0.25, -0.4],
+        ])
+        .with_inserted_indices(Indices::U32(vec![
+            0, 1, 2,
+            0, 2, 3,
+            0, 3, 1,
+            1, 3, 2,
+        ]));
+
+    // Flat normals need one vertex per face corner
+    mesh.duplicate_vertices();
+    mesh.compute_flat_normals();
+    mesh
+}
+
+fn setup(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut rng: ResMut<SimRng>,
+    settings: Res<BoidSettings>,
+) {
+    // Slightly above the volume, looking along it
+    let orbit = OrbitCamera { focus: Vec3::ZERO, yaw: 0.0, pitch: (150_f32 / 900.).atan(), distance: 912. };
+    commands.spawn((Camera3dBundle { transform: orbit.transform(), ..default() }, orbit));
+
+    commands.spawn(DirectionalLightBundle {
+        transform: Transform::from_xyz(1.0, 2.0, 1.5).looking_at(Vec3::ZERO, Vec3::Y),
+        ..default()
+    });
+
+    // Shared so every boid draws from the same mesh and a handful of materials
+    let mesh = meshes.add(boid_mesh());
+    let palette = (0..PALETTE_SIZE)
+        .map(|i| materials.add(Color::hsl(360. * i as f32 / PALETTE_SIZE as f32, 0.8, 0.7)))
+        .collect::<Vec<_>>();
+
+    // Halton sequence for Boid spawns
+    let seq = Sequence::new(2).zip(Sequence::new(3)).zip(Sequence::new(5))
+        .take(settings.count);
+
+    for ((x, y), z) in seq {
+        let spawn = (Vec3::new(x as f32, y as f32, z as f32) - 0.5) * WORLD_BOUNDS;
+
+        let velocity = Velocity3d(Vec3::new(rng.random_range(-1.0..1.0),
+                                            rng.random_range(-1.0..1.0),
+                                            rng.random_range(-1.0..1.0)) * settings.min_speed);
+
+        commands.spawn((
+            PbrBundle {
+                mesh: mesh.clone(),
+                // Random color for each boid
+                material: palette[rng.random_range(0..PALETTE_SIZE)].clone(),
+                transform: Transform::from_translation(spawn)
+                    .with_scale(Vec3::splat(settings.size)),
+                ..default()
+            },
+            velocity,
+            Acceleration3d::default(),
+            Travel3d::default(),
+            SpatialEntity3d,
+        ));
+    }
+}
+
+fn orbit_camera(
+    actions: Actions,
+    mut motion: EventReader<MouseMotion>,
+    mut wheel: EventReader<MouseWheel>,
+    mut camera: Query<(&mut OrbitCamera, &mut Transform)>,
+) {
+    let moved = motion.read().map(|event| event.delta).sum::<Vec2>();
+    let scroll = wheel.read()
+        .map(|event| match event.unit {
+            MouseScrollUnit::Line => event.y,
+            MouseScrollUnit::Pixel => event.y / 40.0,
+        })
+        .sum::<f32>();
+
+    for (mut orbit, mut transform) in camera.iter_mut() {
+        let orbiting = actions.pressed(Action::Select) && moved != Vec2::ZERO;
+        let panning = actions.pressed(Action::Place) && moved != Vec2::ZERO;
+        if !orbiting && !panning && scroll == 0.0 {
+            continue;
+        }
+
+        if orbiting {
+            orbit.yaw -= moved.x * ORBIT_SPEED;
+            // Short of straight up or down, where the view would flip over
+            orbit.pitch = (orbit.pitch + moved.y * ORBIT_SPEED).clamp(-1.5, 1.5);
+        }
+        if panning {
+            // Further out, the same drag covers more of the world
+            let scale = orbit.distance * ORBIT_SPEED * 0.3;
+            orbit.focus += (transform.left() * moved.x + transform.up() * moved.y) * scale;
+        }
+        orbit.distance = (orbit.distance * 0.9_f32.powf(scroll)).clamp(MIN_DISTANCE, MAX_DISTANCE);
+
+        *transform = orbit.transform();
+    }
+}
+
+fn draw_boundary_gizmos(
+    mut gizmos: Gizmos,
+    settings: Res<BoidSettings>,
+) {
+    gizmos.cuboid(
+        Transform::from_scale(WORLD_BOUNDS - Vec3::splat(settings.boundary_size)),
+        Color::GRAY,
+    );
+}
+
+fn flocking_dv(
+    kdtree: &KDTree3<SpatialEntity3d>,
+    boid_query: &Query<(Entity, &Velocity3d, &Transform), With<SpatialEntity3d>>,
+    settings: &BoidSettings,
+    boid: &Entity,
+    t0: &Transform,
+) -> Vec3 {
+    let neighbors = kdtree
+        .k_nearest_neighbour(t0.translation, settings.neighbor_cap)
+        .into_iter()
+        .filter_map(|(_, entity)| boid_query.get(entity?).ok())
+        // Don't evaluate against itself
+        .filter(|(other, _, _)| other != boid)
+        .map(|(_, v1, t1)| Neighbor {
+            offset: t1.translation - t0.translation,
+            velocity: v1.0,
+        });
+
+    flocking_rules(settings, t0.rotation * Vec3::X, neighbors).total()
+}
+
+fn flocking_system(
+    boid_query: Query<(Entity, &Velocity3d, &Transform), With<SpatialEntity3d>>,
+    mut accelerations: Query<(Entity, &Transform, &mut Acceleration3d), With<SpatialEntity3d>>,
+    kdtree: Res<KDTree3<SpatialEntity3d>>,
+    settings: Res<BoidSettings>,
+) {
+    accelerations.par_iter_mut().for_each(|(boid, t0, mut acceleration)| {
+        acceleration.0 = flocking_dv(&kdtree, &boid_query, &settings, &boid, t0);
+    });
+}
+
+fn velocity_system(
+    mut boids: Query<(&mut Velocity3d, &mut Acceleration3d, &mut Travel3d, &Transform)>,
+    time: Res<Time>,
+    settings: Res<BoidSettings>,
+) {
+    let dt = time.delta_seconds();
+    let max_speed = settings.max_speed;
+
+    boids.par_iter_mut().for_each(|(mut velocity, mut acceleration, mut travel, transform)| {
+        let mut force = std::mem::take(&mut acceleration.0);
+
+        // Steer back into the box as the 2D flock does its own
+        let turn = box_turn(transform.translation.to_array(), WORLD_BOUNDS.to_array(), settings.boundary_size);
+        force += Vec3::from_array(turn) * settings.turn_factor;
+
+        let before = velocity.0;
+        let flight = Flight { force, mass: 1.0, max_speed, drag: settings.drag };
+        let (after, travelled) = flight.step(settings.integrator, before, dt);
+
+        // Limited and moved along the same as in 2D
+        velocity.0 = limit_velocity(&settings, before, after, max_speed, dt);
+        let top_speed = (settings.drag <= 0.0).then_some(max_speed);
+        travel.0 = Some(limit_travel(travelled, after, velocity.0, top_speed, dt));
+    });
+}
+
+fn movement_system(
+    mut query: Query<(&Velocity3d, &mut Travel3d, &mut Transform)>,
+    time: Res<Time>,
+) {
+    let dt = time.delta_seconds();
+
+    query.par_iter_mut().for_each(|(velocity, mut travel, mut transform)| {
+        if let Some(heading) = velocity.0.try_normalize() {
+            transform.rotation = Quat::from_rotation_arc(Vec3::X, heading);
+        }
+        transform.translation += travel.0.take().unwrap_or(velocity.0 * dt);
+    });
+}
@@ -0,0 +1,83 @@
+use std::collections::VecDeque;
+use bevy::{math::Vec3Swizzles, prelude::*};
+
+use crate::{input::{Action, Actions}, settings::BoidSettings, BoidColor, BoidSet, SpatialEntity};
+
+// Recent positions of a boid, oldest first
+#[derive(Component, Default)]
+pub struct Trail(pub VecDeque<Vec2>);
+
+#[derive(Resource, Default)]
+pub struct TrailsEnabled(pub bool);
+
+// `T` toggles fading trails behind every boid
+pub struct TrailPlugin;
+
+impl Plugin for TrailPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TrailsEnabled>()
+            .add_systems(FixedUpdate, record_trails.after(BoidSet::Movement))
+            .add_systems(Update, (toggle_trails, draw_trails));
+    }
+}
+
+fn toggle_trails(
+    mut commands: Commands,
+    actions: Actions,
+    mut enabled: ResMut<TrailsEnabled>,
+    with_trail: Query<Entity, With<Trail>>,
+    without_trail: Query<Entity, (With<SpatialEntity>, Without<Trail>)>,
+) {
+    if actions.just_pressed(Action::Trails) {
+        enabled.0 = !enabled.0;
+
+        if !enabled.0 {
+            for boid in with_trail.iter() {
+                commands.entity(boid).remove::<Trail>();
+            }
+        }
+    }
+
+    // Also picks up boids spawned while trails are on
+    if enabled.0 {
+        for boid in without_trail.iter() {
+            commands.entity(boid).insert(Trail::default());
+        }
+    }
+}
+
+fn record_trails(
+    mut trails: Query<(&mut Trail, &Transform)>,
+    time: Res<Time>,
+    settings: Res<BoidSettings>,
+) {
+    let max_step = settings.max_speed * time.delta_seconds();
+
+    for (mut trail, transform) in trails.iter_mut() {
+        let position = transform.translation.xy();
+
+        // A jump longer than a tick's travel means the boid wrapped or was moved, start over
+        if trail.0.back().is_some_and(|last| last.distance(position) > max_step * 2.0) {
+            trail.0.clear();
+        }
+
+        trail.0.push_back(position);
+        while trail.0.len() > settings.trail_length {
+            trail.0.pop_front();
+        }
+    }
+}
+
+fn draw_trails(
+    trails: Query<(&Trail, &BoidColor)>,
+    mut gizmos: Gizmos,
+) {
+    for (trail, BoidColor(color)) in trails.iter() {
+        let len = trail.0.len() as f32;
+
+        // Fade in from transparent at the tail to the boid's color at its head
+        gizmos.linestrip_gradient_2d(trail.0.iter().enumerate().map(|(i, point)| {
+            (*point, color.with_a((i + 1) as f32 / len))
+        }));
+    }
+}
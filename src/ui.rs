@@ -0,0 +1,228 @@
+use bevy::prelude::*;
+use bevy_egui::{egui, EguiContexts, EguiPlugin};
+
+use crate::{
+    preset_files::{PresetFile, PRESET_DIR},
+    settings::{BoidRender, BoidSettings, BoundaryMode, ColorMode, Falloff, Integrator, NeighborQuery, SpatialIndex},
+    simulation::{SimulationState, MAX_TIME_SCALE, MIN_TIME_SCALE},
+    species::SpeciesInteractions,
+};
+
+// Live tuning panel for the flocking parameters, and a list of preset files to save it to and
+// load it from
+pub struct TuningPanelPlugin;
+
+impl Plugin for TuningPanelPlugin {
+    fn build(&self, app: &mut App) {
+        // The inspector may have added it already
+        if !app.is_plugin_added::<EguiPlugin>() {
+            app.add_plugins(EguiPlugin);
+        }
+        app.add_systems(Update, (tuning_panel, preset_manager));
+    }
+}
+
+fn tuning_panel(
+    mut contexts: EguiContexts,
+    mut settings: ResMut<BoidSettings>,
+    mut simulation: ResMut<SimulationState>,
+) {
+    // Edit a copy so change detection only fires when a value actually moves
+    let mut edited = settings.clone();
+    let mut fov_deg = edited.fov.to_degrees();
+    let mut time_scale = simulation.time_scale;
+    let mut paused = simulation.paused;
+    let mut step = false;
+
+    egui::Window::new("Flocking").show(contexts.ctx_mut(), |ui| {
+        ui.horizontal(|ui| {
+            ui.toggle_value(&mut paused, "pause");
+            step = ui.add_enabled(paused, egui::Button::new("step")).clicked();
+        });
+        ui.add(egui::Slider::new(&mut time_scale, MIN_TIME_SCALE..=MAX_TIME_SCALE)
+            .logarithmic(true)
+            .text("time scale"));
+
+        ui.separator();
+        ui.label("Rules");
+        ui.add(egui::Slider::new(&mut edited.center_factor, 0.0..=36.0).text("cohesion"));
+        ui.add(egui::Slider::new(&mut edited.matching_factor, 0.0..=12.0).text("alignment"));
+        ui.add(egui::Slider::new(&mut edited.avoid_factor, 0.0..=720.0).text("separation"));
+        ui.add(egui::Slider::new(&mut edited.turn_factor, 0.0..=3600.0).text("turn"));
+        ui.add(egui::Slider::new(&mut edited.mouse_chase_factor, 0.0..=36.0).text("mouse chase"));
+        ui.add(egui::Slider::new(&mut edited.mouse_repel_factor, 0.0..=6000.0).text("mouse repel"));
+        ui.add(egui::Slider::new(&mut edited.waypoint_seek_factor, 0.0..=36.0).text("waypoint seek"));
+        ui.add(egui::Slider::new(&mut edited.leader_follow_factor, 0.0..=36.0).text("leader following"));
+        ui.add(egui::Slider::new(&mut edited.wander_factor, 0.0..=240.0).text("wander"));
+        ui.add(egui::Slider::new(&mut edited.obstacle_avoid_factor, 0.0..=7200.0).text("obstacle avoidance"));
+        ui.add(egui::Slider::new(&mut edited.flow_factor, 0.0..=1200.0).text("flow field"));
+        ui.add(egui::Slider::new(&mut edited.wind_factor, 0.0..=600.0).text("wind"));
+        ui.add(egui::Slider::new(&mut edited.wind_scale, 50.0..=2000.0).text("wind scale"));
+        ui.add(egui::Slider::new(&mut edited.migration_factor, 0.0..=240.0).text("migration"));
+        ui.add(egui::Slider::new(&mut edited.migration_period, 30.0..=1800.0).text("migration period (s)"));
+        ui.add(egui::Slider::new(&mut edited.forage_factor, 0.0..=2400.0).text("foraging"));
+
+        ui.separator();
+        ui.label("Falloff");
+        falloff_radios(ui, "cohesion", &mut edited.cohesion_falloff);
+        falloff_radios(ui, "alignment", &mut edited.alignment_falloff);
+        falloff_radios(ui, "separation", &mut edited.separation_falloff);
+
+        ui.separator();
+        ui.label("Perception");
+        ui.add(egui::Slider::new(&mut edited.vis_range, 0.0..=200.0).text("vision range"));
+        ui.add(egui::Slider::new(&mut edited.prot_range, 0.0..=50.0).text("protected range"));
+        ui.add(egui::Slider::new(&mut fov_deg, 0.0..=180.0).text("fov (deg)"));
+        ui.add(egui::Slider::new(&mut edited.perception_position_noise, 0.0..=20.0).text("position noise"));
+        ui.add(egui::Slider::new(&mut edited.perception_velocity_noise, 0.0..=100.0).text("velocity noise"));
+        ui.add(egui::Slider::new(&mut edited.perception_miss_chance, 0.0..=1.0).text("miss chance"));
+        ui.add(egui::Slider::new(&mut edited.obstacle_range, 0.0..=100.0).text("obstacle range"));
+        ui.checkbox(&mut edited.line_of_sight, "obstacles block sight");
+
+        ui.separator();
+        ui.label("Motion");
+        ui.add(egui::Slider::new(&mut edited.min_speed, 0.0..=600.0).text("min speed"));
+        ui.add(egui::Slider::new(&mut edited.max_speed, 0.0..=600.0).text("max speed"));
+        ui.add(egui::Slider::new(&mut edited.max_turn_rate, 0.0..=1440.0).text("max turn rate (deg/s)"));
+        ui.add(egui::Slider::new(&mut edited.trait_variation, 0.0..=0.5).text("trait variation"));
+        ui.add(egui::Slider::new(&mut edited.lifespan, 0.0..=600.0).text("lifespan (0 forever)"));
+        ui.add(egui::Slider::new(&mut edited.breed_chance, 0.0..=1.0).text("breed chance"));
+        ui.add(egui::Slider::new(&mut edited.mutation, 0.0..=0.5).text("mutation"));
+
+        ui.add(egui::Slider::new(&mut edited.predator_max_speed, 0.0..=600.0).text("predator max speed"));
+
+        ui.separator();
+        ui.label("Predators");
+        ui.add(egui::Slider::new(&mut edited.predator_chase_factor, 0.0..=36.0).text("chase"));
+        ui.add(egui::Slider::new(&mut edited.flee_factor, 0.0..=3600.0).text("flee"));
+        ui.add(egui::Slider::new(&mut edited.species_avoid_factor, 0.0..=1440.0).text("avoid species"));
+        ui.add(egui::Slider::new(&mut edited.species_chase_factor, 0.0..=36.0).text("chase species"));
+
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.label("Boundary");
+            ui.radio_value(&mut edited.boundary_mode, BoundaryMode::Steer, "steer");
+            ui.radio_value(&mut edited.boundary_mode, BoundaryMode::Bounce, "bounce");
+            ui.radio_value(&mut edited.boundary_mode, BoundaryMode::Wrap, "wrap");
+            ui.radio_value(&mut edited.boundary_mode, BoundaryMode::Kill, "kill");
+        });
+        ui.horizontal(|ui| {
+            ui.label("Render");
+            ui.radio_value(&mut edited.render, BoidRender::Mesh, "mesh");
+            ui.radio_value(&mut edited.render, BoidRender::Sprite, "sprite");
+        });
+        ui.horizontal(|ui| {
+            ui.label("Color");
+            ui.radio_value(&mut edited.color_mode, ColorMode::Species, "species");
+            ui.radio_value(&mut edited.color_mode, ColorMode::Heading, "heading");
+            ui.radio_value(&mut edited.color_mode, ColorMode::Speed, "speed");
+            ui.radio_value(&mut edited.color_mode, ColorMode::Cluster, "cluster");
+        });
+        ui.horizontal(|ui| {
+            ui.label("Index");
+            ui.radio_value(&mut edited.spatial_index, SpatialIndex::KdTree, "kd-tree");
+            ui.radio_value(&mut edited.spatial_index, SpatialIndex::Flat, "flat");
+            ui.radio_value(&mut edited.spatial_index, SpatialIndex::Grid, "grid");
+        });
+        ui.checkbox(&mut edited.log_index_timings, "time index queries");
+        ui.horizontal(|ui| {
+            ui.label("Integrator");
+            ui.radio_value(&mut edited.integrator, Integrator::Euler, "Euler");
+            ui.radio_value(&mut edited.integrator, Integrator::SemiImplicit, "semi-implicit");
+            ui.radio_value(&mut edited.integrator, Integrator::Rk4, "RK4");
+        });
+        ui.horizontal(|ui| {
+            ui.label("Query");
+            ui.radio_value(&mut edited.neighbor_query, NeighborQuery::Nearest, "nearest");
+            ui.radio_value(&mut edited.neighbor_query, NeighborQuery::Radius, "radius");
+            ui.radio_value(&mut edited.neighbor_query, NeighborQuery::Hybrid, "hybrid");
+        });
+
+        ui.separator();
+        ui.add(egui::Slider::new(&mut edited.count, 0..=4096).text("boids"));
+        ui.add(egui::Slider::new(&mut edited.species_count, 1..=8).text("species"));
+        ui.add(egui::Slider::new(&mut edited.trail_length, 2..=200).text("trail length"));
+    });
+
+    edited.fov = fov_deg.to_radians();
+    edited.max_speed = edited.max_speed.max(edited.min_speed);
+
+    settings.set_if_neq(edited);
+
+    if time_scale != simulation.time_scale {
+        simulation.time_scale = time_scale;
+    }
+    if paused != simulation.paused {
+        simulation.paused = paused;
+    }
+    if step {
+        simulation.step = true;
+    }
+}
+
+// What the preset manager shows between frames, the file list only re-read when it might change
+#[derive(Default)]
+struct PresetManager {
+    name: String,
+    presets: Option<Vec<String>>,
+}
+
+fn preset_manager(
+    mut contexts: EguiContexts,
+    mut manager: Local<PresetManager>,
+    mut settings: ResMut<BoidSettings>,
+    mut species: ResMut<SpeciesInteractions>,
+) {
+    let PresetManager { name, presets } = &mut *manager;
+    let mut save = false;
+    let mut load = None;
+
+    egui::Window::new("Presets").default_open(false).show(contexts.ctx_mut(), |ui| {
+        ui.horizontal(|ui| {
+            ui.text_edit_singleline(name);
+            save = ui.add_enabled(!name.trim().is_empty(), egui::Button::new("save")).clicked();
+        });
+
+        ui.separator();
+        let listed = presets.get_or_insert_with(PresetFile::list);
+        if listed.is_empty() {
+            ui.label(format!("no presets in {PRESET_DIR}/ yet"));
+        }
+        for preset in listed.iter() {
+            if ui.button(preset).clicked() {
+                load = Some(preset.clone());
+            }
+        }
+        if ui.small_button("refresh").clicked() {
+            *presets = None;
+        }
+    });
+
+    if save {
+        match PresetFile::current(&settings, &species).save(name) {
+            Ok(path) => info!("saved preset to {}", path.display()),
+            Err(err) => error!("failed to save preset {name:?}: {err}"),
+        }
+        *presets = None;
+    }
+    if let Some(preset) = load {
+        match PresetFile::load(&preset) {
+            Ok(file) => {
+                file.apply(&mut settings, &mut species);
+                info!("loaded preset {preset:?}");
+                *name = preset;
+            }
+            Err(err) => error!("failed to load preset {preset:?}: {err}"),
+        }
+    }
+}
+
+fn falloff_radios(ui: &mut egui::Ui, rule: &str, falloff: &mut Falloff) {
+    ui.horizontal(|ui| {
+        ui.label(rule);
+        ui.radio_value(falloff, Falloff::Constant, "constant");
+        ui.radio_value(falloff, Falloff::Linear, "linear");
+        ui.radio_value(falloff, Falloff::InverseSquare, "inverse square");
+        ui.radio_value(falloff, Falloff::Smoothstep, "smoothstep");
+    });
+}
@@ -0,0 +1,105 @@
+use std::borrow::Cow;
+use bevy::prelude::*;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::settings::BoidSettings;
+
+// Per-boid perception and speed, overriding the shared settings
+#[derive(Component, Clone, Copy, Debug, PartialEq, Serialize, Deserialize, Reflect)]
+#[reflect(Component)]
+pub struct BoidTraits {
+    pub vis_range: f32,
+    pub fov: f32,
+    pub prot_range: f32,
+    pub max_speed: f32,
+}
+
+impl BoidTraits {
+    // Each trait within `trait_variation` of the settings, as a fraction either way
+    pub fn random(rng: &mut impl Rng, settings: &BoidSettings) -> Self {
+        let variation = settings.trait_variation.clamp(0.0, 1.0);
+        let mut vary = |value: f32| value * (1.0 + variation * rng.random_range(-1.0..=1.0));
+
+        Self {
+            vis_range: vary(settings.vis_range),
+            fov: vary(settings.fov),
+            prot_range: vary(settings.prot_range),
+            // Never slower than the minimum
+            max_speed: vary(settings.max_speed).max(settings.min_speed),
+        }
+    }
+
+    // Halfway between two parents', each trait then nudged by up to `mutation` either way; a
+    // parent without traits passes on the settings' own
+    pub fn inherit(rng: &mut impl Rng, settings: &BoidSettings, a: Option<&Self>, b: Option<&Self>) -> Self {
+        let uniform = Self {
+            vis_range: settings.vis_range,
+            fov: settings.fov,
+            prot_range: settings.prot_range,
+            max_speed: settings.max_speed,
+        };
+        let (a, b) = (a.unwrap_or(&uniform), b.unwrap_or(&uniform));
+        let mutation = settings.mutation.clamp(0.0, 1.0);
+        let mut blend = |a: f32, b: f32| (a + b) / 2.0 * (1.0 + mutation * rng.random_range(-1.0..=1.0));
+
+        Self {
+            vis_range: blend(a.vis_range, b.vis_range),
+            fov: blend(a.fov, b.fov),
+            prot_range: blend(a.prot_range, b.prot_range),
+            max_speed: blend(a.max_speed, b.max_speed).max(settings.min_speed),
+        }
+    }
+
+    pub fn apply(&self, settings: &BoidSettings) -> BoidSettings {
+        BoidSettings {
+            vis_range: self.vis_range,
+            fov: self.fov,
+            prot_range: self.prot_range,
+            max_speed: self.max_speed,
+            ..settings.clone()
+        }
+    }
+}
+
+// How heavy a boid is, 1 for an ordinary one. Steering pushes heavier boids around less, and
+// they're drawn bigger and keep others further off in proportion to their size
+#[derive(Component, Clone, Copy, Debug, PartialEq, Serialize, Deserialize, Reflect)]
+#[reflect(Component)]
+pub struct Mass(pub f32);
+
+impl Mass {
+    // Within `mass_variation` of 1, as a fraction either way
+    pub fn random(rng: &mut impl Rng, settings: &BoidSettings) -> Self {
+        let variation = settings.mass_variation.clamp(0.0, 0.9);
+        Self(1.0 + variation * rng.random_range(-1.0..=1.0))
+    }
+
+    // Halfway between two parents' masses, nudged by up to `mutation` either way
+    pub fn inherit(rng: &mut impl Rng, settings: &BoidSettings, a: Option<&Self>, b: Option<&Self>) -> Self {
+        let mass = (a.map_or(1.0, |a| a.0) + b.map_or(1.0, |b| b.0)) / 2.0;
+        let mutation = settings.mutation.clamp(0.0, 1.0);
+        Self((mass * (1.0 + mutation * rng.random_range(-1.0..=1.0))).max(0.1))
+    }
+
+    // Length relative to an ordinary boid, its area growing with its mass
+    pub fn scale(&self) -> f32 {
+        self.0.max(f32::EPSILON).sqrt()
+    }
+}
+
+// Settings for a boid that may carry its own traits and mass
+pub fn with_traits<'a>(
+    settings: &'a BoidSettings,
+    traits: Option<&BoidTraits>,
+    mass: Option<&Mass>,
+) -> Cow<'a, BoidSettings> {
+    let mut settings = match traits {
+        Some(traits) => Cow::Owned(traits.apply(settings)),
+        None => Cow::Borrowed(settings),
+    };
+    if let Some(mass) = mass {
+        settings.to_mut().prot_range *= mass.scale();
+    }
+    settings
+}
@@ -0,0 +1,84 @@
+use bevy::{math::Vec3Swizzles, prelude::*};
+
+use crate::{input::{Action, Actions}, settings::BoidSettings, CursorPosition, SpatialEntity};
+
+// Route the flock patrols in order, looping back to the first point after the last;
+// while it has points the flock seeks the current one instead of chasing the cursor
+#[derive(Resource, Clone, Debug, Default, Reflect)]
+#[reflect(Resource)]
+pub struct Waypoints {
+    pub points: Vec<Vec2>,
+    pub current: usize,
+}
+
+impl Waypoints {
+    pub fn target(&self) -> Option<Vec2> {
+        self.points.get(self.current).copied()
+    }
+
+    // Steering towards the current point, zero without a route
+    pub fn seek(&self, settings: &BoidSettings, position: Vec2) -> Vec2 {
+        self.target().map_or(Vec2::ZERO, |target| (target - position) * settings.waypoint_seek_factor)
+    }
+}
+
+// Move on once the flock's centroid reaches the current point
+pub fn advance_waypoints(
+    mut waypoints: ResMut<Waypoints>,
+    boids: Query<&Transform, With<SpatialEntity>>,
+    settings: Res<BoidSettings>,
+) {
+    let Some(target) = waypoints.target() else {
+        return;
+    };
+    let count = boids.iter().len();
+    if count == 0 {
+        return;
+    }
+
+    let centroid = boids.iter().map(|transform| transform.translation.xy()).sum::<Vec2>() / count as f32;
+    if centroid.distance(target) <= settings.waypoint_radius {
+        waypoints.current = (waypoints.current + 1) % waypoints.points.len();
+    }
+}
+
+// Ctrl + left click appends a waypoint, `Backspace` clears the route
+pub struct WaypointPlugin;
+
+impl Plugin for WaypointPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, (place_waypoints, draw_waypoints).chain());
+    }
+}
+
+fn place_waypoints(
+    actions: Actions,
+    cursor: Res<CursorPosition>,
+    mut waypoints: ResMut<Waypoints>,
+) {
+    if actions.just_pressed(Action::ClearRoute) {
+        *waypoints = Waypoints::default();
+    }
+
+    let ctrl = actions.pressed(Action::Route);
+    if let (true, Some(position)) = (ctrl && actions.just_pressed(Action::Select), cursor.0) {
+        waypoints.points.push(position);
+    }
+}
+
+fn draw_waypoints(waypoints: Res<Waypoints>, settings: Res<BoidSettings>, mut gizmos: Gizmos) {
+    if waypoints.points.is_empty() {
+        return;
+    }
+
+    let route = waypoints.points.iter().chain(waypoints.points.first()).copied();
+    gizmos.linestrip_2d(route, Color::TEAL.with_a(0.4));
+
+    for (i, point) in waypoints.points.iter().enumerate() {
+        if i == waypoints.current {
+            gizmos.circle_2d(*point, settings.waypoint_radius, Color::TEAL);
+        } else {
+            gizmos.circle_2d(*point, 4.0, Color::TEAL);
+        }
+    }
+}
@@ -0,0 +1,18 @@
+use bevy_spatial_boids::{BoidSettings, BoidsSimulation};
+
+// The flock as another crate sees it, through the library alone
+#[test]
+fn runs_headless_from_outside_the_crate() {
+    let settings = BoidSettings { count: 100, ..Default::default() };
+    let run = |seed| {
+        let mut sim = BoidsSimulation::new(settings.clone(), seed);
+        sim.step(60);
+        (sim.positions(), sim.velocities())
+    };
+
+    let (positions, velocities) = run(1);
+    assert_eq!(positions.len(), 100);
+    assert_eq!(velocities.len(), 100);
+    assert!(positions.iter().chain(&velocities).all(|v| v.is_finite()));
+    assert_eq!(run(1), (positions, velocities), "equal seeds run alike");
+}
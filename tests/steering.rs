@@ -0,0 +1,29 @@
+use bevy::prelude::*;
+use bevy_spatial_boids::{
+    steering::{SteeringBehavior, SteeringBehaviors, SteeringContext},
+    BoidSettings,
+    BoidsSimulation,
+};
+
+// Hard east, whatever the flock is doing
+struct East;
+
+impl SteeringBehavior for East {
+    fn name(&self) -> &'static str {
+        "east"
+    }
+
+    fn steer(&self, _ctx: &SteeringContext) -> Vec2 {
+        Vec2::X * 5000.0
+    }
+}
+
+#[test]
+fn behaviors_registered_from_outside_steer_the_flock() {
+    let mut sim = BoidsSimulation::new(BoidSettings { count: 100, ..Default::default() }, 1);
+    sim.app().world.resource_mut::<SteeringBehaviors>().register(East, 1.0);
+    sim.step(30);
+
+    let heading = sim.velocities().iter().map(|velocity| velocity.normalize_or_zero()).sum::<Vec2>() / 100.0;
+    assert!(heading.x > 0.8, "{heading}");
+}